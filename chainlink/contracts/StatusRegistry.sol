@@ -24,9 +24,14 @@ contract StatusRegistry {
   StatusState.IssuerId public constant INVALID_ISSUER_ID = StatusState.IssuerId.wrap(0);
 
   event StatusUpdated(StatusState.IssuerId issuerId, StatusState.StatusType statusType);
+  event StatusRequestFailed(bytes32 requestId, StatusState.IssuerId issuerId, StatusState.StatusType statusType, bytes err);
 
   mapping(bytes32 => Request) public requests;
   mapping(StatusState.IssuerId => StatusState.Issuer) public issuers;
+  // The statuses below are keyed by IssuerId and type only, so every status list of an issuer is registered
+  // under its own IssuerId, with its url `<issuer url>?list=<list id>`. A url and mechanism, that is a list,
+  // belong to one IssuerId, which verifiers look up with getIssuerIdByList.
+  mapping(bytes32 => StatusState.IssuerId) public issuerIdsByList;
   mapping(StatusState.IssuerId => mapping(StatusState.StatusType => StatusState.BSLStatus)) public bslStatuses;
   mapping(StatusState.IssuerId => mapping(StatusState.StatusType => StatusState.BigBSLStatus)) public bbslStatuses;
   mapping(StatusState.IssuerId => mapping(StatusState.StatusType => StatusState.MTStatus[])) public mtStatuses;
@@ -44,6 +49,12 @@ contract StatusRegistry {
     // stateTransitionVerifier = IGroth16Verifier(stateTransitionVerifierAddress);
   }
 
+  // a request is fulfilled or failed only by the consumer it was sent to
+  modifier onlyConsumerOf(bytes32 requestId) {
+    if (msg.sender != address(oracleConsumers[requests[requestId].oracleType])) revert Errors.UnauthorizedCaller(msg.sender);
+    _;
+  }
+
   function setStateTransitionVerifierAddress(address verifierAddress) external {
     stateTransitionVerifier = IGroth16Verifier(verifierAddress);
   }
@@ -76,6 +87,21 @@ contract StatusRegistry {
     return issuer;
   }
 
+  function getListKey(string memory url, StatusState.StatusMechanism statusMechanism) internal pure returns (bytes32) {
+    return keccak256(abi.encode(url, statusMechanism));
+  }
+
+  function getIssuerIdByList(
+    string calldata url,
+    StatusState.StatusMechanism statusMechanism
+  ) external view returns (StatusState.IssuerId) {
+    StatusState.IssuerId issuerId = issuerIdsByList[getListKey(url, statusMechanism)];
+    if (StatusState.IssuerId.unwrap(issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
+      revert Errors.ListNotFound(url, statusMechanism);
+
+    return issuerId;
+  }
+
   function addIssuer(
     StatusState.IssuerId issuerId,
     string calldata url,
@@ -86,7 +112,19 @@ contract StatusRegistry {
 
     if (bytes(url).length == 0) revert Errors.InvalidUrl(url);
 
+    // the statuses of an IssuerId are those of one list, which cannot be moved to another IssuerId
+    bytes32 listKey = getListKey(url, statusMechanism);
+    StatusState.IssuerId listIssuerId = issuerIdsByList[listKey];
+    if (StatusState.IssuerId.unwrap(listIssuerId) != StatusState.IssuerId.unwrap(INVALID_ISSUER_ID) &&
+      StatusState.IssuerId.unwrap(listIssuerId) != StatusState.IssuerId.unwrap(issuerId))
+      revert Errors.ListAlreadyRegistered(url, listIssuerId);
+
+    StatusState.Issuer memory oldIssuer = issuers[issuerId];
+    if (bytes(oldIssuer.url).length != 0 && getListKey(oldIssuer.url, oldIssuer.statusMechanism) != listKey)
+      revert Errors.IssuerAlreadyExists(issuerId);
+
     issuers[issuerId] = StatusState.Issuer(url, statusMechanism);
+    issuerIdsByList[listKey] = issuerId;
 
     if (statusMechanism == StatusState.StatusMechanism.MerkleTree) {
       // console.log("StatusRegistry: addIssuer MT initial status");
@@ -281,7 +319,7 @@ contract StatusRegistry {
     bytes32 requestId,
     StatusState.StatusType statusType,
     StatusState.BSLStatus memory status
-  ) external {
+  ) external onlyConsumerOf(requestId) {
     Request memory request = requests[requestId];
     if (StatusState.IssuerId.unwrap(request.issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
       revert Errors.RequestNotFound(requestId);
@@ -297,7 +335,19 @@ contract StatusRegistry {
     emit StatusUpdated(request.issuerId, statusType);
   }
 
-  function fulfillBSLStatusWithProof(bytes32 requestId, StatusState.StatusType statusType, StatusState.BSLStatus memory status, uint256[8] memory proof) external {
+  function failRequest(
+    bytes32 requestId,
+    StatusState.StatusType statusType,
+    bytes memory err
+  ) external onlyConsumerOf(requestId) {
+    Request memory request = requests[requestId];
+    if (StatusState.IssuerId.unwrap(request.issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
+      revert Errors.RequestNotFound(requestId);
+
+    emit StatusRequestFailed(requestId, request.issuerId, statusType, err);
+  }
+
+  function fulfillBSLStatusWithProof(bytes32 requestId, StatusState.StatusType statusType, StatusState.BSLStatus memory status, uint256[8] memory proof) external onlyConsumerOf(requestId) {
     // console.log("StatusRegistry fulfillBSLStatusWithProof");
     Request memory request = requests[requestId];
     if (StatusState.IssuerId.unwrap(request.issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
@@ -333,7 +383,7 @@ contract StatusRegistry {
     bytes32 requestId,
     StatusState.StatusType statusType,
     StatusState.BigBSLStatus memory status
-  ) external {
+  ) external onlyConsumerOf(requestId) {
     // console.log("StatusRegistry: fulfillBigBSLStatus");
     Request memory request = requests[requestId];
     if (StatusState.IssuerId.unwrap(request.issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
@@ -359,7 +409,7 @@ contract StatusRegistry {
     StatusState.StatusType statusType,
     StatusState.BigBSLStatus memory status,
     uint256[8] memory proof
-  ) external {
+  ) external onlyConsumerOf(requestId) {
     // console.log("StatusRegistry: fulfillBigBSLStatusWithProof");
 
     Request memory request = requests[requestId];
@@ -397,7 +447,7 @@ contract StatusRegistry {
     StatusState.StatusType statusType,
     StatusState.MTStatus memory status,
     uint256[8] memory proof
-  ) external {
+  ) external onlyConsumerOf(requestId) {
     // console.log("StatusRegistry: fulfillMTStatus");
     Request memory request = requests[requestId];
     if (StatusState.IssuerId.unwrap(request.issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
@@ -429,7 +479,7 @@ contract StatusRegistry {
     uint32[10] memory times,
     uint256[10] memory roots,
     uint256[8] memory proof
-  ) external {
+  ) external onlyConsumerOf(requestId) {
     // console.log("StatusRegistry: fulfillMTStatusWithProof");
     Request memory request = requests[requestId];
     if (StatusState.IssuerId.unwrap(request.issuerId) == StatusState.IssuerId.unwrap(INVALID_ISSUER_ID))
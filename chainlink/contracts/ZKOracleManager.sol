@@ -38,10 +38,15 @@ contract ZKOracleManager {
   event RequestReceived(bytes32 requestId);
   event OracleAdded(Oracle oracle);
   event ResponseReceived(bytes32 requestId, bytes response, bytes err);
+  event RequestFailed(bytes32 requestId, bytes err);
 
   mapping(uint8 => Oracle) public oracles;
   uint8[] public oracleIds;
   Request[] public requests;
+  // a request is fulfilled once, by whichever aggregator gets there first
+  mapping(bytes32 => bool) public fulfilled;
+  // only registered oracles can fulfill or fail a request
+  mapping(address => bool) public isOracleAddress;
   uint8 public numAggregators;
   uint8 public currentAggregatorIndex;
   uint8 public numAgreements;
@@ -52,6 +57,11 @@ contract ZKOracleManager {
     numAgreements = _numAgreements;
   }
 
+  modifier onlyOracle() {
+    if (!isOracleAddress[msg.sender]) revert Errors.UnauthorizedCaller(msg.sender);
+    _;
+  }
+
   function getNumOracles() external view returns (uint256) {
     return oracleIds.length;
   }
@@ -70,6 +80,7 @@ contract ZKOracleManager {
       Oracle memory oracle = Oracle(oracleId, msg.sender, url, amount);
       oracles[oracleId] = oracle;
       oracleIds.push(oracleId);
+      isOracleAddress[msg.sender] = true;
 
       emit OracleAdded(oracle);
     }
@@ -141,43 +152,63 @@ contract ZKOracleManager {
     return requestId;
   }
 
-  function fulfillRequest(bytes32 requestId, ResponseType responseType, bytes memory response, bytes memory err) external {
+  function fulfillRequest(bytes32 requestId, ResponseType responseType, bytes memory response, bytes memory err) external onlyOracle {
+    fulfill(requestId, responseType, response, err);
+  }
+
+  function fulfillRequestWithError(bytes32 requestId, bytes memory err) external onlyOracle {
+    fulfill(requestId, ResponseType.LastStatus, "", err);
+  }
+
+  function fulfillRequestWithLastStatus(bytes32 requestId, bytes memory response, bytes memory err) external onlyOracle {
+    fulfill(requestId, ResponseType.LastStatus, response, err);
+  }
+
+  function fulfillRequestWithAllStatuses(bytes32 requestId, bytes memory response, bytes memory err) external onlyOracle {
+    fulfill(requestId, ResponseType.AllStatuses, response, err);
+  }
+
+  function fulfillRequestWithProof(bytes32 requestId, bytes memory response, bytes memory err) external onlyOracle {
+    fulfill(requestId, ResponseType.LastStatusWithProof, response, err);
+  }
+
+  // every entry point goes through here, so that a request is fulfilled once whichever one is called
+  function fulfill(bytes32 requestId, ResponseType responseType, bytes memory response, bytes memory err) internal {
     // console.log("ZKOracleManager fulfillRequest");
     // console.log(uint(responseType));
 
-    if (response.length == 0) {
+    if (response.length == 0 && err.length == 0) {
       revert Errors.WrongOracleExecution(err);
     }
 
-    if (responseType == ResponseType.LastStatus) {
-      this.fulfillRequestWithLastStatus(requestId, response, err);
+    if (fulfilled[requestId]) revert Errors.RequestAlreadyFulfilled(requestId);
+    fulfilled[requestId] = true;
+
+    if (err.length > 0) {
+      applyError(requestId, err);
+    } else if (responseType == ResponseType.LastStatus) {
+      applyLastStatus(requestId, response);
     } else if (responseType == ResponseType.AllStatuses) {
-      this.fulfillRequestWithAllStatuses(requestId, response, err);
+      applyAllStatuses(requestId, response);
     } else if (responseType == ResponseType.LastStatusWithProof) {
-      this.fulfillRequestWithProof(requestId, response, err);
+      applyProof(requestId, response);
     } else {
       revert Errors.UnsupportedResponseType(uint8(responseType));
     }
-    // Request memory request = this.getRequestById(requestId);
-
-    // if (request.statusMechanism == StatusState.StatusMechanism.BitStatusList) {
-    //   IStatusRegistry registry = IStatusRegistry(request.requesterAddress);
-    //   if (response.length > 0) {
-    //     (uint64 time, uint64 status) = abi.decode(response, (uint64, uint64));
-    //     emit StatusReceived(requestId, time, status);
-    //     registry.fulfillBSLStatus(requestId, request.statusType, StatusState.BSLStatus(time, status));
-    //   } else {
-    //     registry.fulfillBSLStatus(requestId, StatusState.StatusType.Invalid, StatusState.BSLStatus(0, 0));
-    //   }
-    // } else {
-    //   revert Errors.UnsupportedStatusMechanism(request.statusMechanism);
-    // }
 
     emit ResponseReceived(requestId, response, err);
   }
 
+  function applyError(bytes32 requestId, bytes memory err) internal {
+    // err is abi.encode(uint8 code, string reason) agreed by a quorum of oracles
+    Request memory request = this.getRequestById(requestId);
+    IStatusRegistry registry = IStatusRegistry(request.requesterAddress);
+    registry.failRequest(requestId, request.statusType, err);
 
-  function fulfillRequestWithLastStatus(bytes32 requestId, bytes memory response, bytes memory err) external {
+    emit RequestFailed(requestId, err);
+  }
+
+  function applyLastStatus(bytes32 requestId, bytes memory response) internal {
     // console.log("ZKOracleManager: fulfillRequestWithLastStatus");
     Request memory request = this.getRequestById(requestId);
     IStatusRegistry registry = IStatusRegistry(request.requesterAddress);
@@ -199,7 +230,7 @@ contract ZKOracleManager {
     }
   }
 
-  function fulfillRequestWithAllStatuses(bytes32 requestId, bytes memory response, bytes memory err) external {
+  function applyAllStatuses(bytes32 requestId, bytes memory response) internal {
     // console.log("ZKOracleManager: fulfillRequestWithAllStatuses");
     Request memory request = this.getRequestById(requestId);
     IStatusRegistry registry = IStatusRegistry(request.requesterAddress);
@@ -239,7 +270,7 @@ contract ZKOracleManager {
     uint256[10] roots;
   }
 
-  function fulfillRequestWithProof(bytes32 requestId, bytes memory response, bytes memory err) external {
+  function applyProof(bytes32 requestId, bytes memory response) internal {
     // console.log("ZKOracleManager: fulfillRequestWithProof");
     Request memory request = this.getRequestById(requestId);
     IStatusRegistry registry = IStatusRegistry(request.requesterAddress);
@@ -274,7 +305,5 @@ contract ZKOracleManager {
     } else {
       revert Errors.UnsupportedStatusMechanism(request.statusMechanism);
     }
-
-    emit ResponseReceived(requestId, response, err);
   }
 }
\ No newline at end of file
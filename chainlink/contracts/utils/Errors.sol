@@ -12,7 +12,11 @@ library Errors {
   error InvalidUrl(string url);
   error InvalidRequesterAddress(address requesterAddress);
   error IssuerNotFound(StatusState.IssuerId issuerId);
+  error IssuerAlreadyExists(StatusState.IssuerId issuerId);
+  error ListNotFound(string url, StatusState.StatusMechanism statusMechanism);
+  error ListAlreadyRegistered(string url, StatusState.IssuerId issuerId);
   error RequestNotFound(bytes32 requestId);
+  error RequestAlreadyFulfilled(bytes32 requestId);
   error InvalidBSLStatus(bytes32 requestId);
   error InvalidIssuerId(StatusState.IssuerId issuerId);
   error InvalidDeposit(uint64 token);
@@ -23,4 +27,5 @@ library Errors {
   error UnsupportedResponseType(uint8 responseType);
   error InvalidMTStatusTime(uint32 lastTime, uint32 time);
   error InvalidStatusVerification(uint8 issuerId, uint8 time);
+  error UnauthorizedCaller(address caller);
 }
@@ -54,4 +54,10 @@ interface IStatusRegistry {
     uint256[10] memory roots,
     uint256[8] memory proof
   ) external;
+
+  function failRequest(
+    bytes32 requestId,
+    StatusState.StatusType statusType,
+    bytes memory err
+  ) external;
 }
@@ -168,7 +168,7 @@ async function updateStatusesScenario(contracts: { [key: string]: Contract }, re
         request.requestId,
         ResponseType.AllStatuses,
         encodedStatuses,
-        "0x00"
+        "0x"
     );
 
     const receipt = await requestTx.wait(1);
@@ -185,7 +185,7 @@ async function updateLastStatusScenario(contracts: { [key: string]: Contract },
         request.requestId,
         ResponseType.LastStatus,
         encodedStatus,
-        "0x00"
+        "0x"
     );
 
     const receipt = await requestTx.wait(1);
@@ -244,7 +244,7 @@ async function updateLastStatusWithProof(contracts: { [key: string]: Contract },
         request.requestId,
         ResponseType.LastStatusWithProof,
         encodedStatus,
-        "0x00"
+        "0x"
     );
 
     const receipt = await requestTx.wait(1);
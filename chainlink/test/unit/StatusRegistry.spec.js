@@ -0,0 +1,87 @@
+const { expect } = require("chai")
+const { loadFixture } = require("@nomicfoundation/hardhat-network-helpers")
+const { ethers } = require("hardhat")
+
+const ISSUER_URL = "http://localhost:8000"
+const StatusType = { Issuance: 1, Revocation: 2 }
+const StatusMechanism = { BitStatusList: 0, MerkleTree: 1, BigBitStatusList: 2 }
+
+// every list of an issuer is registered under its own IssuerId
+function getListUrl(listId) {
+  return `${ISSUER_URL}?list=${listId}`
+}
+
+describe("Status Registry Unit Tests", async function () {
+  async function deployFixture() {
+    const [owner] = await ethers.getSigners()
+    // no request is sent to the consumers here
+    const registry = await (await ethers.getContractFactory("StatusRegistry")).deploy(owner.address, owner.address)
+    await registry.deployed()
+
+    await (await registry.addIssuer(1, getListUrl(0), StatusMechanism.BitStatusList)).wait()
+    await (await registry.addIssuer(2, getListUrl(1), StatusMechanism.BitStatusList)).wait()
+
+    return { registry }
+  }
+
+  describe("getIssuerIdByList", async function () {
+    it("resolves each list to its IssuerId", async () => {
+      const { registry } = await loadFixture(deployFixture)
+
+      expect(await registry.getIssuerIdByList(getListUrl(0), StatusMechanism.BitStatusList)).to.equal(1)
+      expect(await registry.getIssuerIdByList(getListUrl(1), StatusMechanism.BitStatusList)).to.equal(2)
+    })
+
+    it("tells the mechanisms of a url apart", async () => {
+      const { registry } = await loadFixture(deployFixture)
+      await (await registry.addIssuer(3, getListUrl(1), StatusMechanism.MerkleTree)).wait()
+
+      expect(await registry.getIssuerIdByList(getListUrl(1), StatusMechanism.MerkleTree)).to.equal(3)
+      expect(await registry.getIssuerIdByList(getListUrl(1), StatusMechanism.BitStatusList)).to.equal(2)
+    })
+
+    it("rejects a list that is not registered", async () => {
+      const { registry } = await loadFixture(deployFixture)
+
+      await expect(registry.getIssuerIdByList(getListUrl(2), StatusMechanism.BitStatusList))
+        .to.be.revertedWithCustomError(registry, "ListNotFound")
+        .withArgs(getListUrl(2), StatusMechanism.BitStatusList)
+    })
+  })
+
+  describe("addIssuer", async function () {
+    it("keeps the statuses of the lists apart", async () => {
+      const { registry } = await loadFixture(deployFixture)
+      await (await registry.setBSLStatus(2, StatusType.Revocation, { time: 5, status: 0b10 })).wait()
+
+      const listIssuerId = await registry.getIssuerIdByList(getListUrl(1), StatusMechanism.BitStatusList)
+      const status = await registry.getBSLStatus(listIssuerId, StatusType.Revocation)
+      expect(status.time).to.equal(5)
+      expect(status.status).to.equal(0b10)
+      expect((await registry.getBSLStatus(1, StatusType.Revocation)).time).to.equal(0)
+    })
+
+    it("registers a list under one IssuerId only", async () => {
+      const { registry } = await loadFixture(deployFixture)
+
+      await expect(registry.addIssuer(3, getListUrl(1), StatusMechanism.BitStatusList))
+        .to.be.revertedWithCustomError(registry, "ListAlreadyRegistered")
+        .withArgs(getListUrl(1), 2)
+    })
+
+    it("does not move an IssuerId to another list", async () => {
+      const { registry } = await loadFixture(deployFixture)
+
+      await expect(registry.addIssuer(1, getListUrl(2), StatusMechanism.BitStatusList))
+        .to.be.revertedWithCustomError(registry, "IssuerAlreadyExists")
+        .withArgs(1)
+    })
+
+    it("accepts the same registration again", async () => {
+      const { registry } = await loadFixture(deployFixture)
+
+      await (await registry.addIssuer(2, getListUrl(1), StatusMechanism.BitStatusList)).wait()
+      expect(await registry.getIssuerIdByList(getListUrl(1), StatusMechanism.BitStatusList)).to.equal(2)
+    })
+  })
+})
@@ -0,0 +1,128 @@
+const { expect } = require("chai")
+const { loadFixture } = require("@nomicfoundation/hardhat-network-helpers")
+const { ethers } = require("hardhat")
+
+const ISSUER_ID = 1
+const ISSUER_URL = "http://localhost:8000/status?list=0"
+const StatusType = { Issuance: 1, Revocation: 2 }
+const StatusMechanism = { BitStatusList: 0 }
+const OracleType = { ChainlinkConsumer: 0, ZKConsumer: 1 }
+const ResponseType = { LastStatus: 0 }
+// FailureCode::IssuerUnavailable of the oracles
+const FAILURE_CODE = 1
+
+describe("ZK Oracle Manager Unit Tests", async function () {
+  // the hardhat network has a single funded account, so the other callers are funded from it
+  async function getFundedWallet(funder) {
+    const wallet = ethers.Wallet.createRandom().connect(ethers.provider)
+    await (await funder.sendTransaction({ to: wallet.address, value: ethers.utils.parseEther("1") })).wait()
+    return wallet
+  }
+
+  async function deployFixture() {
+    const [oracle] = await ethers.getSigners()
+    const stranger = await getFundedWallet(oracle)
+
+    const manager = await (await ethers.getContractFactory("ZKOracleManager")).deploy(1, 1)
+    await manager.deployed()
+    // the chainlink consumer is not used here
+    const registry = await (await ethers.getContractFactory("StatusRegistry")).deploy(stranger.address, manager.address)
+    await registry.deployed()
+
+    await (await manager.addOracle(1, "http://localhost:3000", 1)).wait()
+    await (await registry.addIssuer(ISSUER_ID, ISSUER_URL, StatusMechanism.BitStatusList)).wait()
+
+    const requestId = await registry.callStatic.requestStatus(
+      oracle.address,
+      ISSUER_ID,
+      StatusType.Issuance,
+      true,
+      OracleType.ZKConsumer,
+      0,
+      300000
+    )
+    await (
+      await registry.requestStatus(oracle.address, ISSUER_ID, StatusType.Issuance, true, OracleType.ZKConsumer, 0, 300000)
+    ).wait()
+
+    return { manager, registry, oracle, stranger, requestId }
+  }
+
+  function encodeError(code, reason) {
+    return ethers.utils.defaultAbiCoder.encode(["uint8", "string"], [code, reason])
+  }
+
+  describe("applyError", async function () {
+    it("fails the request on the registry with the agreed err", async () => {
+      const { manager, registry, oracle, requestId } = await loadFixture(deployFixture)
+      const err = encodeError(FAILURE_CODE, "issuer unavailable")
+
+      await expect(manager.connect(oracle).fulfillRequestWithError(requestId, err))
+        .to.emit(manager, "RequestFailed")
+        .withArgs(requestId, err)
+        .and.to.emit(registry, "StatusRequestFailed")
+        .withArgs(requestId, ISSUER_ID, StatusType.Issuance, err)
+    })
+
+    it("takes the err over a response", async () => {
+      const { manager, registry, oracle, requestId } = await loadFixture(deployFixture)
+      const err = encodeError(FAILURE_CODE, "issuer unavailable")
+
+      await expect(manager.connect(oracle).fulfillRequest(requestId, ResponseType.LastStatus, "0x01", err))
+        .to.emit(registry, "StatusRequestFailed")
+        .withArgs(requestId, ISSUER_ID, StatusType.Issuance, err)
+    })
+
+    it("rejects a fulfillment without a response or an err", async () => {
+      const { manager, oracle, requestId } = await loadFixture(deployFixture)
+
+      await expect(
+        manager.connect(oracle).fulfillRequest(requestId, ResponseType.LastStatus, "0x", "0x")
+      ).to.be.revertedWithCustomError(manager, "WrongOracleExecution")
+    })
+  })
+
+  describe("failRequest", async function () {
+    it("only accepts the consumer the request was sent to", async () => {
+      const { registry, stranger, requestId } = await loadFixture(deployFixture)
+      const err = encodeError(FAILURE_CODE, "issuer unavailable")
+
+      await expect(registry.connect(stranger).failRequest(requestId, StatusType.Issuance, err))
+        .to.be.revertedWithCustomError(registry, "UnauthorizedCaller")
+        .withArgs(stranger.address)
+    })
+  })
+
+  describe("fulfilled", async function () {
+    it("marks a request once it is fulfilled", async () => {
+      const { manager, oracle, requestId } = await loadFixture(deployFixture)
+      expect(await manager.fulfilled(requestId)).to.equal(false)
+
+      await (await manager.connect(oracle).fulfillRequestWithError(requestId, encodeError(FAILURE_CODE, "first"))).wait()
+      expect(await manager.fulfilled(requestId)).to.equal(true)
+    })
+
+    it("rejects a second fulfillment whichever entry point is used", async () => {
+      const { manager, oracle, requestId } = await loadFixture(deployFixture)
+      const err = encodeError(FAILURE_CODE, "second")
+
+      await (await manager.connect(oracle).fulfillRequestWithError(requestId, encodeError(FAILURE_CODE, "first"))).wait()
+
+      await expect(manager.connect(oracle).fulfillRequestWithError(requestId, err))
+        .to.be.revertedWithCustomError(manager, "RequestAlreadyFulfilled")
+        .withArgs(requestId)
+      await expect(manager.connect(oracle).fulfillRequestWithLastStatus(requestId, "0x01", err))
+        .to.be.revertedWithCustomError(manager, "RequestAlreadyFulfilled")
+        .withArgs(requestId)
+    })
+
+    it("only accepts registered oracles", async () => {
+      const { manager, stranger, requestId } = await loadFixture(deployFixture)
+
+      await expect(manager.connect(stranger).fulfillRequestWithError(requestId, encodeError(FAILURE_CODE, "stranger")))
+        .to.be.revertedWithCustomError(manager, "UnauthorizedCaller")
+        .withArgs(stranger.address)
+      expect(await manager.fulfilled(requestId)).to.equal(false)
+    })
+  })
+})
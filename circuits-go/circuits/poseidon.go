@@ -0,0 +1,202 @@
+package circuits
+
+import (
+	"math/big"
+	"sync"
+
+	"github.com/consensys/gnark-crypto/ecc"
+	"github.com/consensys/gnark/frontend"
+)
+
+// Poseidon over BN254 with the circomlib parameters: x^5 S-boxes, 8 full rounds and the partial
+// rounds below. It is the hash of the commitments data_apis stores (`calculate_poseidon_hash`),
+// so that a circuit can take them as public inputs.
+const (
+	PoseidonMaxInputs  = 16
+	poseidonFullRounds = 8
+	poseidonFieldBits  = 254
+)
+
+// Partial rounds by width, from 2 (one input) to 17 (16 inputs).
+var poseidonPartialRounds = [PoseidonMaxInputs]int{56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68}
+
+type poseidonParameters struct {
+	partialRounds  int
+	roundConstants []*big.Int
+	mds            [][]*big.Int
+}
+
+var poseidonParametersLock sync.Mutex
+var poseidonParametersByWidth = map[int]*poseidonParameters{}
+
+// grainLFSR derives the parameters like generate_parameters_grain.sage of the Poseidon paper,
+// which is where the circomlib constants come from.
+type grainLFSR struct {
+	state [80]uint8
+}
+
+func newGrainLFSR(width, partialRounds int) *grainLFSR {
+	lfsr := &grainLFSR{}
+	position := 0
+	push := func(value, numBits int) {
+		for i := numBits - 1; i >= 0; i-- {
+			lfsr.state[position] = uint8(value>>i) & 1
+			position++
+		}
+	}
+
+	push(1, 2) // prime field
+	push(0, 4) // x^alpha S-box
+	push(poseidonFieldBits, 12)
+	push(width, 12)
+	push(poseidonFullRounds, 10)
+	push(partialRounds, 10)
+	push(1<<30-1, 30)
+
+	for i := 0; i < 160; i++ {
+		lfsr.step()
+	}
+
+	return lfsr
+}
+
+func (lfsr *grainLFSR) step() uint8 {
+	s := lfsr.state
+	bit := s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0]
+	copy(lfsr.state[:], s[1:])
+	lfsr.state[len(s)-1] = bit
+	return bit
+}
+
+// nextBit keeps the second bit of the first pair that starts with 1.
+func (lfsr *grainLFSR) nextBit() uint8 {
+	for {
+		first, second := lfsr.step(), lfsr.step()
+		if first == 1 {
+			return second
+		}
+	}
+}
+
+func (lfsr *grainLFSR) nextBits(numBits int) *big.Int {
+	value := new(big.Int)
+	for i := 0; i < numBits; i++ {
+		value.Lsh(value, 1)
+		value.SetBit(value, 0, uint(lfsr.nextBit()))
+	}
+
+	return value
+}
+
+// nextFieldElement rejects the values above the modulus, as round constants are sampled.
+func (lfsr *grainLFSR) nextFieldElement(modulus *big.Int) *big.Int {
+	for {
+		value := lfsr.nextBits(poseidonFieldBits)
+		if value.Cmp(modulus) < 0 {
+			return value
+		}
+	}
+}
+
+func newPoseidonParameters(width int) *poseidonParameters {
+	modulus := ecc.BN254.ScalarField()
+	partialRounds := poseidonPartialRounds[width-2]
+	lfsr := newGrainLFSR(width, partialRounds)
+
+	roundConstants := make([]*big.Int, (poseidonFullRounds+partialRounds)*width)
+	for i := range roundConstants {
+		roundConstants[i] = lfsr.nextFieldElement(modulus)
+	}
+
+	// a Cauchy matrix 1 / (x_i + y_j), with x and y sampled after the round constants
+	xs := make([]*big.Int, 2*width)
+	for i := range xs {
+		xs[i] = lfsr.nextBits(poseidonFieldBits)
+		xs[i].Mod(xs[i], modulus)
+	}
+	ys := xs[width:]
+
+	mds := make([][]*big.Int, width)
+	for i := range mds {
+		mds[i] = make([]*big.Int, width)
+		for j := range mds[i] {
+			sum := new(big.Int).Add(xs[i], ys[j])
+			sum.Mod(sum, modulus)
+			mds[i][j] = new(big.Int).ModInverse(sum, modulus)
+		}
+	}
+
+	return &poseidonParameters{partialRounds, roundConstants, mds}
+}
+
+func getPoseidonParameters(width int) *poseidonParameters {
+	poseidonParametersLock.Lock()
+	defer poseidonParametersLock.Unlock()
+
+	parameters, ok := poseidonParametersByWidth[width]
+	if !ok {
+		parameters = newPoseidonParameters(width)
+		poseidonParametersByWidth[width] = parameters
+	}
+
+	return parameters
+}
+
+func poseidonSBox(api frontend.API, x frontend.Variable) frontend.Variable {
+	x2 := api.Mul(x, x)
+	x4 := api.Mul(x2, x2)
+	return api.Mul(x4, x)
+}
+
+// poseidonPermutation hashes up to PoseidonMaxInputs inputs, with the capacity element set to 0.
+func poseidonPermutation(api frontend.API, inputs []frontend.Variable) frontend.Variable {
+	width := len(inputs) + 1
+	parameters := getPoseidonParameters(width)
+	state := append([]frontend.Variable{0}, inputs...)
+
+	numRounds := poseidonFullRounds + parameters.partialRounds
+	for round := 0; round < numRounds; round++ {
+		for i := range state {
+			state[i] = api.Add(state[i], parameters.roundConstants[round*width+i])
+		}
+
+		isFullRound := round < poseidonFullRounds/2 || round >= poseidonFullRounds/2+parameters.partialRounds
+		if isFullRound {
+			for i := range state {
+				state[i] = poseidonSBox(api, state[i])
+			}
+		} else {
+			state[0] = poseidonSBox(api, state[0])
+		}
+
+		mixed := make([]frontend.Variable, width)
+		for i := range mixed {
+			mixed[i] = frontend.Variable(0)
+			for j := range state {
+				mixed[i] = api.Add(mixed[i], api.Mul(parameters.mds[i][j], state[j]))
+			}
+		}
+		state = mixed
+	}
+
+	return state[0]
+}
+
+// Poseidon hashes the inputs like `calculate_poseidon_hash` of data_apis: inputs beyond 16 are
+// absorbed 15 at a time, each call taking the previous digest as its first input.
+func Poseidon(api frontend.API, inputs ...frontend.Variable) frontend.Variable {
+	if len(inputs) == 0 {
+		panic("poseidon: cannot hash an empty input")
+	}
+
+	first := min(len(inputs), PoseidonMaxInputs)
+	digest := poseidonPermutation(api, inputs[:first])
+
+	for rest := inputs[first:]; len(rest) > 0; {
+		chunk := min(len(rest), PoseidonMaxInputs-1)
+		digest = poseidonPermutation(api, append([]frontend.Variable{digest}, rest[:chunk]...))
+		rest = rest[chunk:]
+	}
+
+	return digest
+}
@@ -0,0 +1,80 @@
+package circuits
+
+import (
+	"testing"
+
+	"github.com/consensys/gnark-crypto/ecc"
+	"github.com/consensys/gnark/frontend"
+	"github.com/consensys/gnark/test"
+)
+
+type poseidonCircuit struct {
+	Inputs []frontend.Variable
+	Digest frontend.Variable `gnark:",public"`
+}
+
+func (circuit *poseidonCircuit) Define(api frontend.API) error {
+	api.AssertIsEqual(Poseidon(api, circuit.Inputs...), circuit.Digest)
+	return nil
+}
+
+func checkPoseidon(t *testing.T, inputs []frontend.Variable, digest frontend.Variable) error {
+	t.Helper()
+	circuit := &poseidonCircuit{Inputs: make([]frontend.Variable, len(inputs))}
+	witness := &poseidonCircuit{Inputs: inputs, Digest: digest}
+
+	return test.IsSolved(circuit, witness, ecc.BN254.ScalarField())
+}
+
+// The same vectors are asserted by data_apis (`utils::tests`), so that the commitments it stores
+// can be recomputed here.
+func TestPoseidonMatchesTheSharedVectors(t *testing.T) {
+	vectors := []struct {
+		name   string
+		inputs []frontend.Variable
+		digest string
+	}{
+		{"circomlib", []frontend.Variable{1, 2}, "7853200120776062878684798364095072458815029376092732009249414926327459813530"},
+		{"circomlib", []frontend.Variable{1, 2, 3, 4}, "18821383157269793795438455681495246036402687001665670618754263018637548127333"},
+		// calculate_status_commitment of the issuance status 0b1011 at time 5 of a bit status list
+		{"status commitment", []frontend.Variable{0, 0, 5, 0b1011}, "9369453522483212879924925258841810605190261124448240357801507940333507057703"},
+	}
+
+	for _, vector := range vectors {
+		if err := checkPoseidon(t, vector.inputs, vector.digest); err != nil {
+			t.Errorf("%s %v: %v", vector.name, vector.inputs, err)
+		}
+	}
+}
+
+func TestPoseidonRejectsAnotherDigest(t *testing.T) {
+	if err := checkPoseidon(t, []frontend.Variable{1, 2}, 0); err == nil {
+		t.Error("a wrong digest was accepted")
+	}
+}
+
+type poseidonChainCircuit struct {
+	Inputs [40]frontend.Variable
+}
+
+// the long input is the chain of the 16 first inputs, then 15 by 15
+func (circuit *poseidonChainCircuit) Define(api frontend.API) error {
+	inputs := circuit.Inputs[:]
+	first := Poseidon(api, inputs[:16]...)
+	second := Poseidon(api, append([]frontend.Variable{first}, inputs[16:31]...)...)
+	third := Poseidon(api, append([]frontend.Variable{second}, inputs[31:]...)...)
+
+	api.AssertIsEqual(Poseidon(api, inputs...), third)
+	return nil
+}
+
+func TestPoseidonAbsorbsLongInputsIntoThePreviousDigest(t *testing.T) {
+	witness := &poseidonChainCircuit{}
+	for i := range witness.Inputs {
+		witness.Inputs[i] = i
+	}
+
+	if err := test.IsSolved(&poseidonChainCircuit{}, witness, ecc.BN254.ScalarField()); err != nil {
+		t.Error(err)
+	}
+}
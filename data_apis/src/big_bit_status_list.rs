@@ -0,0 +1,238 @@
+use bson::oid::ObjectId;
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+use zkcdid_lib_rs::models::status_state::StatusType;
+
+use crate::errors::{ApiError, ApiResult};
+
+/// Path segment of the mechanism in the credential and status routes. `StatusMechanism` comes from
+/// `zkcdid_lib_rs` and has no variant for it, so the mechanism has its own routes and collections.
+pub const BIG_BIT_STATUS_LIST: &str = "BigBitStatusList";
+/// The mechanism byte of signed messages and commitments, after those of `StatusMechanism`.
+pub const BIG_BIT_STATUS_LIST_ID: u8 = 2;
+/// `uint256` words of `StatusState.BigBSLStatus`.
+pub const BIG_BSL_WORDS: usize = 7;
+/// Credential indexes per list.
+pub const BIG_BSL_BITS: u64 = BIG_BSL_WORDS as u64 * 256;
+
+type Word = [u8; 32];
+
+/// A status of `StatusState.BigBSLStatus`: bit `i` of the list is bit `i % 256` of word `i / 256`,
+/// counted from the least significant bit as in `set1BitAtPosition`.
+///
+/// Words are serialized to JSON as 0x-prefixed 32-byte hex, which `BigNumber.from` reads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BigStatusState {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub status_type: StatusType,
+    /// Statuses stored before lists existed have none and are in list 0.
+    #[serde(default)]
+    pub list_id: u64,
+    pub time: u32,
+    #[serde(serialize_with = "serialize_words", deserialize_with = "deserialize_words")]
+    pub data: [Word; BIG_BSL_WORDS],
+    pub signature: Option<String>,
+}
+
+impl BigStatusState {
+    pub fn get_initial_status(status_type: StatusType, list_id: u64) -> Self {
+        Self {
+            id: None,
+            status_type,
+            list_id,
+            time: 0,
+            data: [[0u8; 32]; BIG_BSL_WORDS],
+            signature: None,
+        }
+    }
+
+    /// The byte of the word holding `index` and the mask of its bit.
+    fn get_position(index: u64) -> ApiResult<(usize, usize, u8)> {
+        if index >= BIG_BSL_BITS {
+            return Err(ApiError::CommonError(format!("Index {} is out of a list of {} bits", index, BIG_BSL_BITS)));
+        }
+
+        let bit = (index % 256) as usize;
+        Ok(((index / 256) as usize, 31 - bit / 8, 1 << (bit % 8)))
+    }
+
+    pub fn set_index_status(&mut self, index: u64) -> ApiResult<()> {
+        let (word, byte, mask) = Self::get_position(index)?;
+        self.data[word][byte] |= mask;
+        Ok(())
+    }
+
+    pub fn get_index_status(&self, index: u64) -> ApiResult<bool> {
+        let (word, byte, mask) = Self::get_position(index)?;
+        Ok(self.data[word][byte] & mask != 0)
+    }
+
+    pub fn get_set_indexes(&self) -> Vec<u64> {
+        (0..BIG_BSL_BITS).filter(|index| self.get_index_status(*index).unwrap_or(false)).collect()
+    }
+
+    /// `abi.encode(uint32 time, uint256[7] data)`, as `StatusState.decodeBigBSLStatus` reads it.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = vec![0u8; 28];
+        encoded.extend_from_slice(&self.time.to_be_bytes());
+        for word in self.data.iter() {
+            encoded.extend_from_slice(word);
+        }
+
+        encoded
+    }
+
+    pub fn decode(status_type: StatusType, list_id: u64, encoded: &[u8]) -> ApiResult<Self> {
+        if encoded.len() != 32 * (BIG_BSL_WORDS + 1) {
+            return Err(ApiError::SerializationError(format!("A big status is {} bytes, not {}", 32 * (BIG_BSL_WORDS + 1), encoded.len())));
+        }
+        if encoded[..28].iter().any(|byte| *byte != 0) {
+            return Err(ApiError::SerializationError("The time of a big status does not fit in a uint32".to_string()));
+        }
+
+        let mut status = Self::get_initial_status(status_type, list_id);
+        status.time = u32::from_be_bytes([encoded[28], encoded[29], encoded[30], encoded[31]]);
+        for (word, chunk) in status.data.iter_mut().zip(encoded[32..].chunks(32)) {
+            word.copy_from_slice(chunk);
+        }
+
+        Ok(status)
+    }
+}
+
+pub fn word_to_hex(word: &Word) -> String {
+    let digits: String = word.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", digits)
+}
+
+pub fn hex_to_word(value: &str) -> Option<Word> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.len() != 64 || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut word = [0u8; 32];
+    for (byte, pair) in word.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+
+    Some(word)
+}
+
+fn serialize_words<S>(words: &[Word; BIG_BSL_WORDS], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let words: Vec<String> = words.iter().map(word_to_hex).collect();
+    words.serialize(serializer)
+}
+
+fn deserialize_words<'de, D>(deserializer: D) -> Result<[Word; BIG_BSL_WORDS], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values: Vec<String> = Deserialize::deserialize(deserializer)?;
+    if values.len() != BIG_BSL_WORDS {
+        return Err(DeError::custom(format!("A big status has {} words, not {}", BIG_BSL_WORDS, values.len())));
+    }
+
+    let mut words = [[0u8; 32]; BIG_BSL_WORDS];
+    for (word, value) in words.iter_mut().zip(values.iter()) {
+        *word = hex_to_word(value).ok_or(DeError::custom(format!("Invalid uint256 word {:?}", value)))?;
+    }
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_with(indexes: &[u64]) -> BigStatusState {
+        let mut status = BigStatusState::get_initial_status(StatusType::Revocation, 0);
+        for index in indexes.iter() {
+            status.set_index_status(*index).unwrap();
+        }
+
+        status
+    }
+
+    #[test]
+    fn bits_are_counted_from_the_least_significant_bit_of_each_word() {
+        let status = status_with(&[0, 9, 255, 256, BIG_BSL_BITS - 1]);
+
+        assert_eq!(status.data[0][31], 0b0000_0001);
+        assert_eq!(status.data[0][30], 0b0000_0010);
+        assert_eq!(status.data[0][0], 0b1000_0000);
+        assert_eq!(status.data[1][31], 0b0000_0001);
+        assert_eq!(status.data[BIG_BSL_WORDS - 1][0], 0b1000_0000);
+        assert_eq!(status.get_set_indexes(), vec![0, 9, 255, 256, BIG_BSL_BITS - 1]);
+    }
+
+    #[test]
+    fn set_indexes_read_back() {
+        let status = status_with(&[3, 700]);
+
+        assert!(status.get_index_status(3).unwrap());
+        assert!(status.get_index_status(700).unwrap());
+        assert!(!status.get_index_status(4).unwrap());
+    }
+
+    #[test]
+    fn indexes_out_of_the_list_are_rejected() {
+        let mut status = status_with(&[]);
+
+        assert!(status.set_index_status(BIG_BSL_BITS).is_err());
+        assert!(status.get_index_status(BIG_BSL_BITS).is_err());
+    }
+
+    #[test]
+    fn encoding_is_the_abi_layout_and_decodes_back() {
+        let mut status = status_with(&[1, 1000]);
+        status.time = 0x01020304;
+
+        let encoded = status.encode();
+        assert_eq!(encoded.len(), 32 * (BIG_BSL_WORDS + 1));
+        assert_eq!(encoded[28..32], [1, 2, 3, 4]);
+        assert_eq!(encoded[32..64], status.data[0]);
+
+        let decoded = BigStatusState::decode(StatusType::Revocation, 5, &encoded).unwrap();
+        assert_eq!(decoded.time, status.time);
+        assert_eq!(decoded.data, status.data);
+        assert_eq!(decoded.list_id, 5);
+    }
+
+    #[test]
+    fn malformed_encodings_are_rejected() {
+        let encoded = status_with(&[1]).encode();
+        assert!(BigStatusState::decode(StatusType::Issuance, 0, &encoded[1..]).is_err());
+
+        let mut large_time = encoded;
+        large_time[27] = 1;
+        assert!(BigStatusState::decode(StatusType::Issuance, 0, &large_time).is_err());
+    }
+
+    #[test]
+    fn words_round_trip_through_hex() {
+        let mut word = [0u8; 32];
+        word[0] = 0xab;
+        word[31] = 0x01;
+
+        let hex = word_to_hex(&word);
+        assert_eq!(hex, format!("0xab{}01", "0".repeat(60)));
+        assert_eq!(hex_to_word(&hex), Some(word));
+        assert_eq!(hex_to_word(&hex[2..]), Some(word));
+        assert_eq!(hex_to_word("0x01"), None);
+        assert_eq!(hex_to_word(&format!("0x{}", "zz".repeat(32))), None);
+    }
+
+    #[test]
+    fn words_serialize_as_hex_strings() {
+        let status = status_with(&[0]);
+        let value = serde_json::to_value(&status).unwrap();
+
+        assert_eq!(value["data"][0], word_to_hex(&status.data[0]));
+        let deserialized: BigStatusState = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.data, status.data);
+    }
+}
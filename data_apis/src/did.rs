@@ -0,0 +1,61 @@
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{errors::{ApiError, ApiResult}, services::issuer_key_service::IssuerPublicKey};
+
+const DID_WEB_PREFIX: &str = "did:web:";
+const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+const JWK_CONTEXT: &str = "https://w3id.org/security/suites/jws-2020/v1";
+/// Type of the service whose endpoint serves `/statuses/{mechanism}/{type}`, read by the oracles' resolver.
+pub const STATUS_SERVICE_TYPE: &str = "ZkcdidStatusService";
+const STATUS_SERVICE_FRAGMENT: &str = "status";
+
+/// The `did:web` of the issuer at `base_url`: the host, its port as `%3A<port>`, then the path segments
+/// separated by `:`. `https://issuer.example:8443/a` is `did:web:issuer.example%3A8443:a`.
+///
+/// The scheme is not part of the DID and resolvers fetch the document over https, so an issuer
+/// served over http, e.g. on localhost, is only resolved by oracles with `[did] web_scheme = "http"`.
+pub fn get_issuer_did(base_url: &str) -> ApiResult<String> {
+    let url = Url::parse(base_url)?;
+    let host = url.host_str()
+        .ok_or(ApiError::ConfigError(format!("api_url {} has no host", base_url)))?;
+
+    let mut did = format!("{}{}", DID_WEB_PREFIX, host);
+    if let Some(port) = url.port() {
+        did.push_str(&format!("%3A{}", port));
+    }
+    for segment in url.path_segments().into_iter().flatten().filter(|segment| !segment.is_empty()) {
+        did.push(':');
+        did.push_str(segment);
+    }
+
+    Ok(did)
+}
+
+/// The DID document of the issuer: its status signing key and its status service.
+pub fn build_did_document(base_url: &str, public_key: &IssuerPublicKey) -> ApiResult<Value> {
+    let did = get_issuer_did(base_url)?;
+    let key_id = format!("{}#{}", did, public_key.key_id);
+
+    Ok(json!({
+        "@context": [DID_CONTEXT, JWK_CONTEXT],
+        "id": did,
+        "verificationMethod": [{
+            "id": key_id,
+            "type": "JsonWebKey2020",
+            "controller": did,
+            "publicKeyJwk": {
+                "kty": "OKP",
+                "crv": public_key.algorithm,
+                "x": public_key.public_key,
+                "kid": public_key.key_id,
+            },
+        }],
+        "assertionMethod": [key_id],
+        "service": [{
+            "id": format!("{}#{}", did, STATUS_SERVICE_FRAGMENT),
+            "type": STATUS_SERVICE_TYPE,
+            "serviceEndpoint": base_url.trim_end_matches('/'),
+        }],
+    }))
+}
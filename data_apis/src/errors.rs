@@ -4,6 +4,8 @@ use mongodb::error::Error as MongoError;
 use serde_json::Error as SerdeError;
 use url::ParseError;
 
+use crate::{mongo::{is_duplicate_key_error_on, is_transient_transaction_error}, services::status_service::APPEND_INDEX_NAME};
+
 
 pub type ApiResult<T> = std::result::Result<T, ApiError>;
 
@@ -12,6 +14,9 @@ pub enum ApiError {
     DatabaseError(String),
     SerializationError(String),
     CommonError(String),
+    ConfigError(String),
+    /// A write lost against a concurrent one; running it again may succeed.
+    ConflictError(String),
 }
 
 impl std::fmt::Display for ApiError {
@@ -20,6 +25,8 @@ impl std::fmt::Display for ApiError {
             ApiError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
             ApiError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             ApiError::CommonError(msg) => write!(f, "Common error: {}", msg),
+            ApiError::ConfigError(msg) => write!(f, "Config error: {}", msg),
+            ApiError::ConflictError(msg) => write!(f, "Conflict error: {}", msg),
         }
     }
 }
@@ -28,6 +35,11 @@ impl error::Error for ApiError { }
 
 impl From<MongoError> for ApiError {
     fn from(error: MongoError) -> Self {
+        // only a lost compare-and-append is worth running again, any other duplicate key is a bug
+        if is_duplicate_key_error_on(&error, APPEND_INDEX_NAME) || is_transient_transaction_error(&error) {
+            return ApiError::ConflictError(error.to_string());
+        }
+
         ApiError::DatabaseError(error.to_string())
     }
 }
@@ -44,6 +56,12 @@ impl From<base64::DecodeError> for ApiError {
     }
 }
 
+impl From<bson::ser::Error> for ApiError {
+    fn from(error: bson::ser::Error) -> Self {
+        ApiError::SerializationError(error.to_string())
+    }
+}
+
 impl From<bson::oid::Error> for ApiError {
     fn from(error: bson::oid::Error) -> Self {
         ApiError::SerializationError(error.to_string())
@@ -1,4 +1,11 @@
 pub mod routes;
 pub mod utils;
 pub mod services;
-pub mod errors;
\ No newline at end of file
+pub mod errors;
+pub mod config;
+pub mod merkle_tree;
+pub mod big_bit_status_list;
+pub mod status_list;
+pub mod w3c;
+pub mod did;
+pub mod mongo;
\ No newline at end of file
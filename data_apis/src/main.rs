@@ -2,16 +2,44 @@ use actix_web::{web, App, HttpServer};
 use data_apis::{routes::{self}, utils::AppData};
 use actix_web::middleware::Logger;
 use env_logger::Env;
-use zkcdid_lib_rs::config::Config;
+use data_apis::{config::ApiConfig, services::issuer_key_service::IssuerKeyService};
+
+/// `config check` prints the effective config with the secrets hidden and exits.
+fn check_config(api_config: &ApiConfig) -> std::io::Result<()> {
+    let redacted = api_config.to_redacted().map_err(std::io::Error::other)?;
+    println!("{}", serde_json::to_string_pretty(&redacted)?);
+
+    let problems = api_config.get_problems();
+    if problems.is_empty() {
+        println!("The config is valid");
+        return Ok(());
+    }
+
+    for problem in problems.iter() {
+        println!("- {}", problem);
+    }
+    Err(std::io::Error::other(format!("{} problem(s) found", problems.len())))
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     println!("Loading configuration...");
-    let config = Config::load_api_config();
+    let api_config = ApiConfig::load().map_err(std::io::Error::other)?;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args == ["config", "check"] {
+        return check_config(&api_config);
+    }
+
+    // a bad config stops the server here rather than in the middle of a request
+    api_config.validate().map_err(std::io::Error::other)?;
+    let config = &api_config.config;
+    let issuer_key_service = IssuerKeyService::load_or_create(&api_config.issuer).map_err(std::io::Error::other)?;
+    println!("Issuer key {}", issuer_key_service.get_public_key().key_id);
 
     loop {
         println!("Initializing services...");
-        if let Ok(app_data) = AppData::new(&config).await {
+        if let Ok(app_data) = AppData::new(&api_config, &issuer_key_service).await {
             println!("Starting server {:?} at {:?}", config.get_name(), config.get_api_port());
 
             env_logger::init_from_env(Env::default().default_filter_or("info"));
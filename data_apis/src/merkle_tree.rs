@@ -0,0 +1,237 @@
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use serde::Serialize;
+
+use crate::{errors::{ApiError, ApiResult}, utils::field_to_string};
+
+/// Height of the issuer's status trees, i.e. 2048 credential indexes per status type.
+///
+/// The circuits pad with zero leaves, which the pair hash leaves unchanged, so a smaller tree
+/// has the same root as the circuits' tree of `Height` 15.
+pub const MERKLE_TREE_HEIGHT: u32 = 11;
+
+/// The pair hash of the circuits (`additionHash` in `circuits-go` and `scripts/did/mimc.ts`).
+pub fn hash_pair(left: &Fr, right: &Fr) -> Fr {
+    *left + *right
+}
+
+/// The leaf of a credential index whose status is set; unset leaves are 0, as in `MTStatus.getLeavesAtTime`.
+pub fn get_leaf_value(index: u64) -> Fr {
+    Fr::from(index + 1)
+}
+
+/// Inclusion path of a leaf, from the leaf up to the root. Values are decimal field elements.
+#[derive(Debug, Clone, Serialize)]
+pub struct MerklePath {
+    pub index: u64,
+    pub height: u32,
+    pub leaf: String,
+    pub siblings: Vec<String>,
+    pub root: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    height: u32,
+    leaves: Vec<Fr>,
+}
+
+impl MerkleTree {
+    pub fn new(height: u32) -> Self {
+        Self {
+            height,
+            leaves: vec![Fr::from(0u64); 1 << height],
+        }
+    }
+
+    /// Rebuilds a tree from the indexes whose status is set.
+    pub fn from_set_indexes(height: u32, indexes: &[u64]) -> ApiResult<Self> {
+        let mut tree = Self::new(height);
+        for index in indexes.iter() {
+            tree.set(*index)?;
+        }
+
+        Ok(tree)
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    fn check_index(&self, index: u64) -> ApiResult<usize> {
+        match usize::try_from(index) {
+            Ok(position) if position < self.leaves.len() => Ok(position),
+            _ => Err(ApiError::CommonError(format!("Index {} is out of a tree of height {}", index, self.height))),
+        }
+    }
+
+    pub fn set(&mut self, index: u64) -> ApiResult<()> {
+        let position = self.check_index(index)?;
+        self.leaves[position] = get_leaf_value(index);
+        Ok(())
+    }
+
+    pub fn is_set(&self, index: u64) -> ApiResult<bool> {
+        let position = self.check_index(index)?;
+        Ok(self.leaves[position] != Fr::from(0u64))
+    }
+
+    /// The indexes whose status is set, which is all that is needed to rebuild the tree.
+    pub fn get_set_indexes(&self) -> Vec<u64> {
+        let zero = Fr::from(0u64);
+        (0..self.leaves.len() as u64).filter(|index| self.leaves[*index as usize] != zero).collect()
+    }
+
+    /// Every level of the tree, from the leaves to the root.
+    fn get_levels(&self) -> Vec<Vec<Fr>> {
+        let mut levels = vec![self.leaves.clone()];
+
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let parents = level.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])).collect();
+            levels.push(parents);
+        }
+
+        levels
+    }
+
+    pub fn get_root(&self) -> Fr {
+        self.get_levels().last().map(|root| root[0]).unwrap_or(Fr::from(0u64))
+    }
+
+    /// The root as the `status` of a `StatusState`.
+    pub fn get_root_status(&self) -> ApiResult<u64> {
+        let root = self.get_root().into_bigint();
+        let limbs = root.as_ref();
+        if limbs[1..].iter().any(|limb| *limb != 0) {
+            return Err(ApiError::CommonError(format!("Root {} does not fit in a status", root)));
+        }
+
+        Ok(limbs[0])
+    }
+
+    pub fn get_path(&self, index: u64) -> ApiResult<MerklePath> {
+        let mut position = self.check_index(index)?;
+        let levels = self.get_levels();
+
+        let mut siblings = vec![];
+        for level in levels.iter().take(levels.len() - 1) {
+            siblings.push(field_to_string(&level[position ^ 1]));
+            position /= 2;
+        }
+
+        Ok(MerklePath {
+            index,
+            height: self.height,
+            leaf: field_to_string(&self.leaves[index as usize]),
+            siblings,
+            root: field_to_string(&self.get_root()),
+        })
+    }
+}
+
+/// Whether `path` leads from its leaf to its root; the sibling is on the right when the index bit is 0.
+pub fn verify_path(path: &MerklePath) -> bool {
+    let parse = |value: &str| value.parse::<Fr>().ok();
+
+    let mut node = match parse(&path.leaf) {
+        Some(node) => node,
+        None => return false,
+    };
+
+    for (level, sibling) in path.siblings.iter().enumerate() {
+        let sibling = match parse(sibling) {
+            Some(sibling) => sibling,
+            None => return false,
+        };
+
+        node = match (path.index >> level) & 1 {
+            0 => hash_pair(&node, &sibling),
+            _ => hash_pair(&sibling, &node),
+        };
+    }
+
+    parse(&path.root) == Some(node) && path.siblings.len() == path.height as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_tree_has_a_zero_root() {
+        let tree = MerkleTree::new(MERKLE_TREE_HEIGHT);
+
+        assert_eq!(tree.get_root(), Fr::from(0u64));
+        assert_eq!(tree.get_root_status().unwrap(), 0);
+        assert!(tree.get_set_indexes().is_empty());
+    }
+
+    #[test]
+    fn set_leaves_add_up_to_the_root() {
+        let mut tree = MerkleTree::new(3);
+        tree.set(0).unwrap();
+        tree.set(5).unwrap();
+
+        assert!(tree.is_set(0).unwrap());
+        assert!(tree.is_set(5).unwrap());
+        assert!(!tree.is_set(1).unwrap());
+        // with the addition pair hash the root is the sum of the leaves, index + 1 each
+        assert_eq!(tree.get_root_status().unwrap(), 1 + 6);
+    }
+
+    #[test]
+    fn set_indexes_rebuild_the_same_tree() {
+        let mut tree = MerkleTree::new(4);
+        for index in [3, 7, 15] {
+            tree.set(index).unwrap();
+        }
+
+        assert_eq!(tree.get_set_indexes(), vec![3, 7, 15]);
+        let rebuilt = MerkleTree::from_set_indexes(4, &tree.get_set_indexes()).unwrap();
+        assert_eq!(rebuilt.get_root(), tree.get_root());
+    }
+
+    #[test]
+    fn indexes_out_of_the_tree_are_rejected() {
+        let mut tree = MerkleTree::new(2);
+
+        assert!(tree.set(4).is_err());
+        assert!(tree.is_set(4).is_err());
+        assert!(tree.get_path(u64::MAX).is_err());
+        assert!(MerkleTree::from_set_indexes(2, &[1, 4]).is_err());
+    }
+
+    #[test]
+    fn paths_lead_from_the_leaf_to_the_root() {
+        let tree = MerkleTree::from_set_indexes(MERKLE_TREE_HEIGHT, &[2, 9, 2047]).unwrap();
+
+        for index in [0, 2, 9, 2047] {
+            let path = tree.get_path(index).unwrap();
+            assert_eq!(path.siblings.len(), MERKLE_TREE_HEIGHT as usize);
+            assert_eq!(path.root, field_to_string(&tree.get_root()));
+            assert!(verify_path(&path));
+        }
+    }
+
+    #[test]
+    fn tampered_paths_do_not_verify() {
+        let tree = MerkleTree::from_set_indexes(3, &[1, 4]).unwrap();
+        let path = tree.get_path(1).unwrap();
+
+        let mut wrong_leaf = path.clone();
+        wrong_leaf.leaf = "0".to_string();
+        assert!(!verify_path(&wrong_leaf));
+
+        let mut wrong_root = path.clone();
+        wrong_root.root = "1".to_string();
+        assert!(!verify_path(&wrong_root));
+
+        let mut short = path.clone();
+        short.siblings.pop();
+        assert!(!verify_path(&short));
+
+        let mut unparsable = path;
+        unparsable.siblings[0] = "not a number".to_string();
+        assert!(!verify_path(&unparsable));
+    }
+}
@@ -0,0 +1,114 @@
+use std::{future::Future, time::Duration};
+
+use mongodb::{error::{Error, ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT}, Client, ClientSession};
+
+use crate::errors::{ApiError, ApiResult};
+
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// How many times a transaction is run before its conflict is returned. Every concurrent
+/// append to the same status list but one loses a round, so this bounds the writers per list.
+pub const MAX_TRANSACTION_ATTEMPTS: u32 = 100;
+/// Upper bound of the random pause before running a transaction again, in milliseconds.
+const MAX_RETRY_DELAY: u64 = 50;
+
+/// Whether the write was rejected by the unique index `index_name`, which the server names in its message.
+pub fn is_duplicate_key_error_on(error: &Error, index_name: &str) -> bool {
+    let message = match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == DUPLICATE_KEY_CODE => &write_error.message,
+        ErrorKind::Command(command_error) if command_error.code == DUPLICATE_KEY_CODE => &command_error.message,
+        _ => return false,
+    };
+
+    get_duplicate_key_index(message) == Some(index_name)
+}
+
+/// The index of an `E11000 duplicate key error collection: ... index: <name> dup key: ...` message.
+fn get_duplicate_key_index(message: &str) -> Option<&str> {
+    message.split_whitespace().skip_while(|word| *word != "index:").nth(1)
+}
+
+/// Whether the whole transaction can be run again, e.g. after a write conflict with a concurrent one.
+pub fn is_transient_transaction_error(error: &Error) -> bool {
+    error.contains_label(TRANSIENT_TRANSACTION_ERROR)
+}
+
+/// Whether the commit may or may not have been applied, in which case only the commit is retried.
+pub fn is_unknown_commit_result(error: &Error) -> bool {
+    error.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+}
+
+/// A random pause, so that the writers that lost do not collide again all at once.
+async fn wait_before_retry() {
+    let delay = chrono::Utc::now().timestamp_subsec_nanos() as u64 % MAX_RETRY_DELAY;
+    tokio::time::sleep(Duration::from_millis(delay)).await;
+}
+
+/// Commits the transaction, retrying the commit alone while its outcome is unknown, up to `MAX_TRANSACTION_ATTEMPTS`.
+/// A commit whose outcome stays unknown is not a conflict: running the transaction again could apply it twice.
+async fn commit(session: &mut ClientSession) -> ApiResult<()> {
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        match session.commit_transaction().await {
+            Ok(()) => return Ok(()),
+            Err(e) if is_unknown_commit_result(&e) => {
+                if attempts >= MAX_TRANSACTION_ATTEMPTS {
+                    return Err(ApiError::DatabaseError(format!("Unknown transaction commit result after {} attempts: {}", attempts, e)));
+                }
+                wait_before_retry().await;
+            },
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Runs `attempt` in a transaction, and again after a `ConflictError` until `MAX_TRANSACTION_ATTEMPTS`.
+/// The attempt gets the session and hands it back with its result, so that it can borrow its other inputs.
+pub async fn run_transaction<T, F, Fut>(client: &Client, mut attempt: F) -> ApiResult<T>
+where
+    F: FnMut(ClientSession) -> Fut,
+    Fut: Future<Output = (ClientSession, ApiResult<T>)>,
+{
+    let mut session = client.start_session().await?;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        session.start_transaction().await?;
+
+        let (returned, result) = attempt(session).await;
+        session = returned;
+        let error = match result {
+            Ok(value) => match commit(&mut session).await {
+                Ok(()) => return Ok(value),
+                Err(e) => e,
+            },
+            Err(e) => {
+                let _ = session.abort_transaction().await;
+                e
+            },
+        };
+
+        match error {
+            ApiError::ConflictError(_) if attempts < MAX_TRANSACTION_ATTEMPTS => wait_before_retry().await,
+            e => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_key_messages_name_their_index() {
+        let message = "E11000 duplicate key error collection: data_apis.BitStatusList_Issuance index: list_id_1_previous_time_1 dup key: { list_id: 0, previous_time: 5 }";
+        assert_eq!(get_duplicate_key_index(message), Some("list_id_1_previous_time_1"));
+
+        let message = "E11000 duplicate key error collection: data_apis.credentials index: _id_ dup key: { _id: 1 }";
+        assert_eq!(get_duplicate_key_index(message), Some("_id_"));
+        assert_eq!(get_duplicate_key_index("Command failed"), None);
+    }
+}
@@ -1,11 +1,10 @@
 use actix_web::{web::{self, Data, Json}, Responder};
 
-use crate::{errors::ApiResult, utils::AppData};
+use crate::{config::redact_config, errors::ApiResult, utils::AppData};
 
 async fn get_configuration(app_data: Data<AppData>) -> ApiResult<impl Responder> {
     println!("Getting configuration");
-    let config = &app_data.config;
-    Ok(Json(config.clone()))
+    Ok(Json(redact_config(&app_data.config)?))
 }
 
 async fn reset(app_data: Data<AppData>) -> ApiResult<impl Responder> {
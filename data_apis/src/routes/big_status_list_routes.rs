@@ -0,0 +1,92 @@
+use actix_web::{web::{self, Data, Json}, Responder};
+use serde_json::json;
+use zkcdid_lib_rs::models::{request_params::{CredentialIssuanceParams, StatusQueryParams}, status_state::StatusType};
+
+use crate::{big_bit_status_list::{word_to_hex, BIG_BIT_STATUS_LIST}, errors::ApiResult, services::{credential_service::{IssuanceQueryParams, SignedCredential}, verification_service::VerificationQueryParams}, status_list::ListQueryParams, utils::AppData};
+
+async fn issue(issuance_params: web::Json<CredentialIssuanceParams>, query: web::Query<IssuanceQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let credential_service = &app_data.credential_service;
+    let status_service = &app_data.status_service;
+
+    let credential = credential_service.issue_big_credential(&issuance_params.into_inner(), query.valid_until, status_service, &app_data.config).await?;
+    Ok(Json(credential))
+}
+
+async fn verify(presented: web::Json<SignedCredential>, query: web::Query<VerificationQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.verification_service;
+
+    let result = service.verify_big_credential(&presented, query.source, &app_data.status_service).await;
+    Ok(Json(result))
+}
+
+async fn revoke(id: web::Path<String>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let credential_service = &app_data.credential_service;
+    let status_service = &app_data.status_service;
+
+    let credential = credential_service.revoke_big_credential(&id, status_service).await?;
+    Ok(Json(credential))
+}
+
+async fn get_credential(id: web::Path<String>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let credential = app_data.credential_service.get_big_credential_by_id(&id).await?;
+    Ok(Json(credential))
+}
+
+async fn get_all_credentials(app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let credentials = app_data.credential_service.get_all_big_credentials().await?;
+    Ok(Json(credentials))
+}
+
+async fn get_verifiable_credential(id: web::Path<String>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let credential = app_data.credential_service.get_big_verifiable_credential(&id, &app_data.config).await?;
+    Ok(Json(credential))
+}
+
+async fn get_all_statuses(status_type: web::Path<StatusType>, query: web::Query<StatusQueryParams>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let statuses = app_data.status_service.get_big_statuses(&status_type, list.list, &query).await?;
+    Ok(Json(statuses))
+}
+
+async fn get_latest_status(status_type: web::Path<StatusType>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let status = app_data.status_service.get_latest_big_status(&status_type, list.list).await?;
+    Ok(Json(status))
+}
+
+/// The latest status as `abi.encode(uint32, uint256[7])`, which `StatusState.decodeBigBSLStatus` decodes.
+async fn get_latest_encoded_status(status_type: web::Path<StatusType>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let status = app_data.status_service.get_latest_big_status(&status_type, list.list).await?;
+    let encoded: String = status.encode().iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(Json(json!({
+        "list_id": status.list_id,
+        "time": status.time,
+        "data": status.data.iter().map(word_to_hex).collect::<Vec<_>>(),
+        "encoded": format!("0x{}", encoded),
+        "signature": status.signature,
+    })))
+}
+
+async fn get_status_list_credential(status_type: web::Path<StatusType>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let credential = app_data.status_service.get_big_status_list_credential(&status_type, list.list, &app_data.config).await?;
+    Ok(Json(credential))
+}
+
+/// Registered before the routes of the other mechanisms, whose `{status_mechanism}` would match it too.
+pub fn initialize(config: &mut web::ServiceConfig) {
+    config
+        .service(
+            web::scope(&format!("/credentials/{}", BIG_BIT_STATUS_LIST))
+                .route("/{id}", web::delete().to(revoke))
+                .route("", web::post().to(issue))
+                .route("/verify", web::post().to(verify))
+                .route("/{id}", web::get().to(get_credential))
+                .route("/{id}/vc", web::get().to(get_verifiable_credential))
+                .route("", web::get().to(get_all_credentials))
+        )
+        .service(
+            web::scope(&format!("/statuses/{}/{{status_type}}", BIG_BIT_STATUS_LIST))
+                .route("", web::get().to(get_all_statuses))
+                .route("/latest", web::get().to(get_latest_status))
+                .route("/latest/encoded", web::get().to(get_latest_encoded_status))
+                .route("/bitstring", web::get().to(get_status_list_credential))
+        );
+}
@@ -1,16 +1,25 @@
 use actix_web::{web::{self, Data, Json}, Responder};
+use serde_json::json;
 use zkcdid_lib_rs::models::{request_params::CredentialIssuanceParams, status_state::StatusMechanism};
 
-use crate::{errors::ApiResult, utils::AppData};
+use crate::{errors::ApiResult, services::{credential_service::{IssuanceQueryParams, SignedCredential}, verification_service::VerificationQueryParams}, utils::AppData};
 
-async fn issue(status_mechanism: web::Path<StatusMechanism>, issuance_params: web::Json<CredentialIssuanceParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+async fn issue(status_mechanism: web::Path<StatusMechanism>, issuance_params: web::Json<CredentialIssuanceParams>, query: web::Query<IssuanceQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
     let credential_service = &app_data.credential_service;
     let status_service = &app_data.status_service;
 
-    let credential = credential_service.issue_credential(&issuance_params.into_inner(), &status_mechanism, status_service, &app_data.config).await?;
+    let credential = credential_service.issue_credential(&issuance_params.into_inner(), &status_mechanism, query.valid_until, status_service, &app_data.config).await?;
     Ok(Json(credential))
 }
 
+/// Checks a presented credential against the issuer's statuses, or the `StatusRegistry` with `?source=chain`.
+async fn verify(status_mechanism: web::Path<StatusMechanism>, presented: web::Json<SignedCredential>, query: web::Query<VerificationQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.verification_service;
+
+    let result = service.verify_credential(&presented, &status_mechanism, query.source, &app_data.status_service).await;
+    Ok(Json(result))
+}
+
 async fn revoke(params: web::Path<(StatusMechanism, String)>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
     let credential_service = &app_data.credential_service;
     let status_service = &app_data.status_service;
@@ -34,12 +43,32 @@ async fn get_all_credentials(status_mechanism: web::Path<StatusMechanism>, app_d
     Ok(Json(credentials))
 }
 
+async fn get_credential_commitment(params: web::Path<(StatusMechanism, String)>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.credential_service;
+    let (status_mechanism, id) = params.into_inner();
+
+    let commitment = service.get_credential_commitment(&status_mechanism, &id).await?;
+    Ok(Json(json!({ "id": id, "commitment": commitment })))
+}
+
+/// The credential as a W3C VC 2.0 document.
+async fn get_verifiable_credential(params: web::Path<(StatusMechanism, String)>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.credential_service;
+    let (status_mechanism, id) = params.into_inner();
+
+    let credential = service.get_verifiable_credential(&status_mechanism, &id, &app_data.config).await?;
+    Ok(Json(credential))
+}
+
 pub fn initialize(config: &mut web::ServiceConfig) {
     config.service(
         web::scope("/credentials/{status_mechanism}")
             .route("/{id}", web::delete().to(revoke))
             .route("", web::post().to(issue))
+            .route("/verify", web::post().to(verify))
             .route("/{id}", web::get().to(get_credential))
+            .route("/{id}/commitment", web::get().to(get_credential_commitment))
+            .route("/{id}/vc", web::get().to(get_verifiable_credential))
             .route("", web::get().to(get_all_credentials))
     );
 }
\ No newline at end of file
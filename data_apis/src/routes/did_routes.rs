@@ -0,0 +1,18 @@
+use actix_web::{web::{self, Data, Json}, Responder};
+
+use crate::{did::build_did_document, errors::ApiResult, utils::AppData};
+
+/// The `did:web` document of the issuer.
+async fn get_did_document(app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let public_key = app_data.issuer_key_service.get_public_key();
+    let document = build_did_document(&app_data.config.get_api_url(), &public_key)?;
+    Ok(Json(document))
+}
+
+/// `did:web` resolves a DID without a path to `/.well-known/did.json` and one with a path to `<path>/did.json`,
+/// where `<path>` is the path of the api url behind a proxy.
+pub fn initialize(config: &mut web::ServiceConfig) {
+    config
+        .route("/.well-known/did.json", web::get().to(get_did_document))
+        .route("/did.json", web::get().to(get_did_document));
+}
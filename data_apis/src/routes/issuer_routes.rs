@@ -0,0 +1,15 @@
+use actix_web::{web::{self, Data, Json}, Responder};
+
+use crate::{errors::ApiResult, utils::AppData};
+
+/// The public key oracles verify the signatures of status states with.
+async fn get_key(app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    Ok(Json(app_data.issuer_key_service.get_public_key()))
+}
+
+pub fn initialize(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/issuer")
+            .route("/key", web::get().to(get_key))
+    );
+}
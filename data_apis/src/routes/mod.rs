@@ -3,14 +3,20 @@ use actix_web::web;
 pub mod status_routes;
 pub mod credential_routes;
 pub mod app_routes;
+pub mod issuer_routes;
+pub mod big_status_list_routes;
+pub mod did_routes;
 
 pub fn initialize(config: &mut web::ServiceConfig) {
     config.service(
         web::scope("")
             // .wrap(Logger::default())
+            .configure(big_status_list_routes::initialize)
             .configure(credential_routes::initialize)
             .configure(status_routes::initialize)
-            .configure(app_routes::initialize),
+            .configure(app_routes::initialize)
+            .configure(issuer_routes::initialize)
+            .configure(did_routes::initialize),
 
     );
 
@@ -1,22 +1,50 @@
 use actix_web::{web::{self, Data, Json}, Responder};
+use serde_json::json;
 use zkcdid_lib_rs::models::{request_params::StatusQueryParams, status_state::{StatusMechanism, StatusState, StatusType}};
 
-use crate::{errors::ApiResult, utils::AppData};
+use crate::{errors::{ApiError, ApiResult}, status_list::ListQueryParams, utils::AppData};
 
-async fn get_all_statuses(status_mechanism: web::Path<(StatusMechanism, StatusType)>, query: web::Query<StatusQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+async fn get_all_statuses(status_mechanism: web::Path<(StatusMechanism, StatusType)>, query: web::Query<StatusQueryParams>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
     let service = &app_data.status_service;
     let (status_mechanism, status_type) = *status_mechanism;
-    let statuses = service.get_statuses(&status_mechanism, &status_type, &query).await?;
+    let statuses = service.get_statuses(&status_mechanism, &status_type, list.list, &query).await?;
     Ok(Json(statuses))
 }
 
-async fn get_latest_statuses(status_mechanism: web::Path<(StatusMechanism, StatusType)>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+async fn get_latest_statuses(status_mechanism: web::Path<(StatusMechanism, StatusType)>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
     let service = &app_data.status_service;
     let (status_mechanism, status_type) = *status_mechanism;
-    let status = service.get_latest_status(&status_mechanism, &status_type).await?;
+    let status = service.get_latest_status(&status_mechanism, &status_type, list.list).await?;
     Ok(Json(status))
 }
 
+async fn get_status_commitments(status_mechanism: web::Path<(StatusMechanism, StatusType)>, query: web::Query<StatusQueryParams>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.status_service;
+    let (status_mechanism, status_type) = *status_mechanism;
+    let commitments = service.get_status_commitments(&status_mechanism, &status_type, list.list, &query).await?;
+    let commitments: Vec<_> = commitments.into_iter().map(|(time, commitment)| json!({ "time": time, "commitment": commitment })).collect();
+    Ok(Json(commitments))
+}
+
+async fn get_inclusion_path(path: web::Path<(StatusMechanism, StatusType, u64)>, query: web::Query<StatusQueryParams>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.status_service;
+    let (status_mechanism, status_type, index) = *path;
+    if status_mechanism != StatusMechanism::MerkleTree {
+        return Err(ApiError::CommonError(format!("{} statuses have no inclusion paths", status_mechanism)));
+    }
+
+    let path = service.get_inclusion_path(&status_type, list.list, index, query.time).await?;
+    Ok(Json(path))
+}
+
+/// The latest BSL status as a `BitstringStatusListCredential`.
+async fn get_status_list_credential(status_mechanism: web::Path<(StatusMechanism, StatusType)>, list: web::Query<ListQueryParams>, app_data: Data<AppData>) -> ApiResult<impl Responder> {
+    let service = &app_data.status_service;
+    let (status_mechanism, status_type) = *status_mechanism;
+    let credential = service.get_status_list_credential(&status_mechanism, &status_type, list.list, &app_data.config).await?;
+    Ok(Json(credential))
+}
+
 async fn get_sample() -> ApiResult<impl Responder> {
     let sample = StatusState::get_sample_status();
     Ok(Json(sample))
@@ -27,6 +55,9 @@ pub fn initialize(config: &mut web::ServiceConfig) {
         web::scope("/statuses/{status_mechanism}/{status_type}")
             .route("", web::get().to(get_all_statuses))
             .route("/latest", web::get().to(get_latest_statuses))
+            .route("/commitments", web::get().to(get_status_commitments))
+            .route("/paths/{index}", web::get().to(get_inclusion_path))
+            .route("/bitstring", web::get().to(get_status_list_credential))
             .route("/sample", web::get().to(get_sample)),
     );
 }
\ No newline at end of file
@@ -1,30 +1,66 @@
 use std::collections::HashMap;
 
-use bson::{doc, oid::ObjectId};
+use bson::{doc, oid::ObjectId, Document};
 use futures_util::TryStreamExt;
-use mongodb::{Collection, Database};
+use mongodb::{options::ReturnDocument, Client, ClientSession, Collection, Database};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::IntoEnumIterator;
 use url::Url;
 use zkcdid_lib_rs::{config::Config, models::{credential::Credential, request_params::CredentialIssuanceParams, status_state::{StatusMechanism, StatusType}}};
 
-use crate::errors::ApiResult;
+use crate::{big_bit_status_list::{BIG_BIT_STATUS_LIST, BIG_BIT_STATUS_LIST_ID, BIG_BSL_BITS}, did::get_issuer_did, errors::{ApiError, ApiResult}, mongo::run_transaction, status_list::{get_list_capacity, get_mechanism_id, LIST_ID_FIELD, LIST_QUERY_PARAM}, utils::{calculate_credential_commitment, COMMITMENT_FIELD}, w3c::{build_bitstring_status_entry, build_credential_url, build_merkle_tree_status_entry, build_status_list_url, build_verifiable_credential}};
 
-use super::status_service::StatusService;
+use super::{issuer_clock_service::COUNTERS_COLLECTION, issuer_key_service::{get_credential_message, IssuerKeyService}, status_service::StatusService};
 
+/// Fields stored next to the credential documents, see `SignedCredential`.
+pub const VALID_UNTIL_FIELD: &str = "valid_until";
+pub const ISSUER_SIGNATURE_FIELD: &str = "issuer_signature";
+
+/// A credential as its holder gets it at issuance and presents it for verification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedCredential {
+    #[serde(flatten)]
+    pub credential: Credential,
+    /// Credentials stored before lists existed are in list 0.
+    #[serde(default)]
+    pub list_id: u64,
+    /// Unix time from which the credential is expired, none when it does not expire.
+    #[serde(default)]
+    pub valid_until: Option<u64>,
+    /// The issuer's signature of the `get_credential_message` of the credential, none for credentials
+    /// issued before credentials were signed.
+    #[serde(default)]
+    pub issuer_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IssuanceQueryParams {
+    pub valid_until: Option<u64>,
+}
 
 #[derive(Debug, Clone)]
 pub struct CredentialService {
     pub collections: HashMap<StatusMechanism, Collection<Credential>>,
+    /// Credentials with a `BigBitStatusList` status, kept apart since `StatusMechanism` has no variant for it.
+    pub big_collection: Collection<Credential>,
+    /// The position counter of each credential collection, in the issuer's counters.
+    counters: Collection<Document>,
+    client: Client,
+    issuer_key_service: IssuerKeyService,
 }
 
 impl CredentialService {
-    pub fn new(database: &Database) -> Self {
-        let config = Config::load_api_config();
-
+    pub fn new(database: &Database, config: &Config, issuer_key_service: &IssuerKeyService) -> Self {
         Self {
             collections: [
                 (StatusMechanism::BitStatusList, database.collection(&format!("{}_bsl", config.get_credentials_collection_name()))),
                 (StatusMechanism::MerkleTree, database.collection(&format!("{}_merkle", config.get_credentials_collection_name()))),
             ].into_iter().collect(),
+            big_collection: database.collection(&format!("{}_bbsl", config.get_credentials_collection_name())),
+            counters: database.collection(COUNTERS_COLLECTION),
+            client: database.client().clone(),
+            issuer_key_service: issuer_key_service.clone(),
         }
     }
 
@@ -32,29 +68,101 @@ impl CredentialService {
         for collection in self.collections.values() {
             collection.delete_many(doc! {}).await?;
         }
+        self.big_collection.delete_many(doc! {}).await?;
 
         Ok(())
     }
 
     pub async fn reset(&self) -> ApiResult<()> {
         self.delete_all().await?;
+
+        let counter_ids: Vec<String> = self.get_all_collections().map(Self::get_position_counter_id).collect();
+        self.counters.delete_many(doc! { "_id": { "$in": counter_ids } }).await?;
         Ok(())
     }
 
-    pub async fn insert_one(&self, credential: &mut Credential) -> ApiResult<()> {
-        let collection = self.collections.get(&credential.status_mechanism).unwrap();
-        let res = collection.insert_one(&mut *credential).await?;
-        credential.id = res.inserted_id.as_object_id();
+    fn get_all_collections(&self) -> impl Iterator<Item = &Collection<Credential>> {
+        self.collections.values().chain([&self.big_collection])
+    }
+
+    fn get_position_counter_id(collection: &Collection<Credential>) -> String {
+        format!("{}_position", collection.name())
+    }
+
+    /// Starts the position counters after the credentials issued before the counters existed.
+    pub async fn initialize_counters(&self) -> ApiResult<()> {
+        for collection in self.get_all_collections() {
+            if let Some((list_id, index)) = Self::get_last_position_in(collection).await? {
+                self.counters
+                    .update_one(
+                        doc! { "_id": Self::get_position_counter_id(collection) },
+                        doc! { "$setOnInsert": { LIST_ID_FIELD: list_id as i64, "index": index as i64 } },
+                    )
+                    .upsert(true)
+                    .await?;
+            }
+        }
+
         Ok(())
     }
 
-    pub async fn get_credential_by_id(&self, status_mechanism: &StatusMechanism, id: &str) -> ApiResult<Credential> {
+    /// Stores the credential in `session` with its status list, expiry, Poseidon commitment and issuer signature.
+    async fn insert_into(&self, collection: &Collection<Credential>, mechanism_id: u8, mut credential: Credential, list_id: u64, valid_until: Option<u64>, session: &mut ClientSession) -> ApiResult<SignedCredential> {
+        let commitment = calculate_credential_commitment(&credential)?;
+        let issuer_signature = self.issuer_key_service.sign_credential(&get_credential_message(mechanism_id, list_id, valid_until, &commitment));
+
+        let mut document = bson::to_document(&credential)?;
+        document.insert(LIST_ID_FIELD, list_id as i64);
+        if let Some(valid_until) = valid_until {
+            document.insert(VALID_UNTIL_FIELD, valid_until as i64);
+        }
+        document.insert(COMMITMENT_FIELD, commitment);
+        document.insert(ISSUER_SIGNATURE_FIELD, issuer_signature.clone());
+
+        let res = collection.clone_with_type::<Document>().insert_one(document).session(session).await?;
+        credential.id = res.inserted_id.as_object_id();
+
+        Ok(SignedCredential {
+            credential,
+            list_id,
+            valid_until,
+            issuer_signature: Some(issuer_signature),
+        })
+    }
+
+    pub async fn get_credential_commitment(&self, status_mechanism: &StatusMechanism, id: &str) -> ApiResult<Option<String>> {
         let object_id = ObjectId::parse_str(id)?;
+        let collection = self.collections.get(status_mechanism).unwrap().clone_with_type::<Document>();
+        let document = collection.find_one(doc! { "_id": object_id }).await?;
+
+        Ok(document.and_then(|document| document.get_str(COMMITMENT_FIELD).ok().map(|commitment| commitment.to_string())))
+    }
+
+    pub async fn get_credential_by_id(&self, status_mechanism: &StatusMechanism, id: &str) -> ApiResult<Credential> {
         let collection = self.collections.get(&status_mechanism).unwrap();
-        let filter = doc! { "_id": object_id};
-        let credential = collection.find_one(filter).await?;
+        Ok(Self::get_signed_credential(collection, id).await?.credential)
+    }
+
+    async fn get_signed_credential(collection: &Collection<Credential>, id: &str) -> ApiResult<SignedCredential> {
+        let object_id = ObjectId::parse_str(id)?;
+        let document = collection.clone_with_type::<Document>()
+            .find_one(doc! { "_id": object_id })
+            .await?
+            .ok_or(ApiError::CommonError(format!("No credential {}", id)))?;
+
+        // credentials stored before lists existed are in list 0
+        let list_id = document.get_i64(LIST_ID_FIELD).unwrap_or(0) as u64;
+        let valid_until = document.get_i64(VALID_UNTIL_FIELD).ok().map(|valid_until| valid_until as u64);
+        let issuer_signature = document.get_str(ISSUER_SIGNATURE_FIELD).ok().map(|signature| signature.to_string());
+        let credential = bson::from_document(document)
+            .map_err(|e| ApiError::SerializationError(e.to_string()))?;
 
-        Ok(credential.unwrap())
+        Ok(SignedCredential {
+            credential,
+            list_id,
+            valid_until,
+            issuer_signature,
+        })
     }
 
     pub async fn get_all_credentials(&self, status_mechanism: StatusMechanism) -> ApiResult<Vec<Credential>> {
@@ -64,84 +172,223 @@ impl CredentialService {
         Ok(credentials)
     }
 
-    pub async fn get_next_credential_index(&self, status_mechanism: &StatusMechanism) -> ApiResult<u64> {
-        let collection = self.collections.get(&status_mechanism).unwrap();
-        let credential = collection
+    /// The list and index of the last credential stored in `collection`.
+    async fn get_last_position_in(collection: &Collection<Credential>) -> ApiResult<Option<(u64, u64)>> {
+        let document = collection.clone_with_type::<Document>()
             .find_one(doc! {})
-            .sort(doc! { "index": -1 })
+            .sort(doc! { LIST_ID_FIELD: -1, "index": -1 })
             .await?;
 
-        match credential {
-            Some(credential) => {
-                if credential.index == u64::MAX {
-                    return Err("Credential index overflow".into());
-                }
-
-                return Ok(credential.index + 1);
+        match document {
+            Some(document) => {
+                let list_id = document.get_i64(LIST_ID_FIELD).unwrap_or(0) as u64;
+                let credential: Credential = bson::from_document(document)
+                    .map_err(|e| ApiError::SerializationError(e.to_string()))?;
+                Ok(Some((list_id, credential.index)))
             },
-            None => Ok(0),
+            None => Ok(None),
         }
     }
 
-    fn build_status_url(status_mechanism: &StatusMechanism, status_type: &StatusType, base_url: &str) -> ApiResult<String> {
+    /// Takes the list and index of the next credential of `collection` in `session`, starting a new list when
+    /// the last one is full. Concurrent issuances conflict on the counter, and an aborted one gives its position
+    /// back, so positions are never shared nor skipped.
+    async fn reserve_position(&self, collection: &Collection<Credential>, capacity: u64, session: &mut ClientSession) -> ApiResult<(u64, u64)> {
+        let next_index = doc! { "$add": [{ "$ifNull": ["$index", -1i64] }, 1i64] };
+        let is_full = doc! { "$gte": [next_index.clone(), capacity as i64] };
+        let list_id = doc! { "$ifNull": [format!("${}", LIST_ID_FIELD), 0i64] };
+        let update = vec![doc! {
+            "$set": {
+                "index": { "$cond": [is_full.clone(), 0i64, next_index] },
+                LIST_ID_FIELD: { "$cond": [is_full, { "$add": [list_id.clone(), 1i64] }, list_id] },
+            }
+        }];
+
+        let document = self.counters
+            .find_one_and_update(doc! { "_id": Self::get_position_counter_id(collection) }, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .session(session)
+            .await?
+            .ok_or(ApiError::DatabaseError("The position counter was not updated".to_string()))?;
+
+        let list_id = document.get_i64(LIST_ID_FIELD).map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+        let index = document.get_i64("index").map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+        Ok((list_id as u64, index as u64))
+    }
+
+    /// The url of the statuses of `list_id`, `statuses/{mechanism}/{type}?list={list_id}`.
+    fn build_status_url(status_mechanism: &str, status_type: &StatusType, base_url: &str, list_id: u64) -> ApiResult<String> {
         let mut api_url = Url::parse(base_url)?;
         api_url = api_url.join(&format!("statuses/{}/{}", status_mechanism, status_type))?;
-        // api_url = api_url.join(&serde_json::to_string(status_mechanism)?)?;
-        // api_url = api_url.join(&serde_json::to_string(status_type)?)?;
-            // .join("statuses")?
-            // .join(&serde_json::to_string(status_mechanism)?)?
-            // .join(&serde_json::to_string(status_type)?)?;
+        api_url.query_pairs_mut().append_pair(LIST_QUERY_PARAM, &list_id.to_string());
 
         Ok(api_url.to_string())
     }
 
-    pub async fn issue_credential(&self, issuance_params: &CredentialIssuanceParams, status_mechanism: &StatusMechanism, status_service: &StatusService, config: &Config) -> ApiResult<Credential> {
-        // get status url
-        let issuance_status_url = Self::build_status_url(status_mechanism, &StatusType::Issuance, &config.get_api_url())?;
-        let revocation_status_url = Self::build_status_url(status_mechanism, &StatusType::Revocation, &config.get_api_url())?;
-
-        // get the highest index of all credentials
-        let index = self.get_next_credential_index(status_mechanism).await?;
-
-        // get current time
-        // let time = chrono::Utc::now().timestamp() as u64;
-        let time = index + 1;
-
-        // create a new credential
-        let mut credential = Credential::new(
-            &issuance_params.subject,
-            &issuance_params.data,
-            status_mechanism,
-            index,
-            &issuance_status_url,
-            &revocation_status_url,
-            time,
-        );
-        // credential.update_hash_sha256();
-
-        self.insert_one(&mut credential).await?;
-
-        // update issuance status
-        let mut last_issuance_status = status_service.get_latest_status(status_mechanism, &StatusType::Issuance).await?;
-        last_issuance_status.update_index_status(index);
-        last_issuance_status.id = None;
-        last_issuance_status.time = time;
-        last_issuance_status.signature = None;
-        status_service.insert_one(&last_issuance_status).await?;
+    /// Takes a position, stores the credential and appends its issuance status in one transaction, run again
+    /// when another issuance took the position or another write appended to the same status list first.
+    pub async fn issue_credential(&self, issuance_params: &CredentialIssuanceParams, status_mechanism: &StatusMechanism, valid_until: Option<u64>, status_service: &StatusService, config: &Config) -> ApiResult<SignedCredential> {
+        let collection = self.collections.get(status_mechanism).unwrap();
 
-        Ok(credential)
+        run_transaction(&self.client, |mut session| async move {
+            let result: ApiResult<SignedCredential> = async {
+                // the index is the position in its status list
+                let (list_id, index) = self.reserve_position(collection, get_list_capacity(status_mechanism), &mut session).await?;
+                status_service.create_list(status_mechanism, list_id, Some(&mut session)).await?;
+
+                // get status url
+                let issuance_status_url = &Self::build_status_url(&status_mechanism.to_string(), &StatusType::Issuance, &config.get_api_url(), list_id)?;
+                let revocation_status_url = &Self::build_status_url(&status_mechanism.to_string(), &StatusType::Revocation, &config.get_api_url(), list_id)?;
+
+                // one clock for both status types, so that issuance and revocation times never collide
+                let time = status_service.clock.next_time().await?;
+
+                // create a new credential
+                let credential = Credential::new(
+                    &issuance_params.subject,
+                    &issuance_params.data,
+                    status_mechanism,
+                    index,
+                    issuance_status_url,
+                    revocation_status_url,
+                    time,
+                );
+                // credential.update_hash_sha256();
+
+                let credential = self.insert_into(collection, get_mechanism_id(status_mechanism), credential, list_id, valid_until, &mut session).await?;
+
+                // update issuance status
+                match status_mechanism {
+                    StatusMechanism::BitStatusList => status_service.set_status_bit_in_session(&StatusType::Issuance, list_id, index, time, &mut session).await?,
+                    StatusMechanism::MerkleTree => status_service.set_tree_leaf_in_session(&StatusType::Issuance, list_id, index, time, &mut session).await?,
+                };
+
+                Ok(credential)
+            }.await;
+
+            (session, result)
+        }).await
     }
 
     pub async fn revoke_credential(&self, id: &str, status_mechanism: &StatusMechanism, status_service: &StatusService) -> ApiResult<Credential> {
         // get the credential by id
-        let credential = self.get_credential_by_id(status_mechanism, id).await?;
-
-        let mut last_revocation_status = status_service.get_latest_status(status_mechanism, &StatusType::Revocation).await?;
-        last_revocation_status.update_index_status(credential.index);
-        last_revocation_status.id = None;
-        last_revocation_status.time += 1;
-        last_revocation_status.signature = None;
-        status_service.insert_one(&last_revocation_status).await?;
+        let collection = self.collections.get(status_mechanism).unwrap();
+        let SignedCredential { credential, list_id, .. } = Self::get_signed_credential(collection, id).await?;
+        let index = credential.index;
+
+        run_transaction(&self.client, |mut session| async move {
+            let result: ApiResult<()> = async {
+                let time = status_service.clock.next_time().await?;
+                match status_mechanism {
+                    StatusMechanism::BitStatusList => status_service.set_status_bit_in_session(&StatusType::Revocation, list_id, index, time, &mut session).await?,
+                    StatusMechanism::MerkleTree => status_service.set_tree_leaf_in_session(&StatusType::Revocation, list_id, index, time, &mut session).await?,
+                };
+
+                Ok(())
+            }.await;
+
+            (session, result)
+        }).await?;
+
+        Ok(credential)
+    }
+
+    /// The credential as a W3C VC 2.0 document, with a status entry per status type.
+    pub async fn get_verifiable_credential(&self, status_mechanism: &StatusMechanism, id: &str, config: &Config) -> ApiResult<Value> {
+        let collection = self.collections.get(status_mechanism).unwrap();
+        let SignedCredential { credential, list_id, .. } = Self::get_signed_credential(collection, id).await?;
+        let base_url = config.get_api_url();
+        let mechanism = status_mechanism.to_string();
+
+        let mut status_entries = vec![];
+        for status_type in StatusType::iter() {
+            status_entries.push(match status_mechanism {
+                StatusMechanism::BitStatusList => {
+                    let list_url = build_status_list_url(&base_url, &mechanism, &status_type, list_id)?;
+                    build_bitstring_status_entry(&list_url, &status_type, credential.index)
+                },
+                // a tree is not a bitstring, its entry points at the statuses and their inclusion paths
+                StatusMechanism::MerkleTree => {
+                    let status_url = Self::build_status_url(&mechanism, &status_type, &base_url, list_id)?;
+                    build_merkle_tree_status_entry(&status_url, &status_type, credential.index)
+                },
+            });
+        }
+
+        build_verifiable_credential(&credential, &build_credential_url(&base_url, &mechanism, id)?, &get_issuer_did(&base_url)?, status_entries)
+    }
+
+    pub async fn get_big_verifiable_credential(&self, id: &str, config: &Config) -> ApiResult<Value> {
+        let SignedCredential { credential, list_id, .. } = Self::get_signed_credential(&self.big_collection, id).await?;
+        let base_url = config.get_api_url();
+
+        let mut status_entries = vec![];
+        for status_type in StatusType::iter() {
+            let list_url = build_status_list_url(&base_url, BIG_BIT_STATUS_LIST, &status_type, list_id)?;
+            status_entries.push(build_bitstring_status_entry(&list_url, &status_type, credential.index));
+        }
+
+        build_verifiable_credential(&credential, &build_credential_url(&base_url, BIG_BIT_STATUS_LIST, id)?, &get_issuer_did(&base_url)?, status_entries)
+    }
+
+    pub async fn get_big_credential_by_id(&self, id: &str) -> ApiResult<Credential> {
+        Ok(Self::get_signed_credential(&self.big_collection, id).await?.credential)
+    }
+
+    pub async fn get_all_big_credentials(&self) -> ApiResult<Vec<Credential>> {
+        let cursor = self.big_collection.find(doc! {}).await?;
+        let credentials = cursor.try_collect::<Vec<Credential>>().await?;
+        Ok(credentials)
+    }
+
+    /// Issues a credential under a `BigBitStatusList` of 1792 indexes per list. Its `status_mechanism` field is
+    /// `BitStatusList`, the closest `zkcdid_lib_rs` has; the status urls point to the big lists.
+    pub async fn issue_big_credential(&self, issuance_params: &CredentialIssuanceParams, valid_until: Option<u64>, status_service: &StatusService, config: &Config) -> ApiResult<SignedCredential> {
+        run_transaction(&self.client, |mut session| async move {
+            let result: ApiResult<SignedCredential> = async {
+                let (list_id, index) = self.reserve_position(&self.big_collection, BIG_BSL_BITS, &mut session).await?;
+                status_service.create_big_list(list_id, Some(&mut session)).await?;
+
+                let issuance_status_url = &Self::build_status_url(BIG_BIT_STATUS_LIST, &StatusType::Issuance, &config.get_api_url(), list_id)?;
+                let revocation_status_url = &Self::build_status_url(BIG_BIT_STATUS_LIST, &StatusType::Revocation, &config.get_api_url(), list_id)?;
+
+                // the big statuses on chain have a uint32 time
+                let time = status_service.clock.next_big_time().await?;
+
+                let credential = Credential::new(
+                    &issuance_params.subject,
+                    &issuance_params.data,
+                    &StatusMechanism::BitStatusList,
+                    index,
+                    issuance_status_url,
+                    revocation_status_url,
+                    time as u64,
+                );
+
+                let credential = self.insert_into(&self.big_collection, BIG_BIT_STATUS_LIST_ID, credential, list_id, valid_until, &mut session).await?;
+                status_service.set_big_status_bit_in_session(&StatusType::Issuance, list_id, index, time, &mut session).await?;
+
+                Ok(credential)
+            }.await;
+
+            (session, result)
+        }).await
+    }
+
+    pub async fn revoke_big_credential(&self, id: &str, status_service: &StatusService) -> ApiResult<Credential> {
+        let SignedCredential { credential, list_id, .. } = Self::get_signed_credential(&self.big_collection, id).await?;
+        let index = credential.index;
+
+        run_transaction(&self.client, |mut session| async move {
+            let result: ApiResult<()> = async {
+                let time = status_service.clock.next_big_time().await?;
+                status_service.set_big_status_bit_in_session(&StatusType::Revocation, list_id, index, time, &mut session).await?;
+                Ok(())
+            }.await;
+
+            (session, result)
+        }).await?;
 
         Ok(credential)
     }
@@ -0,0 +1,71 @@
+use bson::{doc, Document};
+use mongodb::{options::ReturnDocument, Collection, Database};
+
+use crate::{config::ClockMode, errors::{ApiError, ApiResult}};
+
+/// Collection of the issuer's persistent counters.
+pub const COUNTERS_COLLECTION: &str = "counters";
+const CLOCK_ID: &str = "issuer_clock";
+const TIME_FIELD: &str = "time";
+
+/// One clock for the statuses of every mechanism, type and list, kept in the database so that
+/// times never repeat or go backwards, across restarts and between processes sharing the database.
+#[derive(Debug, Clone)]
+pub struct IssuerClockService {
+    collection: Collection<Document>,
+    mode: ClockMode,
+}
+
+impl IssuerClockService {
+    pub fn new(database: &Database, mode: ClockMode) -> Self {
+        Self {
+            collection: database.collection(COUNTERS_COLLECTION),
+            mode,
+        }
+    }
+
+    /// Moves the clock to at least `time`, the latest time already published.
+    pub async fn advance_to(&self, time: u64) -> ApiResult<()> {
+        self.collection
+            .update_one(doc! { "_id": CLOCK_ID }, doc! { "$max": { TIME_FIELD: time as i64 } })
+            .upsert(true)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The next time, in one atomic update: the last time plus one, or the current UTC second when later.
+    pub async fn next_time(&self) -> ApiResult<u64> {
+        let next = doc! { "$add": [{ "$ifNull": [format!("${}", TIME_FIELD), 0i64] }, 1i64] };
+        let time = match self.mode {
+            ClockMode::Timestamp => doc! { "$max": [next, chrono::Utc::now().timestamp()] },
+            ClockMode::Counter => next,
+        };
+
+        let document = self.collection
+            .find_one_and_update(doc! { "_id": CLOCK_ID }, vec![doc! { "$set": { TIME_FIELD: time } }])
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .ok_or(ApiError::DatabaseError("The issuer clock was not updated".to_string()))?;
+
+        document.get_i64(TIME_FIELD)
+            .map(|time| time as u64)
+            .map_err(|e| ApiError::DatabaseError(e.to_string()))
+    }
+
+    /// The next time for the `uint32` time of big statuses.
+    pub async fn next_big_time(&self) -> ApiResult<u32> {
+        let time = self.next_time().await?;
+        u32::try_from(time).map_err(|_| ApiError::CommonError(format!("Time {} does not fit in a uint32", time)))
+    }
+
+    /// Restarts a counter clock. A timestamp clock keeps its time, which statuses on chain were compared against.
+    pub async fn reset(&self) -> ApiResult<()> {
+        if self.mode == ClockMode::Counter {
+            self.collection.delete_one(doc! { "_id": CLOCK_ID }).await?;
+        }
+
+        Ok(())
+    }
+}
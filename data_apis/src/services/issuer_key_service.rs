@@ -0,0 +1,164 @@
+use std::{path::Path, sync::Arc};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::{rand::SystemRandom, signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519}};
+use serde::Serialize;
+use zkcdid_lib_rs::models::status_state::{StatusState, StatusType};
+
+use crate::{big_bit_status_list::BigStatusState, config::IssuerSettings, errors::{ApiError, ApiResult}, status_list::get_mechanism_id, utils::calculate_sha256_hash};
+
+/// Prefix of every signed status message, so that a signature cannot be replayed for another kind of message.
+const STATUS_SIGNATURE_DOMAIN: &[u8] = b"zkcdid.status-state.v2";
+const BIG_STATUS_SIGNATURE_DOMAIN: &[u8] = b"zkcdid.big-status-state.v2";
+const CREDENTIAL_SIGNATURE_DOMAIN: &[u8] = b"zkcdid.credential.v1";
+const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
+/// The issuer's public key as published on `/issuer/key`.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuerPublicKey {
+    pub algorithm: String,
+    /// Hex of the first 8 bytes of the SHA-256 of the public key.
+    pub key_id: String,
+    /// Base64url without padding.
+    pub public_key: String,
+}
+
+/// Canonical encoding of a status state: the domain, the mechanism and type as one byte each,
+/// then the list id, the time and the status as big-endian u64.
+///
+/// `zk_oracles::utils::status_signature` must encode it the same way.
+pub fn get_status_message(status: &StatusState, list_id: u64) -> Vec<u8> {
+    let mut message = STATUS_SIGNATURE_DOMAIN.to_vec();
+    message.push(get_mechanism_id(&status.status_mechanism));
+    message.push(get_status_type_byte(&status.status_type));
+    message.extend_from_slice(&list_id.to_be_bytes());
+    message.extend_from_slice(&status.time.to_be_bytes());
+    message.extend_from_slice(&status.status.to_be_bytes());
+    message
+}
+
+fn get_status_type_byte(status_type: &StatusType) -> u8 {
+    match status_type {
+        StatusType::Issuance => 0,
+        StatusType::Revocation => 1,
+    }
+}
+
+/// The big status domain, the type as one byte, the list id as big-endian u64, then the ABI encoding of the status.
+pub fn get_big_status_message(status: &BigStatusState) -> Vec<u8> {
+    let mut message = BIG_STATUS_SIGNATURE_DOMAIN.to_vec();
+    message.push(get_status_type_byte(&status.status_type));
+    message.extend_from_slice(&status.list_id.to_be_bytes());
+    message.extend_from_slice(&status.encode());
+    message
+}
+
+/// The credential domain, the mechanism as one byte, the list id and the expiry as big-endian u64
+/// (0 when the credential does not expire), then the decimal Poseidon commitment of the credential.
+///
+/// The commitment covers the subject, data and index, so the signature binds all of them to the list.
+pub fn get_credential_message(mechanism_id: u8, list_id: u64, valid_until: Option<u64>, commitment: &str) -> Vec<u8> {
+    let mut message = CREDENTIAL_SIGNATURE_DOMAIN.to_vec();
+    message.push(mechanism_id);
+    message.extend_from_slice(&list_id.to_be_bytes());
+    message.extend_from_slice(&valid_until.unwrap_or(0).to_be_bytes());
+    message.extend_from_slice(commitment.as_bytes());
+    message
+}
+
+/// Signs every status state and credential the issuer publishes.
+#[derive(Debug, Clone)]
+pub struct IssuerKeyService {
+    key_pair: Arc<Ed25519KeyPair>,
+}
+
+impl IssuerKeyService {
+    /// Loads the key, creating it on first start.
+    pub fn load_or_create(settings: &IssuerSettings) -> ApiResult<Self> {
+        let path = Path::new(&settings.key_path);
+        if !path.exists() {
+            println!("Creating the issuer key at {:?}...", path);
+            let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| ApiError::ConfigError("Cannot generate the issuer key".to_string()))?;
+            Self::write_key(path, document.as_ref())?;
+        }
+
+        let document = std::fs::read(path)
+            .map_err(|e| ApiError::ConfigError(format!("Cannot read the issuer key {:?}: {}", path, e)))?;
+        let key_pair = Ed25519KeyPair::from_pkcs8(&document)
+            .map_err(|e| ApiError::ConfigError(format!("Invalid issuer key {:?}: {}", path, e)))?;
+
+        Ok(Self {
+            key_pair: Arc::new(key_pair),
+        })
+    }
+
+    fn write_key(path: &Path, document: &[u8]) -> ApiResult<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path)
+            .map_err(|e| ApiError::ConfigError(format!("Cannot create the issuer key {:?}: {}", path, e)))?;
+        std::io::Write::write_all(&mut file, document)
+            .map_err(|e| ApiError::ConfigError(format!("Cannot write the issuer key {:?}: {}", path, e)))
+    }
+
+    pub fn get_public_key(&self) -> IssuerPublicKey {
+        let public_key = self.key_pair.public_key().as_ref();
+
+        IssuerPublicKey {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            key_id: calculate_sha256_hash(public_key)[..8].iter().map(|byte| format!("{:02x}", byte)).collect(),
+            public_key: URL_SAFE_NO_PAD.encode(public_key),
+        }
+    }
+
+    /// Sets the signature of `status` in list `list_id` over its canonical encoding, replacing any previous one.
+    pub fn sign_status(&self, status: &mut StatusState, list_id: u64) {
+        let signature = self.key_pair.sign(&get_status_message(status, list_id));
+        status.signature = Some(URL_SAFE_NO_PAD.encode(signature.as_ref()));
+    }
+
+    pub fn sign_big_status(&self, status: &mut BigStatusState) {
+        let signature = self.key_pair.sign(&get_big_status_message(status));
+        status.signature = Some(URL_SAFE_NO_PAD.encode(signature.as_ref()));
+    }
+
+    /// The base64url signature of a `get_credential_message`.
+    pub fn sign_credential(&self, message: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(self.key_pair.sign(message).as_ref())
+    }
+
+    /// Whether `signature` is the issuer's base64url signature of `message`.
+    pub fn is_signed_by_issuer(&self, message: &[u8], signature: &str) -> bool {
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+            return false;
+        };
+
+        UnparsedPublicKey::new(&ED25519, self.key_pair.public_key().as_ref())
+            .verify(message, &signature)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zkcdid_lib_rs::models::status_state::StatusMechanism;
+
+    use super::*;
+
+    /// The revocation status 0b1011 at time 5 of list 2 of a Merkle tree issuer. `zk_oracles::utils::status_signature`
+    /// asserts the same bytes, so that the oracles verify what the issuer signs.
+    const STATUS_MESSAGE: &[u8] = b"zkcdid.status-state.v2\x01\x01\0\0\0\0\0\0\0\x02\0\0\0\0\0\0\0\x05\0\0\0\0\0\0\0\x0b";
+
+    #[test]
+    fn status_messages_match_the_shared_vector() {
+        let mut status = StatusState::get_initial_status(StatusMechanism::MerkleTree, StatusType::Revocation);
+        status.time = 5;
+        status.status = 0b1011;
+
+        assert_eq!(get_status_message(&status, 2), STATUS_MESSAGE);
+    }
+}
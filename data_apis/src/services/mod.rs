@@ -1,2 +1,6 @@
 pub mod status_service;
-pub mod credential_service;
\ No newline at end of file
+pub mod credential_service;
+pub mod issuer_key_service;
+pub mod status_registry_service;
+pub mod verification_service;
+pub mod issuer_clock_service;
\ No newline at end of file
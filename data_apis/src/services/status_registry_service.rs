@@ -0,0 +1,189 @@
+use alloy::{primitives::{Address, U256}, providers::{ProviderBuilder, RootProvider}, sol, transports::http::{Client, Http}};
+use url::Url;
+use zkcdid_lib_rs::models::status_state::StatusType;
+
+use crate::{big_bit_status_list::BigStatusState, config::IssuerSettings, errors::{ApiError, ApiResult}, status_list::LIST_QUERY_PARAM};
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface StatusRegistry {
+        struct BSLStatus {
+            uint64 time;
+            uint64 status;
+        }
+
+        struct BigBSLStatus {
+            uint32 time;
+            uint256[7] data;
+        }
+
+        struct MTStatus {
+            uint32 time;
+            uint32 height;
+            uint256 data;
+        }
+
+        function getIssuerIdByList(string calldata url, uint8 statusMechanism) external view returns (uint8);
+        function getBSLStatus(uint8 issuerId, uint8 statusType) external view returns (BSLStatus memory);
+        function getBigBSLStatus(uint8 issuerId, uint8 statusType) external view returns (BigBSLStatus memory);
+        function getNumMTStatuses(uint8 issuerId, uint8 statusType) external view returns (uint256);
+        function getMTStatus(uint8 issuerId, uint8 statusType) external view returns (MTStatus memory);
+    }
+}
+
+type StatusRegistryContract = StatusRegistry::StatusRegistryInstance<Http<Client>, RootProvider<Http<Client>>>;
+
+/// Reads this issuer's statuses from the `StatusRegistry`, which holds one status per `IssuerId` and type:
+/// each status list is registered under its own `IssuerId`, found from the list url.
+#[derive(Debug, Clone)]
+pub struct StatusRegistryService {
+    rpc_url: Url,
+    address: Address,
+    registry_url: String,
+}
+
+impl StatusRegistryService {
+    /// `None` when the issuer settings have no `StatusRegistry`.
+    pub fn new(settings: &IssuerSettings) -> ApiResult<Option<Self>> {
+        let (Some(rpc_url), Some(address), Some(registry_url)) = (&settings.status_registry_rpc_url, &settings.status_registry_address, &settings.registry_url) else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            rpc_url: Url::parse(rpc_url)?,
+            address: address.parse()
+                .map_err(|_| ApiError::ConfigError(format!("Invalid StatusRegistry address {:?}", address)))?,
+            registry_url: registry_url.trim_end_matches('/').to_string(),
+        }))
+    }
+
+    /// The urls list `list_id` may be registered under, `<registry_url>?list=<list id>` first. List 0 may also be
+    /// registered under the bare url, which the oracles read as list 0 too.
+    pub fn get_list_urls(&self, list_id: u64) -> Vec<String> {
+        let list_url = format!("{}?{}={}", self.registry_url, LIST_QUERY_PARAM, list_id);
+        match list_id {
+            0 => vec![list_url, self.registry_url.clone()],
+            _ => vec![list_url],
+        }
+    }
+
+    /// The `IssuerId` list `list_id` of the mechanism `mechanism_id` (`StatusState.StatusMechanism`) is registered under.
+    pub async fn get_issuer_id(&self, mechanism_id: u8, list_id: u64) -> ApiResult<u8> {
+        let contract = self.get_contract();
+        let mut result = Err(ApiError::CommonError(format!("List {} is not registered in the StatusRegistry", list_id)));
+
+        for url in self.get_list_urls(list_id) {
+            result = contract.getIssuerIdByList(url, mechanism_id)
+                .call()
+                .await
+                .map(|issuer_id| issuer_id._0)
+                .map_err(Self::map_call_error);
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// `StatusState.StatusType`, where 0 is `Invalid`.
+    fn get_registry_status_type(status_type: &StatusType) -> u8 {
+        match status_type {
+            StatusType::Issuance => 1,
+            StatusType::Revocation => 2,
+        }
+    }
+
+    fn get_contract(&self) -> StatusRegistryContract {
+        let provider = ProviderBuilder::new().on_http(self.rpc_url.clone());
+        StatusRegistry::new(self.address, provider)
+    }
+
+    fn map_call_error(error: alloy::contract::Error) -> ApiError {
+        ApiError::CommonError(format!("StatusRegistry call failed: {}", error))
+    }
+
+    /// The on-chain BSL status as `(time, status)`.
+    pub async fn get_bsl_status(&self, issuer_id: u8, status_type: &StatusType) -> ApiResult<(u64, u64)> {
+        let status = self.get_contract()
+            .getBSLStatus(issuer_id, Self::get_registry_status_type(status_type))
+            .call()
+            .await
+            .map_err(Self::map_call_error)?
+            ._0;
+
+        Ok((status.time, status.status))
+    }
+
+    pub async fn get_big_bsl_status(&self, issuer_id: u8, status_type: &StatusType, list_id: u64) -> ApiResult<BigStatusState> {
+        let status = self.get_contract()
+            .getBigBSLStatus(issuer_id, Self::get_registry_status_type(status_type))
+            .call()
+            .await
+            .map_err(Self::map_call_error)?
+            ._0;
+
+        let mut big_status = BigStatusState::get_initial_status(*status_type, list_id);
+        big_status.time = status.time;
+        for (word, data) in big_status.data.iter_mut().zip(status.data.iter()) {
+            *word = data.to_be_bytes::<32>();
+        }
+
+        Ok(big_status)
+    }
+
+    /// The latest on-chain Merkle-tree status as `(time, root)`, `None` before the first one.
+    pub async fn get_latest_mt_status(&self, issuer_id: u8, status_type: &StatusType) -> ApiResult<Option<(u64, U256)>> {
+        let contract = self.get_contract();
+        let registry_status_type = Self::get_registry_status_type(status_type);
+
+        // getMTStatus reverts on an empty history
+        let count = contract.getNumMTStatuses(issuer_id, registry_status_type)
+            .call()
+            .await
+            .map_err(Self::map_call_error)?
+            ._0;
+        if count.is_zero() {
+            return Ok(None);
+        }
+
+        let status = contract.getMTStatus(issuer_id, registry_status_type)
+            .call()
+            .await
+            .map_err(Self::map_call_error)?
+            ._0;
+
+        Ok(Some((status.time as u64, status.data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_service(registry_url: &str) -> StatusRegistryService {
+        let settings = IssuerSettings {
+            status_registry_rpc_url: Some("http://localhost:8545".to_string()),
+            status_registry_address: Some("0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string()),
+            registry_url: Some(registry_url.to_string()),
+            ..Default::default()
+        };
+
+        StatusRegistryService::new(&settings).unwrap().unwrap()
+    }
+
+    #[test]
+    fn credentials_of_list_1_are_looked_up_under_their_list_url() {
+        let service = get_service("http://localhost:3000/");
+
+        assert_eq!(service.get_list_urls(1), vec!["http://localhost:3000?list=1".to_string()]);
+    }
+
+    #[test]
+    fn list_0_may_be_registered_under_the_bare_url() {
+        let service = get_service("http://localhost:3000");
+
+        assert_eq!(service.get_list_urls(0), vec!["http://localhost:3000?list=0".to_string(), "http://localhost:3000".to_string()]);
+    }
+}
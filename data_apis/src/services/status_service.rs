@@ -1,16 +1,31 @@
-use bson::{doc, Bson};
+use bson::{doc, Bson, Document};
 use futures_util::TryStreamExt;
-use mongodb::{Collection, Database};
+use mongodb::{options::IndexOptions, ClientSession, Collection, Database, IndexModel};
 use strum::IntoEnumIterator;
+use serde_json::Value;
 use zkcdid_lib_rs::{config::Config, models::{request_params::StatusQueryParams, status_state::{StatusMechanism, StatusState, StatusType}}};
 use std::collections::HashMap;
 
-use crate::errors::ApiResult;
+use crate::{big_bit_status_list::{BigStatusState, BIG_BIT_STATUS_LIST, BIG_BSL_BITS}, did::get_issuer_did, errors::{ApiError, ApiResult}, merkle_tree::{MerklePath, MerkleTree, MERKLE_TREE_HEIGHT}, status_list::{get_list_filter, LIST_ID_FIELD}, utils::{calculate_big_status_commitment, calculate_status_commitment, COMMITMENT_FIELD}, w3c::{build_status_list_credential, build_status_list_url, get_bsl_indexes}};
+
+use super::{issuer_clock_service::IssuerClockService, issuer_key_service::IssuerKeyService};
+
+/// The indexes set in the tree of a Merkle-tree status, stored next to its root to rebuild the tree.
+pub const MERKLE_LEAVES_FIELD: &str = "merkle_leaves";
+/// The time of the status a status was appended to. At most one status of a list follows each one.
+pub const PREVIOUS_TIME_FIELD: &str = "previous_time";
+/// `PREVIOUS_TIME_FIELD` of the first status of a list, so that a list is created once.
+const FIRST_PREVIOUS_TIME: i64 = -1;
+/// The unique `(LIST_ID_FIELD, PREVIOUS_TIME_FIELD)` index, named as MongoDB names it by default.
+pub const APPEND_INDEX_NAME: &str = "list_id_1_previous_time_1";
 
 
 #[derive(Debug, Clone)]
 pub struct StatusService {
     pub collections: HashMap<(StatusMechanism, StatusType), Collection<StatusState>>,
+    pub big_collections: HashMap<StatusType, Collection<BigStatusState>>,
+    issuer_key_service: IssuerKeyService,
+    pub clock: IssuerClockService,
 }
 
 impl StatusService {
@@ -18,10 +33,13 @@ impl StatusService {
         format!("{:?}_{:?}_{:?}",serde_json::to_string(status_type), collection_name, serde_json::to_string(status_mechanism))
     }
 
-    pub fn new(database: &Database) -> Self {
-        let mut collections = HashMap::new();
-        let config = Config::load_api_config();
+    fn get_big_collection_name(collection_name: &str, status_type: &StatusType) -> String {
+        format!("{:?}_{:?}_{:?}",serde_json::to_string(status_type), collection_name, serde_json::to_string(BIG_BIT_STATUS_LIST))
+    }
 
+    pub fn new(database: &Database, config: &Config, issuer_key_service: &IssuerKeyService, clock: &IssuerClockService) -> Self {
+        let mut collections = HashMap::new();
+        let mut big_collections = HashMap::new();
 
         for status_mechanism in StatusMechanism::iter() {
             for status_type in StatusType::iter() {
@@ -31,13 +49,22 @@ impl StatusService {
             }
         }
 
+        for status_type in StatusType::iter() {
+            let collection_name = Self::get_big_collection_name(config.get_statuses_collection_name(), &status_type);
+            big_collections.insert(status_type, database.collection(&collection_name));
+        }
+
         Self {
-            collections
+            collections,
+            big_collections,
+            issuer_key_service: issuer_key_service.clone(),
+            clock: clock.clone(),
         }
     }
 
     pub async fn reset(&self) -> ApiResult<()> {
         self.delete_all().await?;
+        self.clock.reset().await?;
         self.insert_first_status().await?;
         Ok(())
     }
@@ -48,13 +75,87 @@ impl StatusService {
         Ok(())
     }
 
+    /// Makes the status lists append-only chains: a status appended after one that already has a
+    /// successor is rejected, whatever the number of concurrent writers. Statuses stored before
+    /// chains existed have no previous time and are left out.
+    pub async fn ensure_indexes(&self) -> ApiResult<()> {
+        let index = IndexModel::builder()
+            .keys(doc! { LIST_ID_FIELD: 1, PREVIOUS_TIME_FIELD: 1 })
+            .options(IndexOptions::builder()
+                .name(APPEND_INDEX_NAME.to_string())
+                .unique(true)
+                .partial_filter_expression(doc! { PREVIOUS_TIME_FIELD: { "$exists": true } })
+                .build())
+            .build();
+
+        for collection in self.collections.values() {
+            collection.create_index(index.clone()).await?;
+        }
+        for collection in self.big_collections.values() {
+            collection.create_index(index.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the issuer clock past every published status, including those stamped before it existed.
+    pub async fn initialize_clock(&self) -> ApiResult<()> {
+        let mut latest_time = 0;
+        for collection in self.collections.values() {
+            if let Some(status) = collection.find_one(doc! {}).sort(doc! { "time": -1 }).await? {
+                latest_time = latest_time.max(status.time);
+            }
+        }
+        for collection in self.big_collections.values() {
+            if let Some(status) = collection.find_one(doc! {}).sort(doc! { "time": -1 }).await? {
+                latest_time = latest_time.max(status.time as u64);
+            }
+        }
+
+        self.clock.advance_to(latest_time).await
+    }
+
     pub async fn insert_first_status(&self) -> ApiResult<()> {
-        // create the first status
-        for status_type in StatusType::iter() {
-            let first_status = StatusState::get_initial_status(StatusMechanism::BitStatusList, status_type);
-            self.insert_one(&first_status).await?;
+        // create the first list of every mechanism
+        for status_mechanism in StatusMechanism::iter() {
+            self.create_list(&status_mechanism, 0, None).await?;
         }
+        self.create_big_list(0, None).await?;
+
+        Ok(())
+    }
 
+    /// Publishes the first statuses of a list, unless it already has some. In `session`, a list a concurrent
+    /// transaction created first is a `ConflictError`, since the duplicate aborted the transaction.
+    pub async fn create_list(&self, status_mechanism: &StatusMechanism, list_id: u64, mut session: Option<&mut ClientSession>) -> ApiResult<()> {
+        for status_type in StatusType::iter() {
+            let collection = self.get_collection(status_mechanism, &status_type)?;
+            let find = collection.find_one(get_list_filter(list_id));
+            let status = match session.as_deref_mut() {
+                Some(session) => find.session(session).await?,
+                None => find.await?,
+            };
+            if status.is_some() {
+                continue;
+            }
+
+            let mut first_status = StatusState::get_initial_status(*status_mechanism, status_type);
+            let fields = match status_mechanism {
+                StatusMechanism::BitStatusList => Document::new(),
+                StatusMechanism::MerkleTree => {
+                    // an empty tree, whose root is 0
+                    let tree = MerkleTree::new(MERKLE_TREE_HEIGHT);
+                    first_status.status = tree.get_root_status()?;
+                    doc! { MERKLE_LEAVES_FIELD: Self::get_leaves_bson(&tree) }
+                },
+            };
+
+            match self.insert_with_fields(&first_status, list_id, FIRST_PREVIOUS_TIME, fields, session.as_deref_mut()).await {
+                // a concurrent issuance created the list first
+                Err(ApiError::ConflictError(_)) if session.is_none() => {},
+                result => result?,
+            }
+        }
 
         Ok(())
     }
@@ -66,34 +167,265 @@ impl StatusService {
         }
     }
 
-    pub async fn insert_one(&self, status: &StatusState) -> ApiResult<()> {
-        let collection = self.get_collection(&status.status_mechanism, &status.status_type)?;
-        collection.insert_one(status).await?;
+    /// Publishes the status after the one at `previous_time`, signed with the issuer key and along with its
+    /// Poseidon commitment. Fails with a `ConflictError` when another status was appended after that one first.
+    async fn insert_with_fields(&self, status: &StatusState, list_id: u64, previous_time: i64, fields: Document, session: Option<&mut ClientSession>) -> ApiResult<()> {
+        let collection = self.get_collection(&status.status_mechanism, &status.status_type)?.clone_with_type::<Document>();
+        let mut status = status.clone();
+        self.issuer_key_service.sign_status(&mut status, list_id);
+
+        let mut document = bson::to_document(&status)?;
+        document.insert(LIST_ID_FIELD, list_id as i64);
+        document.insert(PREVIOUS_TIME_FIELD, previous_time);
+        document.insert(COMMITMENT_FIELD, calculate_status_commitment(&status)?);
+        document.extend(fields);
+
+        let insert = collection.insert_one(document);
+        match session {
+            Some(session) => insert.session(session).await?,
+            None => insert.await?,
+        };
+        Ok(())
+    }
+
+    /// A status at `time` cannot follow one at `previous_time` or later, which a concurrent issuance appended since `time` was taken.
+    fn check_append_time(previous_time: u64, time: u64) -> ApiResult<()> {
+        if previous_time >= time {
+            return Err(ApiError::ConflictError(format!("Status at time {} is already followed by one at time {}", time, previous_time)));
+        }
+
         Ok(())
     }
 
-    pub async fn get_statuses(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, query: &StatusQueryParams) -> ApiResult<Vec<StatusState>> {
+    /// Sets the bit of `index` in the latest BSL status of the list and appends the result at `time`, in `session`.
+    pub async fn set_status_bit_in_session(&self, status_type: &StatusType, list_id: u64, index: u64, time: u64, session: &mut ClientSession) -> ApiResult<StatusState> {
+        let mut status = self.find_latest_status(&StatusMechanism::BitStatusList, status_type, list_id, Some(&mut *session)).await?;
+        let previous_time = status.time;
+        Self::check_append_time(previous_time, time)?;
+
+        status.update_index_status(index);
+        status.id = None;
+        status.time = time;
+        self.insert_with_fields(&status, list_id, previous_time as i64, Document::new(), Some(session)).await?;
+        Ok(status)
+    }
+
+    fn get_leaves_bson(tree: &MerkleTree) -> Bson {
+        Bson::Array(tree.get_set_indexes().into_iter().map(|index| Bson::Int64(index as i64)).collect())
+    }
+
+    /// The tree of the latest Merkle-tree status of the list at or before `time`, or of the latest one when `time` is `None`.
+    pub async fn get_tree(&self, status_type: &StatusType, list_id: u64, time: Option<u64>) -> ApiResult<(StatusState, MerkleTree)> {
+        self.find_tree(status_type, list_id, time, None).await
+    }
+
+    async fn find_tree(&self, status_type: &StatusType, list_id: u64, time: Option<u64>, session: Option<&mut ClientSession>) -> ApiResult<(StatusState, MerkleTree)> {
+        let collection = self.get_collection(&StatusMechanism::MerkleTree, status_type)?.clone_with_type::<Document>();
+        let mut filter = get_list_filter(list_id);
+        if let Some(time) = time {
+            filter.insert("time", doc! { "$lte": Bson::Int64(time as i64) });
+        }
+        let find = collection
+            .find_one(filter)
+            .sort(doc! { "time": -1 });
+        let document = match session {
+            Some(session) => find.session(session).await?,
+            None => find.await?,
+        };
+        let document = document
+            .ok_or(ApiError::CommonError(format!("No {} tree in list {} at time {:?}", status_type, list_id, time)))?;
+
+        let indexes = document.get_array(MERKLE_LEAVES_FIELD)
+            .map_err(|e| ApiError::SerializationError(e.to_string()))?
+            .iter()
+            .map(|index| index.as_i64().map(|index| index as u64).ok_or(ApiError::SerializationError(format!("Invalid {} {}", MERKLE_LEAVES_FIELD, index))))
+            .collect::<ApiResult<Vec<u64>>>()?;
+        let tree = MerkleTree::from_set_indexes(MERKLE_TREE_HEIGHT, &indexes)?;
+        let status = bson::from_document(document)
+            .map_err(|e| ApiError::SerializationError(e.to_string()))?;
+
+        Ok((status, tree))
+    }
+
+    /// Sets the leaf of `index` in the latest tree and appends the new root as a status at `time`, in `session`.
+    pub async fn set_tree_leaf_in_session(&self, status_type: &StatusType, list_id: u64, index: u64, time: u64, session: &mut ClientSession) -> ApiResult<StatusState> {
+        let (mut status, mut tree) = self.find_tree(status_type, list_id, None, Some(&mut *session)).await?;
+        let previous_time = status.time;
+        Self::check_append_time(previous_time, time)?;
+        tree.set(index)?;
+
+        status.id = None;
+        status.time = time;
+        status.status = tree.get_root_status()?;
+        self.insert_with_fields(&status, list_id, previous_time as i64, doc! { MERKLE_LEAVES_FIELD: Self::get_leaves_bson(&tree) }, Some(session)).await?;
+        Ok(status)
+    }
+
+    /// The inclusion path of `index` in the tree at `time`, or in the latest tree when `time` is `None`.
+    pub async fn get_inclusion_path(&self, status_type: &StatusType, list_id: u64, index: u64, time: Option<u64>) -> ApiResult<MerklePath> {
+        let (_, tree) = self.get_tree(status_type, list_id, time).await?;
+        tree.get_path(index)
+    }
+
+    pub async fn get_statuses(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, list_id: u64, query: &StatusQueryParams) -> ApiResult<Vec<StatusState>> {
         let start_time = Bson::Int64(query.time.unwrap_or(0) as i64);
         let collection = self.get_collection(status_mechanism, status_type)?;
+        let mut filter = get_list_filter(list_id);
+        filter.insert("time", doc! { "$gte": start_time });
         let cursor = collection
-            .find(doc! {
-               "time": doc! { "$gte": start_time }
-            })
+            .find(filter)
             .await?;
         let statuses = cursor.try_collect::<Vec<StatusState>>().await?;
         Ok(statuses)
     }
 
-    pub async fn get_latest_status(&self, status_mechanism: &StatusMechanism, status_type: &StatusType) -> ApiResult<StatusState> {
+    pub async fn get_latest_status(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, list_id: u64) -> ApiResult<StatusState> {
+        self.find_latest_status(status_mechanism, status_type, list_id, None).await
+    }
+
+    /// The latest status of the list, as `session` sees it when there is one.
+    async fn find_latest_status(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, list_id: u64, session: Option<&mut ClientSession>) -> ApiResult<StatusState> {
         // Find the document with the highest time value
         let collection = self.get_collection(status_mechanism, status_type)?;
-        let status = collection
-            .find_one(doc! {})
-            .sort(doc! { "time": -1 })
-            .await?;
+        let find = collection
+            .find_one(get_list_filter(list_id))
+            .sort(doc! { "time": -1 });
+        let status = match session {
+            Some(session) => find.session(session).await?,
+            None => find.await?,
+        };
 
         // Return the document if it exists
-        Ok(status.unwrap())
+        status.ok_or(ApiError::CommonError(format!("No {} {} status in list {}", status_mechanism, status_type, list_id)))
+    }
+
+    /// The commitments of the statuses from `query.time` on, as `(time, commitment)`.
+    pub async fn get_status_commitments(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, list_id: u64, query: &StatusQueryParams) -> ApiResult<Vec<(u64, String)>> {
+        let start_time = Bson::Int64(query.time.unwrap_or(0) as i64);
+        let collection = self.get_collection(status_mechanism, status_type)?.clone_with_type::<Document>();
+        let mut filter = get_list_filter(list_id);
+        filter.insert("time", doc! { "$gte": start_time });
+        let cursor = collection
+            .find(filter)
+            .sort(doc! { "time": 1 })
+            .await?;
+        let documents = cursor.try_collect::<Vec<Document>>().await?;
+
+        Ok(documents.into_iter().filter_map(|document| {
+            let commitment = document.get_str(COMMITMENT_FIELD).ok()?.to_string();
+            let status: StatusState = bson::from_document(document).ok()?;
+            Some((status.time, commitment))
+        }).collect())
+    }
+
+    /// The latest BSL status of a list as a `BitstringStatusListCredential`.
+    pub async fn get_status_list_credential(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, list_id: u64, config: &Config) -> ApiResult<Value> {
+        if *status_mechanism != StatusMechanism::BitStatusList {
+            return Err(ApiError::CommonError(format!("{} statuses are not bitstrings", status_mechanism)));
+        }
+
+        let status = self.get_latest_status(status_mechanism, status_type, list_id).await?;
+        let base_url = config.get_api_url();
+        let url = build_status_list_url(&base_url, &status_mechanism.to_string(), status_type, list_id)?;
+        build_status_list_credential(&url, &get_issuer_did(&base_url)?, status_type, &get_bsl_indexes(status.status), u64::BITS as u64)
+    }
+
+    pub async fn get_big_status_list_credential(&self, status_type: &StatusType, list_id: u64, config: &Config) -> ApiResult<Value> {
+        let status = self.get_latest_big_status(status_type, list_id).await?;
+        let base_url = config.get_api_url();
+        let url = build_status_list_url(&base_url, BIG_BIT_STATUS_LIST, status_type, list_id)?;
+        build_status_list_credential(&url, &get_issuer_did(&base_url)?, status_type, &status.get_set_indexes(), BIG_BSL_BITS)
+    }
+
+    fn get_big_collection(&self, status_type: &StatusType) -> ApiResult<&Collection<BigStatusState>> {
+        match self.big_collections.get(status_type) {
+            Some(collection) => Ok(collection),
+            None => Err("Collection not found".into())
+        }
+    }
+
+    /// Publishes the first statuses of a big list, unless it already has some, like `create_list`.
+    pub async fn create_big_list(&self, list_id: u64, mut session: Option<&mut ClientSession>) -> ApiResult<()> {
+        for status_type in StatusType::iter() {
+            let collection = self.get_big_collection(&status_type)?;
+            let find = collection.find_one(get_list_filter(list_id));
+            let status = match session.as_deref_mut() {
+                Some(session) => find.session(session).await?,
+                None => find.await?,
+            };
+            if status.is_some() {
+                continue;
+            }
+
+            match self.insert_big_status(&BigStatusState::get_initial_status(status_type, list_id), FIRST_PREVIOUS_TIME, session.as_deref_mut()).await {
+                // a concurrent issuance created the list first
+                Err(ApiError::ConflictError(_)) if session.is_none() => {},
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publishes the big status after the one at `previous_time`, signed with the issuer key and along with its Poseidon commitment.
+    async fn insert_big_status(&self, status: &BigStatusState, previous_time: i64, session: Option<&mut ClientSession>) -> ApiResult<()> {
+        let collection = self.get_big_collection(&status.status_type)?.clone_with_type::<Document>();
+        let mut status = status.clone();
+        self.issuer_key_service.sign_big_status(&mut status);
+
+        let mut document = bson::to_document(&status)?;
+        document.insert(PREVIOUS_TIME_FIELD, previous_time);
+        document.insert(COMMITMENT_FIELD, calculate_big_status_commitment(&status)?);
+
+        let insert = collection.insert_one(document);
+        match session {
+            Some(session) => insert.session(session).await?,
+            None => insert.await?,
+        };
+        Ok(())
+    }
+
+    pub async fn get_big_statuses(&self, status_type: &StatusType, list_id: u64, query: &StatusQueryParams) -> ApiResult<Vec<BigStatusState>> {
+        let start_time = Bson::Int64(query.time.unwrap_or(0) as i64);
+        let collection = self.get_big_collection(status_type)?;
+        let mut filter = get_list_filter(list_id);
+        filter.insert("time", doc! { "$gte": start_time });
+        let cursor = collection
+            .find(filter)
+            .sort(doc! { "time": 1 })
+            .await?;
+        let statuses = cursor.try_collect::<Vec<BigStatusState>>().await?;
+        Ok(statuses)
+    }
+
+    pub async fn get_latest_big_status(&self, status_type: &StatusType, list_id: u64) -> ApiResult<BigStatusState> {
+        self.find_latest_big_status(status_type, list_id, None).await
+    }
+
+    async fn find_latest_big_status(&self, status_type: &StatusType, list_id: u64, session: Option<&mut ClientSession>) -> ApiResult<BigStatusState> {
+        let collection = self.get_big_collection(status_type)?;
+        let find = collection
+            .find_one(get_list_filter(list_id))
+            .sort(doc! { "time": -1 });
+        let status = match session {
+            Some(session) => find.session(session).await?,
+            None => find.await?,
+        };
+
+        status.ok_or(ApiError::CommonError(format!("No {} {} status in list {}", BIG_BIT_STATUS_LIST, status_type, list_id)))
+    }
+
+    /// Flips the bit of `index` in the latest big status of the list and appends the result at `time`, in `session`.
+    pub async fn set_big_status_bit_in_session(&self, status_type: &StatusType, list_id: u64, index: u64, time: u32, session: &mut ClientSession) -> ApiResult<BigStatusState> {
+        let mut status = self.find_latest_big_status(status_type, list_id, Some(&mut *session)).await?;
+        let previous_time = status.time;
+        Self::check_append_time(previous_time as u64, time as u64)?;
+
+        status.set_index_status(index)?;
+        status.id = None;
+        status.time = time;
+        self.insert_big_status(&status, previous_time as i64, Some(session)).await?;
+        Ok(status)
     }
 
     pub async fn delete_all(&self) -> ApiResult<()> {
@@ -101,6 +433,10 @@ impl StatusService {
             collection.delete_many(doc! {}).await?;
         }
 
+        for collection in self.big_collections.values() {
+            collection.delete_many(doc! {}).await?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,202 @@
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+use zkcdid_lib_rs::models::status_state::{StatusMechanism, StatusType};
+
+use crate::{big_bit_status_list::BIG_BIT_STATUS_LIST_ID, config::IssuerSettings, errors::{ApiError, ApiResult}, status_list::{get_list_capacity, get_mechanism_id}, utils::calculate_credential_commitment};
+
+use super::{credential_service::SignedCredential, issuer_key_service::{get_credential_message, IssuerKeyService}, status_registry_service::StatusRegistryService, status_service::StatusService};
+
+/// Where the statuses of a verified credential are read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationSource {
+    /// The latest statuses the issuer published.
+    #[default]
+    Issuer,
+    /// The statuses the oracles wrote to the `StatusRegistry`, which lag behind the issuer's.
+    Chain,
+}
+
+/// `?source=issuer|chain` of the verify routes.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VerificationQueryParams {
+    #[serde(default)]
+    pub source: VerificationSource,
+}
+
+/// The outcome of every check, with a reason for each failed one. `issued` and `revoked` are
+/// `None` when the status could not be read.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationResult {
+    pub valid: bool,
+    pub source: VerificationSource,
+    pub signature: bool,
+    pub issued: Option<bool>,
+    pub revoked: Option<bool>,
+    pub expired: bool,
+    pub reasons: Vec<String>,
+}
+
+/// Verifies presented credentials: the issuer signature, the issuance and revocation statuses of their index, and their expiry.
+#[derive(Debug, Clone)]
+pub struct VerificationService {
+    issuer_key_service: IssuerKeyService,
+    status_registry_service: Option<StatusRegistryService>,
+}
+
+impl VerificationService {
+    pub fn new(issuer_key_service: &IssuerKeyService, settings: &IssuerSettings) -> ApiResult<Self> {
+        Ok(Self {
+            issuer_key_service: issuer_key_service.clone(),
+            status_registry_service: StatusRegistryService::new(settings)?,
+        })
+    }
+
+    pub async fn verify_credential(&self, presented: &SignedCredential, status_mechanism: &StatusMechanism, source: VerificationSource, status_service: &StatusService) -> VerificationResult {
+        let mut reasons = vec![];
+        if presented.credential.status_mechanism != *status_mechanism {
+            reasons.push(format!("The credential has a {} status, not a {} one", presented.credential.status_mechanism, status_mechanism));
+        }
+
+        let signature = self.check_signature(presented, get_mechanism_id(status_mechanism), &mut reasons);
+        let issued = self.get_index_status(status_mechanism, &StatusType::Issuance, presented, source, status_service).await;
+        let revoked = self.get_index_status(status_mechanism, &StatusType::Revocation, presented, source, status_service).await;
+
+        Self::build_result(presented, source, signature, issued, revoked, reasons)
+    }
+
+    /// Verifies a credential of a `BigBitStatusList`, whose `status_mechanism` field reads `BitStatusList`.
+    pub async fn verify_big_credential(&self, presented: &SignedCredential, source: VerificationSource, status_service: &StatusService) -> VerificationResult {
+        let mut reasons = vec![];
+        let signature = self.check_signature(presented, BIG_BIT_STATUS_LIST_ID, &mut reasons);
+        let issued = self.get_big_index_status(&StatusType::Issuance, presented, source, status_service).await;
+        let revoked = self.get_big_index_status(&StatusType::Revocation, presented, source, status_service).await;
+
+        Self::build_result(presented, source, signature, issued, revoked, reasons)
+    }
+
+    /// Recomputes the commitment from the presented subject, data and index, so that a signature
+    /// cannot be moved to another credential.
+    fn check_signature(&self, presented: &SignedCredential, mechanism_id: u8, reasons: &mut Vec<String>) -> bool {
+        let Some(signature) = &presented.issuer_signature else {
+            reasons.push("The credential has no issuer signature".to_string());
+            return false;
+        };
+        let commitment = match calculate_credential_commitment(&presented.credential) {
+            Ok(commitment) => commitment,
+            Err(e) => {
+                reasons.push(format!("Cannot compute the credential commitment: {}", e));
+                return false;
+            },
+        };
+
+        let message = get_credential_message(mechanism_id, presented.list_id, presented.valid_until, &commitment);
+        if !self.issuer_key_service.is_signed_by_issuer(&message, signature) {
+            reasons.push("The issuer signature does not match the credential".to_string());
+            return false;
+        }
+
+        true
+    }
+
+    fn build_result(presented: &SignedCredential, source: VerificationSource, signature: bool, issued: ApiResult<bool>, revoked: ApiResult<bool>, mut reasons: Vec<String>) -> VerificationResult {
+        let issued = match issued {
+            Ok(issued) => {
+                if !issued {
+                    reasons.push("The credential is not issued".to_string());
+                }
+                Some(issued)
+            },
+            Err(e) => {
+                reasons.push(format!("Cannot read the issuance status: {}", e));
+                None
+            },
+        };
+        let revoked = match revoked {
+            Ok(revoked) => {
+                if revoked {
+                    reasons.push("The credential is revoked".to_string());
+                }
+                Some(revoked)
+            },
+            Err(e) => {
+                reasons.push(format!("Cannot read the revocation status: {}", e));
+                None
+            },
+        };
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let expired = presented.valid_until.is_some_and(|valid_until| now >= valid_until);
+        if expired {
+            reasons.push(format!("The credential expired at {}", presented.valid_until.unwrap_or_default()));
+        }
+
+        VerificationResult {
+            valid: reasons.is_empty(),
+            source,
+            signature,
+            issued,
+            revoked,
+            expired,
+            reasons,
+        }
+    }
+
+    /// The `StatusRegistry` with the `IssuerId` the list is registered under.
+    async fn get_status_registry(&self, mechanism_id: u8, list_id: u64) -> ApiResult<(&StatusRegistryService, u8)> {
+        let status_registry_service = self.status_registry_service.as_ref().ok_or(ApiError::ConfigError("No StatusRegistry is configured".to_string()))?;
+        let issuer_id = status_registry_service.get_issuer_id(mechanism_id, list_id).await?;
+
+        Ok((status_registry_service, issuer_id))
+    }
+
+    /// Whether the bit or leaf of the credential index is set in the latest status of `status_type`.
+    async fn get_index_status(&self, status_mechanism: &StatusMechanism, status_type: &StatusType, presented: &SignedCredential, source: VerificationSource, status_service: &StatusService) -> ApiResult<bool> {
+        let (list_id, index) = (presented.list_id, presented.credential.index);
+        if index >= get_list_capacity(status_mechanism) {
+            return Err(ApiError::CommonError(format!("Index {} is out of a {} list", index, status_mechanism)));
+        }
+
+        match (status_mechanism, source) {
+            (StatusMechanism::BitStatusList, VerificationSource::Issuer) => {
+                let status = status_service.get_latest_status(status_mechanism, status_type, list_id).await?;
+                Ok((status.status >> index) & 1 == 1)
+            },
+            (StatusMechanism::BitStatusList, VerificationSource::Chain) => {
+                let (status_registry_service, issuer_id) = self.get_status_registry(get_mechanism_id(status_mechanism), list_id).await?;
+                let (_, status) = status_registry_service.get_bsl_status(issuer_id, status_type).await?;
+                Ok((status >> index) & 1 == 1)
+            },
+            (StatusMechanism::MerkleTree, VerificationSource::Issuer) => {
+                let (_, tree) = status_service.get_tree(status_type, list_id, None).await?;
+                tree.is_set(index)
+            },
+            // the chain only has the root, so the leaf is read from the issuer's tree with that root
+            (StatusMechanism::MerkleTree, VerificationSource::Chain) => {
+                let (status_registry_service, issuer_id) = self.get_status_registry(get_mechanism_id(status_mechanism), list_id).await?;
+                let Some((time, root)) = status_registry_service.get_latest_mt_status(issuer_id, status_type).await? else {
+                    return Ok(false);
+                };
+                let (status, tree) = status_service.get_tree(status_type, list_id, Some(time)).await?;
+                if U256::from(status.status) != root {
+                    return Err(ApiError::CommonError(format!("The on-chain root at time {} is not the root of the issuer's tree", time)));
+                }
+
+                tree.is_set(index)
+            },
+        }
+    }
+
+    async fn get_big_index_status(&self, status_type: &StatusType, presented: &SignedCredential, source: VerificationSource, status_service: &StatusService) -> ApiResult<bool> {
+        let list_id = presented.list_id;
+        let status = match source {
+            VerificationSource::Issuer => status_service.get_latest_big_status(status_type, list_id).await?,
+            VerificationSource::Chain => {
+                let (status_registry_service, issuer_id) = self.get_status_registry(BIG_BIT_STATUS_LIST_ID, list_id).await?;
+                status_registry_service.get_big_bsl_status(issuer_id, status_type, list_id).await?
+            },
+        };
+
+        status.get_index_status(presented.credential.index)
+    }
+}
@@ -0,0 +1,41 @@
+use bson::{doc, Document};
+use serde::Deserialize;
+use zkcdid_lib_rs::models::status_state::StatusMechanism;
+
+use crate::merkle_tree::MERKLE_TREE_HEIGHT;
+
+/// Field of the credential and status documents holding the status list they belong to.
+pub const LIST_ID_FIELD: &str = "list_id";
+/// Query parameter of the status routes and status urls selecting the list.
+pub const LIST_QUERY_PARAM: &str = "list";
+
+/// `?list=<id>` of the status routes, list 0 when missing.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListQueryParams {
+    #[serde(default)]
+    pub list: u64,
+}
+
+/// Credential indexes per list: the bits of a BSL status or the leaves of a status tree.
+pub fn get_list_capacity(status_mechanism: &StatusMechanism) -> u64 {
+    match status_mechanism {
+        StatusMechanism::BitStatusList => u64::BITS as u64,
+        StatusMechanism::MerkleTree => 1 << MERKLE_TREE_HEIGHT,
+    }
+}
+
+/// The mechanism as one byte in signed messages. `BigBitStatusList` is `BIG_BIT_STATUS_LIST_ID`.
+pub fn get_mechanism_id(status_mechanism: &StatusMechanism) -> u8 {
+    match status_mechanism {
+        StatusMechanism::BitStatusList => 0,
+        StatusMechanism::MerkleTree => 1,
+    }
+}
+
+/// Matches the documents of a list. Documents stored before lists existed have no list id and are in list 0.
+pub fn get_list_filter(list_id: u64) -> Document {
+    match list_id {
+        0 => doc! { LIST_ID_FIELD: doc! { "$in": [0i64, bson::Bson::Null] } },
+        _ => doc! { LIST_ID_FIELD: list_id as i64 },
+    }
+}
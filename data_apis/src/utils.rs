@@ -4,10 +4,20 @@ use mongodb::Database;
 use serde::{Deserialize, Deserializer, Serializer};
 use sha2::{Digest, Sha256};
 use serde::de::Error as DeError;
-use zkcdid_lib_rs::{config::Config, utils::db};
+use std::sync::OnceLock;
 
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use poseidon_ark::Poseidon;
+use zkcdid_lib_rs::{config::Config, models::{credential::Credential, status_state::{StatusMechanism, StatusState, StatusType}}, utils::db};
 
-use crate::{errors::ApiResult, services::{credential_service::CredentialService, status_service::StatusService}};
+use crate::{big_bit_status_list::{BigStatusState, BIG_BIT_STATUS_LIST_ID}, config::ApiConfig};
+
+
+use crate::{errors::{ApiError, ApiResult}, services::{credential_service::CredentialService, issuer_clock_service::IssuerClockService, issuer_key_service::IssuerKeyService, status_service::StatusService, verification_service::VerificationService}};
+
+/// Field stored next to the credential and status state documents, holding their Poseidon commitment.
+pub const COMMITMENT_FIELD: &str = "commitment";
 
 pub fn u64_to_base64<S>(num: &u64, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -64,22 +74,33 @@ pub struct AppData {
     pub database: Database,
     pub status_service: StatusService,
     pub credential_service: CredentialService,
+    pub issuer_key_service: IssuerKeyService,
+    pub verification_service: VerificationService,
     pub config: Config,
 }
 
 impl AppData {
-    pub async fn new(config: &Config) -> ApiResult<Self> {
+    pub async fn new(api_config: &ApiConfig, issuer_key_service: &IssuerKeyService) -> ApiResult<Self> {
+        let config = &api_config.config;
+
         println!("Connecting to database...");
         match db::get_db(config).await {
             Ok(database) => {
                 println!("Database connected");
-                let status_service = StatusService::new(&database);
-                let credential_service = CredentialService::new(&database);
+                let clock = IssuerClockService::new(&database, api_config.issuer.clock);
+                let status_service = StatusService::new(&database, config, issuer_key_service, &clock);
+                status_service.ensure_indexes().await?;
+                status_service.initialize_clock().await?;
+                let credential_service = CredentialService::new(&database, config, issuer_key_service);
+                credential_service.initialize_counters().await?;
+                let verification_service = VerificationService::new(issuer_key_service, &api_config.issuer)?;
 
                 Ok(Self {
                     database: database,
                     status_service: status_service,
                     credential_service: credential_service,
+                    issuer_key_service: issuer_key_service.clone(),
+                    verification_service: verification_service,
                     config: config.clone(),
                 })
             }
@@ -97,7 +118,165 @@ pub fn calculate_sha256_hash(data: &[u8]) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
-pub fn calculate_poseidon_hash() -> ApiResult<()> {
-    // converting data to Fr
-   unimplemented!("This function must be implemented after checking poseidon hash crates");
-}
\ No newline at end of file
+/// Field elements per Poseidon call, the widest `poseidon-ark` supports.
+const POSEIDON_MAX_INPUTS: usize = 16;
+/// Bytes per field element, so that every chunk is below the BN254 scalar modulus.
+const FIELD_CHUNK_BYTES: usize = 31;
+
+fn get_poseidon() -> &'static Poseidon {
+    static POSEIDON: OnceLock<Poseidon> = OnceLock::new();
+    POSEIDON.get_or_init(Poseidon::new)
+}
+
+/// Poseidon over BN254 with the circomlib parameters. Inputs beyond 16 elements are absorbed
+/// 15 at a time, each call taking the previous digest as its first input.
+///
+/// The commitments must stay in the field encoding of the circuits so that they can be used as public
+/// inputs: `circuits.Poseidon` of `circuits-go` computes the same hash, and both assert the same vectors.
+pub fn calculate_poseidon_hash(inputs: &[Fr]) -> ApiResult<Fr> {
+    if inputs.is_empty() {
+        return Err(ApiError::CommonError("Cannot hash an empty input".to_string()));
+    }
+
+    let poseidon = get_poseidon();
+    let (first, rest) = inputs.split_at(inputs.len().min(POSEIDON_MAX_INPUTS));
+    let mut digest = poseidon.hash(first.to_vec()).map_err(ApiError::CommonError)?;
+
+    for chunk in rest.chunks(POSEIDON_MAX_INPUTS - 1) {
+        let mut chunk_inputs = vec![digest];
+        chunk_inputs.extend_from_slice(chunk);
+        digest = poseidon.hash(chunk_inputs).map_err(ApiError::CommonError)?;
+    }
+
+    Ok(digest)
+}
+
+/// Encodes bytes as field elements: the length first, then big-endian chunks of 31 bytes.
+pub fn bytes_to_field_elements(data: &[u8]) -> Vec<Fr> {
+    let mut elements = vec![Fr::from(data.len() as u64)];
+    elements.extend(data.chunks(FIELD_CHUNK_BYTES).map(Fr::from_be_bytes_mod_order));
+    elements
+}
+
+/// Decimal form of a field element.
+pub fn field_to_string(element: &Fr) -> String {
+    element.into_bigint().to_string()
+}
+
+/// `Poseidon(Poseidon(subject), Poseidon(data), index)`, with `data` as JSON with sorted keys.
+pub fn calculate_credential_commitment(credential: &Credential) -> ApiResult<String> {
+    let subject = calculate_poseidon_hash(&bytes_to_field_elements(credential.subject.as_bytes()))?;
+    // serde_json maps keep their keys sorted, which makes the encoding canonical
+    let data = serde_json::to_string(&serde_json::to_value(&credential.data)?)?;
+    let data = calculate_poseidon_hash(&bytes_to_field_elements(data.as_bytes()))?;
+
+    let commitment = calculate_poseidon_hash(&[subject, data, Fr::from(credential.index)])?;
+    Ok(field_to_string(&commitment))
+}
+
+/// `Poseidon(mechanism, type, time, status)`, with the mechanism and type numbered from 0 in declaration order.
+pub fn calculate_status_commitment(status: &StatusState) -> ApiResult<String> {
+    let status_mechanism = match status.status_mechanism {
+        StatusMechanism::BitStatusList => 0u64,
+        StatusMechanism::MerkleTree => 1,
+    };
+    let status_type = match status.status_type {
+        StatusType::Issuance => 0u64,
+        StatusType::Revocation => 1,
+    };
+
+    let commitment = calculate_poseidon_hash(&[
+        Fr::from(status_mechanism),
+        Fr::from(status_type),
+        Fr::from(status.time),
+        Fr::from(status.status),
+    ])?;
+    Ok(field_to_string(&commitment))
+}
+
+/// `Poseidon(2, type, time, words...)`, each word split into its high and low 128 bits to stay in the field.
+pub fn calculate_big_status_commitment(status: &BigStatusState) -> ApiResult<String> {
+    let status_type = match status.status_type {
+        StatusType::Issuance => 0u64,
+        StatusType::Revocation => 1,
+    };
+
+    let mut inputs = vec![Fr::from(BIG_BIT_STATUS_LIST_ID as u64), Fr::from(status_type), Fr::from(status.time as u64)];
+    for word in status.data.iter() {
+        inputs.extend(word.chunks(16).map(Fr::from_be_bytes_mod_order));
+    }
+
+    let commitment = calculate_poseidon_hash(&inputs)?;
+    Ok(field_to_string(&commitment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(inputs: &[u64]) -> Fr {
+        calculate_poseidon_hash(&inputs.iter().map(|input| Fr::from(*input)).collect::<Vec<_>>()).unwrap()
+    }
+
+    /// `TestPoseidonMatchesTheSharedVectors` of `circuits-go` asserts the same digests.
+    #[test]
+    fn poseidon_matches_the_circomlib_vectors() {
+        assert_eq!(field_to_string(&hash(&[1, 2])), "7853200120776062878684798364095072458815029376092732009249414926327459813530");
+        assert_eq!(field_to_string(&hash(&[1, 2, 3, 4])), "18821383157269793795438455681495246036402687001665670618754263018637548127333");
+    }
+
+    #[test]
+    fn status_commitments_match_the_circuit_vector() {
+        let mut status = StatusState::get_initial_status(StatusMechanism::BitStatusList, StatusType::Issuance);
+        status.time = 5;
+        status.status = 0b1011;
+
+        assert_eq!(calculate_status_commitment(&status).unwrap(), "9369453522483212879924925258841810605190261124448240357801507940333507057703");
+    }
+
+    #[test]
+    fn poseidon_rejects_an_empty_input() {
+        assert!(calculate_poseidon_hash(&[]).is_err());
+    }
+
+    #[test]
+    fn poseidon_depends_on_every_input_and_its_order() {
+        assert_eq!(hash(&[1, 2, 3]), hash(&[1, 2, 3]));
+        assert_ne!(hash(&[1, 2, 3]), hash(&[1, 2, 4]));
+        assert_ne!(hash(&[1, 2, 3]), hash(&[3, 2, 1]));
+    }
+
+    #[test]
+    fn poseidon_absorbs_long_inputs_into_the_previous_digest() {
+        let inputs = (0..40).collect::<Vec<u64>>();
+        let first = hash(&inputs[..16]);
+        let second = calculate_poseidon_hash(&[vec![first], inputs[16..31].iter().map(|input| Fr::from(*input)).collect()].concat()).unwrap();
+        let third = calculate_poseidon_hash(&[vec![second], inputs[31..].iter().map(|input| Fr::from(*input)).collect()].concat()).unwrap();
+
+        assert_eq!(hash(&inputs), third);
+        assert_ne!(hash(&inputs), hash(&inputs[..39]));
+    }
+
+    #[test]
+    fn bytes_are_prefixed_with_their_length_and_cut_in_31_byte_chunks() {
+        let data = [0xffu8; 40];
+        let elements = bytes_to_field_elements(&data);
+
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0], Fr::from(40u64));
+        assert_eq!(elements[1], Fr::from_be_bytes_mod_order(&data[..31]));
+        assert_eq!(elements[2], Fr::from_be_bytes_mod_order(&data[31..]));
+        // the length keeps trailing zero bytes from hashing like a shorter input
+        assert_ne!(bytes_to_field_elements(&[1, 0]), bytes_to_field_elements(&[1]));
+        assert_eq!(bytes_to_field_elements(&[]), vec![Fr::from(0u64)]);
+    }
+
+    #[test]
+    fn status_commitments_change_with_the_status() {
+        let mut status = StatusState::get_initial_status(StatusMechanism::BitStatusList, StatusType::Issuance);
+        let initial = calculate_status_commitment(&status).unwrap();
+
+        status.status |= 1;
+        assert_ne!(calculate_status_commitment(&status).unwrap(), initial);
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::Write;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::{json, Map, Value};
+use url::Url;
+use zkcdid_lib_rs::models::{credential::Credential, status_state::StatusType};
+
+use crate::{errors::{ApiError, ApiResult}, status_list::LIST_QUERY_PARAM};
+
+/// Base context of the W3C Verifiable Credentials Data Model 2.0.
+pub const VC_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+/// Shortest bitstring of a `BitstringStatusList`, 16KB, so that a list does not reveal how many credentials it holds.
+pub const BITSTRING_MIN_BITS: u64 = 131_072;
+/// Multibase prefix of base64url without padding.
+const MULTIBASE_BASE64URL: char = 'u';
+
+/// `credentials/{mechanism}/{id}`, where the credential is served.
+pub fn build_credential_url(base_url: &str, status_mechanism: &str, id: &str) -> ApiResult<String> {
+    Ok(Url::parse(base_url)?.join(&format!("credentials/{}/{}", status_mechanism, id))?.to_string())
+}
+
+/// `statuses/{mechanism}/{type}/bitstring?list={list_id}`, where the `BitstringStatusListCredential` of a list is served.
+pub fn build_status_list_url(base_url: &str, status_mechanism: &str, status_type: &StatusType, list_id: u64) -> ApiResult<String> {
+    let mut url = Url::parse(base_url)?.join(&format!("statuses/{}/{}/bitstring", status_mechanism, status_type))?;
+    url.query_pairs_mut().append_pair(LIST_QUERY_PARAM, &list_id.to_string());
+    Ok(url.to_string())
+}
+
+/// `statusPurpose` of the lists of a status type; revocation is a standard purpose, issuance is ours.
+pub fn get_status_purpose(status_type: &StatusType) -> &'static str {
+    match status_type {
+        StatusType::Issuance => "issuance",
+        StatusType::Revocation => "revocation",
+    }
+}
+
+/// The indexes set in a BSL status, index `i` being bit `i` from the least significant bit.
+pub fn get_bsl_indexes(status: u64) -> Vec<u64> {
+    (0..u64::BITS as u64).filter(|index| (status >> index) & 1 == 1).collect()
+}
+
+/// The `encodedList` of a `BitstringStatusList`: the bitstring with index 0 as the most significant bit of
+/// the first byte, padded to 16KB, GZIP-compressed and multibase base64url encoded.
+pub fn encode_bitstring(indexes: &[u64], length: u64) -> ApiResult<String> {
+    let length = length.max(BITSTRING_MIN_BITS);
+    let mut bitstring = vec![0u8; length.div_ceil(8) as usize];
+    for index in indexes.iter() {
+        if *index >= length {
+            return Err(ApiError::CommonError(format!("Index {} is out of a bitstring of {} bits", index, length)));
+        }
+        bitstring[(*index / 8) as usize] |= 0x80 >> (*index % 8);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bitstring)
+        .and_then(|_| encoder.finish())
+        .map(|compressed| format!("{}{}", MULTIBASE_BASE64URL, URL_SAFE_NO_PAD.encode(compressed)))
+        .map_err(|e| ApiError::SerializationError(format!("Cannot compress the bitstring: {}", e)))
+}
+
+/// A `BitstringStatusListCredential` served at `url`, with the `indexes` set. `issuer` is the issuer's DID.
+pub fn build_status_list_credential(url: &str, issuer: &str, status_type: &StatusType, indexes: &[u64], length: u64) -> ApiResult<Value> {
+    Ok(json!({
+        "@context": [VC_CONTEXT],
+        "id": url,
+        "type": ["VerifiableCredential", "BitstringStatusListCredential"],
+        "issuer": issuer,
+        "credentialSubject": {
+            "id": format!("{}#list", url),
+            "type": "BitstringStatusList",
+            "statusPurpose": get_status_purpose(status_type),
+            "encodedList": encode_bitstring(indexes, length)?,
+        },
+    }))
+}
+
+/// A `credentialStatus` entry pointing at the `BitstringStatusListCredential` served at `list_url`.
+pub fn build_bitstring_status_entry(list_url: &str, status_type: &StatusType, index: u64) -> Value {
+    json!({
+        "id": format!("{}#{}", list_url, index),
+        "type": "BitstringStatusListEntry",
+        "statusPurpose": get_status_purpose(status_type),
+        "statusListIndex": index.to_string(),
+        "statusListCredential": list_url,
+    })
+}
+
+/// A `credentialStatus` entry of a status tree, whose statuses and inclusion paths are served at `status_url`.
+pub fn build_merkle_tree_status_entry(status_url: &str, status_type: &StatusType, index: u64) -> Value {
+    json!({
+        "id": format!("{}#{}", status_url, index),
+        "type": "MerkleTreeStatusEntry",
+        "statusPurpose": get_status_purpose(status_type),
+        "statusListIndex": index.to_string(),
+        "statusListCredential": status_url,
+    })
+}
+
+/// The credential as a W3C VC 2.0 document issued by the DID `issuer`. The subject id is the credential subject and its data are
+/// the claims; data that is not a JSON object is kept under `data`.
+pub fn build_verifiable_credential(credential: &Credential, url: &str, issuer: &str, status_entries: Vec<Value>) -> ApiResult<Value> {
+    let mut subject = match serde_json::to_value(&credential.data)? {
+        Value::Object(claims) => claims,
+        data => Map::from_iter([("data".to_string(), data)]),
+    };
+    subject.insert("id".to_string(), Value::String(credential.subject.clone()));
+
+    Ok(json!({
+        "@context": [VC_CONTEXT],
+        "id": url,
+        "type": ["VerifiableCredential"],
+        "issuer": issuer,
+        "credentialSubject": subject,
+        "credentialStatus": status_entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    fn decode_bitstring(encoded: &str) -> Vec<u8> {
+        let compressed = URL_SAFE_NO_PAD.decode(encoded.strip_prefix(MULTIBASE_BASE64URL).unwrap()).unwrap();
+        let mut bitstring = vec![];
+        GzDecoder::new(compressed.as_slice()).read_to_end(&mut bitstring).unwrap();
+        bitstring
+    }
+
+    #[test]
+    fn bitstrings_are_padded_to_16kb() {
+        let bitstring = decode_bitstring(&encode_bitstring(&[], 8).unwrap());
+
+        assert_eq!(bitstring.len() as u64, BITSTRING_MIN_BITS / 8);
+        assert!(bitstring.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn index_0_is_the_most_significant_bit_of_the_first_byte() {
+        let bitstring = decode_bitstring(&encode_bitstring(&[0, 9, BITSTRING_MIN_BITS - 1], 0).unwrap());
+
+        assert_eq!(bitstring[0], 0b1000_0000);
+        assert_eq!(bitstring[1], 0b0100_0000);
+        assert_eq!(bitstring[bitstring.len() - 1], 0b0000_0001);
+        assert_eq!(bitstring.iter().map(|byte| byte.count_ones()).sum::<u32>(), 3);
+    }
+
+    #[test]
+    fn longer_lists_keep_their_length() {
+        let length = BITSTRING_MIN_BITS + 12;
+        let bitstring = decode_bitstring(&encode_bitstring(&[length - 1], length).unwrap());
+
+        assert_eq!(bitstring.len() as u64, length.div_ceil(8));
+        assert_eq!(bitstring[bitstring.len() - 1], 0b0001_0000);
+    }
+
+    #[test]
+    fn encoded_lists_are_multibase_base64url() {
+        let encoded = encode_bitstring(&[1, 2, 3], 0).unwrap();
+
+        assert!(encoded.starts_with('u'));
+        assert!(encoded[1..].chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn indexes_out_of_the_bitstring_are_rejected() {
+        assert!(encode_bitstring(&[BITSTRING_MIN_BITS], 0).is_err());
+    }
+
+    #[test]
+    fn bsl_indexes_are_counted_from_the_least_significant_bit() {
+        assert_eq!(get_bsl_indexes(0), Vec::<u64>::new());
+        assert_eq!(get_bsl_indexes(0b1010), vec![1, 3]);
+        assert_eq!(get_bsl_indexes(1 << 63), vec![63]);
+    }
+}
@@ -4,7 +4,11 @@ docker rm -f $(docker ps -a -q)
 
 echo "Starting containers..."
 echo "Starting MongoDBs..."
-docker run --name mongo1 -d -p 27017:27017 --network zkssi --rm mongo:latest
+docker run --name mongo1 -d -p 27017:27017 --network zkssi --rm mongo:latest --replSet rs0 --bind_ip_all
+# issuance runs in transactions, which need a replica set, even a single-node one
+until docker exec mongo1 mongosh --quiet --eval "try { rs.status() } catch (e) { rs.initiate({_id: 'rs0', members: [{_id: 0, host: 'mongo1:27017'}]}) }"; do
+    sleep 1
+done
 
 echo "Starting APIs..."
 docker run --name api1 -dit -p 3000:8000 -v $(pwd):/app --network zkssi \
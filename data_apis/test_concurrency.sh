@@ -0,0 +1,86 @@
+#!/bin/bash
+# Issues credentials in parallel against a running API, then checks that every one got its own
+# position and that no issuance or revocation bit was lost, through the verify endpoint.
+# Resets the API data first. Needs curl and python3.
+#
+# API_URL=http://localhost:3000 COUNT=300 PARALLEL=50 ./test_concurrency.sh
+set -euo pipefail
+
+API_URL=${API_URL:-http://localhost:3000}
+COUNT=${COUNT:-300}
+PARALLEL=${PARALLEL:-50}
+MECHANISMS=${MECHANISMS:-"bsl mt BigBitStatusList"}
+
+OUT=$(mktemp -d)
+trap 'rm -rf "$OUT"' EXIT
+
+issue() {
+    local mechanism=$1 n=$2
+    curl -sf -X POST "$API_URL/credentials/$mechanism" \
+        -H "Content-Type: application/json" \
+        -d "{\"subject\": \"Holder $n\", \"data\": {\"name\": \"Holder $n\", \"n\": \"$n\"}}" \
+        -o "$OUT/$mechanism/$n.json" \
+        || { rm -f "$OUT/$mechanism/$n.json"; echo "Issuance $n failed" >&2; }
+}
+
+revoke() {
+    local mechanism=$1 file=$2
+    local id
+    id=$(python3 -c 'import json, sys; c = json.load(open(sys.argv[1])); i = c.get("_id", c.get("id")); print(i["$oid"] if isinstance(i, dict) else i)' "$file")
+    curl -sf -X DELETE "$API_URL/credentials/$mechanism/$id" -o /dev/null
+}
+
+# prints one line per credential whose verification does not match the expected revocation state
+verify() {
+    local mechanism=$1 file=$2 revoked=$3
+    curl -sf -X POST "$API_URL/credentials/$mechanism/verify" \
+        -H "Content-Type: application/json" \
+        --data-binary "@$file" \
+        | python3 -c '
+import json, sys
+result, revoked = json.load(sys.stdin), sys.argv[2] == "true"
+if result["issued"] is not True or result["revoked"] is not revoked or result["signature"] is not True:
+    print(sys.argv[1], result["reasons"])
+' "$file" "$revoked"
+}
+
+export -f issue revoke verify
+export API_URL OUT
+
+echo "Resetting data..."
+curl -sf -X GET "$API_URL/app/reset" -o /dev/null
+
+failed=0
+for mechanism in $MECHANISMS; do
+    mkdir -p "$OUT/$mechanism"
+
+    echo "[$mechanism] Issuing $COUNT credentials, $PARALLEL at a time..."
+    seq 1 "$COUNT" | xargs -P "$PARALLEL" -I {} bash -c 'issue "$0" "$1"' "$mechanism" {}
+
+    issued=$(find "$OUT/$mechanism" -name "*.json" | wc -l)
+    duplicates=$(python3 -c '
+import glob, json, sys
+positions = [(c.get("list_id", 0), c["index"]) for c in (json.load(open(f)) for f in glob.glob(sys.argv[1] + "/*.json"))]
+print(len(positions) - len(set(positions)))
+' "$OUT/$mechanism")
+    echo "[$mechanism] Issued $issued, positions given twice: $duplicates"
+
+    mismatches=$(find "$OUT/$mechanism" -name "*.json" | xargs -P "$PARALLEL" -I {} bash -c 'verify "$0" "$1" false' "$mechanism" {})
+    echo "[$mechanism] Credentials not issued: $(echo -n "$mismatches" | grep -c . || true)"
+
+    echo "[$mechanism] Revoking them, $PARALLEL at a time..."
+    find "$OUT/$mechanism" -name "*.json" | xargs -P "$PARALLEL" -I {} bash -c 'revoke "$0" "$1"' "$mechanism" {}
+    revocation_mismatches=$(find "$OUT/$mechanism" -name "*.json" | xargs -P "$PARALLEL" -I {} bash -c 'verify "$0" "$1" true' "$mechanism" {})
+    echo "[$mechanism] Credentials not revoked: $(echo -n "$revocation_mismatches" | grep -c . || true)"
+
+    if [ "$issued" -ne "$COUNT" ] || [ "$duplicates" -ne 0 ] || [ -n "$mismatches" ] || [ -n "$revocation_mismatches" ]; then
+        echo "$mismatches$revocation_mismatches" | head -5
+        failed=1
+    fi
+done
+
+if [ "$failed" -ne 0 ]; then
+    echo "FAILED"
+    exit 1
+fi
+echo "OK"
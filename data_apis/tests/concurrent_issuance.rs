@@ -0,0 +1,176 @@
+//! Concurrent issuances and revocations against a MongoDB replica set, which transactions need.
+//! Skipped unless `TEST_MONGO_URI` is set, e.g.
+//!
+//! TEST_MONGO_URI="mongodb://localhost:27017/?replicaSet=rs0" cargo test --test concurrent_issuance
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bson::{doc, Document};
+use data_apis::{big_bit_status_list::BIG_BSL_BITS, config::{ApiConfig, ClockMode, IssuerSettings}, services::{credential_service::{CredentialService, SignedCredential}, issuer_clock_service::IssuerClockService, issuer_key_service::IssuerKeyService, status_service::{StatusService, PREVIOUS_TIME_FIELD}}, status_list::{get_list_capacity, get_list_filter}, w3c::get_bsl_indexes};
+use futures_util::{future::join_all, TryStreamExt};
+use mongodb::{Client, Collection, Database};
+use strum::IntoEnumIterator;
+use zkcdid_lib_rs::{config::Config, models::{request_params::CredentialIssuanceParams, status_state::{StatusMechanism, StatusType}}};
+
+const TEST_MONGO_URI_ENV: &str = "TEST_MONGO_URI";
+/// Issued at once, then as many again while the first ones are revoked. Below `MAX_TRANSACTION_ATTEMPTS`,
+/// since each round of conflicting transactions commits one of them.
+const COUNT: u64 = 50;
+
+struct TestIssuer {
+    database: Database,
+    config: Config,
+    status_service: StatusService,
+    credential_service: CredentialService,
+}
+
+impl TestIssuer {
+    async fn create(uri: &str) -> Self {
+        let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let database = Client::with_uri_str(uri).await.unwrap().database(&format!("data_apis_test_{}", nanos));
+        let config = ApiConfig::load().unwrap().config;
+
+        let key_path = std::env::temp_dir().join(format!("data_apis_test_{}.pk8", nanos));
+        let issuer_key_service = IssuerKeyService::load_or_create(&IssuerSettings { key_path: key_path.to_string_lossy().to_string(), ..Default::default() }).unwrap();
+        let _ = std::fs::remove_file(key_path);
+
+        let clock = IssuerClockService::new(&database, ClockMode::Counter);
+        let status_service = StatusService::new(&database, &config, &issuer_key_service, &clock);
+        status_service.ensure_indexes().await.unwrap();
+        status_service.initialize().await.unwrap();
+        let credential_service = CredentialService::new(&database, &config, &issuer_key_service);
+
+        Self {
+            database,
+            config,
+            status_service,
+            credential_service,
+        }
+    }
+
+    fn get_issuance_params(n: u64) -> CredentialIssuanceParams {
+        serde_json::from_value(serde_json::json!({
+            "subject": format!("Holder {}", n),
+            "data": { "name": format!("Holder {}", n), "n": n.to_string() },
+        })).unwrap()
+    }
+
+    async fn issue(&self, status_mechanism: Option<StatusMechanism>, range: std::ops::Range<u64>) -> Vec<SignedCredential> {
+        join_all(range.map(|n| async move {
+            let params = Self::get_issuance_params(n);
+            match status_mechanism {
+                Some(status_mechanism) => self.credential_service.issue_credential(&params, &status_mechanism, None, &self.status_service, &self.config).await,
+                None => self.credential_service.issue_big_credential(&params, None, &self.status_service, &self.config).await,
+            }
+        })).await.into_iter().map(Result::unwrap).collect()
+    }
+
+    async fn revoke(&self, status_mechanism: Option<StatusMechanism>, credentials: &[SignedCredential]) {
+        for result in join_all(credentials.iter().map(|credential| async move {
+            let id = credential.credential.id.unwrap().to_hex();
+            match status_mechanism {
+                Some(status_mechanism) => self.credential_service.revoke_credential(&id, &status_mechanism, &self.status_service).await,
+                None => self.credential_service.revoke_big_credential(&id, &self.status_service).await,
+            }
+        })).await {
+            result.unwrap();
+        }
+    }
+
+    /// Issues `COUNT` credentials at once, then `COUNT` more while revoking the first ones, and returns
+    /// all of them with the revoked ones.
+    async fn issue_and_revoke(&self, status_mechanism: Option<StatusMechanism>) -> (Vec<SignedCredential>, Vec<SignedCredential>) {
+        let revoked = self.issue(status_mechanism, 0..COUNT).await;
+        let (issued, _) = tokio::join!(self.issue(status_mechanism, COUNT..2 * COUNT), self.revoke(status_mechanism, &revoked));
+
+        ([revoked.clone(), issued].concat(), revoked)
+    }
+
+    async fn remove(self) {
+        self.database.drop().await.unwrap();
+    }
+}
+
+/// Positions are handed out in order, so the credentials fill list 0, then list 1, ... without a gap.
+fn assert_gap_free_positions(credentials: &[SignedCredential], capacity: u64) {
+    let positions: BTreeSet<(u64, u64)> = credentials.iter().map(|credential| (credential.list_id, credential.credential.index)).collect();
+    assert_eq!(positions.len(), credentials.len(), "positions were given twice");
+
+    let expected: BTreeSet<(u64, u64)> = (0..credentials.len() as u64).map(|n| (n / capacity, n % capacity)).collect();
+    assert_eq!(positions, expected, "positions were skipped");
+}
+
+/// The indexes of each list among `credentials`.
+fn get_indexes_by_list(credentials: &[SignedCredential]) -> BTreeMap<u64, BTreeSet<u64>> {
+    let mut indexes: BTreeMap<u64, BTreeSet<u64>> = BTreeMap::new();
+    for credential in credentials.iter() {
+        indexes.entry(credential.list_id).or_default().insert(credential.credential.index);
+    }
+
+    indexes
+}
+
+/// The statuses of a list follow each other from the first one, one per write, none lost to a concurrent one.
+async fn assert_status_chain(collection: &Collection<Document>, list_id: u64, writes: usize) {
+    let statuses: Vec<Document> = collection.find(get_list_filter(list_id)).sort(doc! { "time": 1 }).await.unwrap().try_collect().await.unwrap();
+    assert_eq!(statuses.len(), writes + 1, "list {} of {} has {} statuses", list_id, collection.name(), statuses.len());
+
+    let mut previous_time = -1;
+    for status in statuses.iter() {
+        assert_eq!(status.get_i64(PREVIOUS_TIME_FIELD).unwrap(), previous_time, "list {} of {} is not a chain", list_id, collection.name());
+        previous_time = match status.get("time").unwrap() {
+            bson::Bson::Int32(time) => *time as i64,
+            time => time.as_i64().unwrap(),
+        };
+    }
+}
+
+async fn check_mechanism(issuer: &TestIssuer, status_mechanism: StatusMechanism) {
+    let (credentials, revoked) = issuer.issue_and_revoke(Some(status_mechanism)).await;
+    assert_gap_free_positions(&credentials, get_list_capacity(&status_mechanism));
+
+    for (status_type, credentials) in [(StatusType::Issuance, &credentials), (StatusType::Revocation, &revoked)] {
+        let collection = issuer.status_service.collections.get(&(status_mechanism, status_type)).unwrap().clone_with_type::<Document>();
+
+        for (list_id, indexes) in get_indexes_by_list(credentials) {
+            assert_status_chain(&collection, list_id, indexes.len()).await;
+
+            let set_indexes: BTreeSet<u64> = match status_mechanism {
+                StatusMechanism::BitStatusList => {
+                    let status = issuer.status_service.get_latest_status(&status_mechanism, &status_type, list_id).await.unwrap();
+                    get_bsl_indexes(status.status).into_iter().collect()
+                },
+                StatusMechanism::MerkleTree => issuer.status_service.get_tree(&status_type, list_id, None).await.unwrap().1.get_set_indexes().into_iter().collect(),
+            };
+            assert_eq!(set_indexes, indexes, "list {} of {} lost a write", list_id, collection.name());
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn concurrent_issuances_and_revocations_keep_positions_and_chains() {
+    let Ok(uri) = std::env::var(TEST_MONGO_URI_ENV) else {
+        println!("{} is not set, skipping", TEST_MONGO_URI_ENV);
+        return;
+    };
+    let issuer = TestIssuer::create(&uri).await;
+
+    for status_mechanism in StatusMechanism::iter() {
+        check_mechanism(&issuer, status_mechanism).await;
+    }
+
+    let (credentials, revoked) = issuer.issue_and_revoke(None).await;
+    assert_gap_free_positions(&credentials, BIG_BSL_BITS);
+    for (status_type, credentials) in [(StatusType::Issuance, &credentials), (StatusType::Revocation, &revoked)] {
+        let collection = issuer.status_service.big_collections.get(&status_type).unwrap().clone_with_type::<Document>();
+
+        for (list_id, indexes) in get_indexes_by_list(credentials) {
+            assert_status_chain(&collection, list_id, indexes.len()).await;
+
+            let status = issuer.status_service.get_latest_big_status(&status_type, list_id).await.unwrap();
+            assert_eq!(status.get_set_indexes().into_iter().collect::<BTreeSet<u64>>(), indexes, "list {} of {} lost a write", list_id, collection.name());
+        }
+    }
+
+    issuer.remove().await;
+}
@@ -7,7 +7,11 @@ source .env
 
 echo "Starting containers..."
 echo "Starting MongoDBs..."
-docker run --name mongo0 -d -p 27017:27017 --network zkssi --rm mongo:latest
+docker run --name mongo0 -d -p 27017:27017 --network zkssi --rm mongo:latest --replSet rs0 --bind_ip_all
+# transactions need a replica set, even a single-node one
+until docker exec mongo0 mongosh --quiet --eval "try { rs.status() } catch (e) { rs.initiate({_id: 'rs0', members: [{_id: 0, host: 'mongo0:27017'}]}) }"; do
+    sleep 1
+done
 
 echo "Starting APIs..."
 docker run --name api0 -dit -p 3000:8000 --network zkssi \
@@ -22,14 +26,16 @@ docker run --name api0 -dit -p 3000:8000 --network zkssi \
     # /bin/bash
 
 
+# each oracle reads its key from secrets/oracle<ID>/keystore.json, unlocked with secrets/oracle<ID>/password,
+# e.g. `cast wallet import oracle0 --interactive --keystore-dir secrets/oracle0` then rename the file to keystore.json
 echo "Starting Listeners..."
 docker run --name oracle0l -dit --network zkssi \
     -v $(pwd)/../zk_oracles:/app/zk_oracles \
     -v $(pwd)/../deployments:/app/deployments \
     -v $(pwd)/../zkcdid-lib-rs:/app/zkcdid-lib-rs \
+    -v $(pwd)/secrets/oracle0:/run/secrets/oracle:ro \
     -e ID=0 \
     -e TYPE=oracle \
-    -e PRIVATE_KEY=$PRIVATE_KEY \
     -e MONGO_URL=mongodb://mongo0:27017 \
     zkcrosschaindid/oracle:1.0 \
     cargo watch -x 'run --bin listener'
@@ -39,9 +45,9 @@ docker run --name oracle0 -dit -p 4000:8000 --network zkssi \
     -v $(pwd)/../zk_oracles:/app/zk_oracles \
     -v $(pwd)/../deployments:/app/deployments \
     -v $(pwd)/../zkcdid-lib-rs:/app/zkcdid-lib-rs \
+    -v $(pwd)/secrets/oracle0:/run/secrets/oracle:ro \
     -e ID=0 \
     -e TYPE=oracle \
-    -e PRIVATE_KEY=$PRIVATE_KEY \
     -e MONGO_URL=mongodb://mongo0:27017 \
     zkcrosschaindid/oracle:1.0 \
     cargo watch -x 'run --bin server'
\ No newline at end of file
@@ -7,7 +7,11 @@ source .env
 
 echo "Starting containers..."
 echo "Starting MongoDBs..."
-docker run --name mongo0 -d -p 27017:27017 --network zkssi --rm mongo:latest
+docker run --name mongo0 -d -p 27017:27017 --network zkssi --rm mongo:latest --replSet rs0 --bind_ip_all
+# transactions need a replica set, even a single-node one
+until docker exec mongo0 mongosh --quiet --eval "try { rs.status() } catch (e) { rs.initiate({_id: 'rs0', members: [{_id: 0, host: 'mongo0:27017'}]}) }"; do
+    sleep 1
+done
 
 echo "Starting APIs..."
 docker run --name api0 -dit -p 3000:8000 --network zkssi \
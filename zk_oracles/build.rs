@@ -1,5 +1,6 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::compile_protos("proto/helloworld.proto")?;
+    tonic_build::compile_protos("proto/oracle_node.proto")?;
     // tonic_build::compile_protos("proto/status_exchange.proto")?;
     Ok(())
-}
\ No newline at end of file
+}
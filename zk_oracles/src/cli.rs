@@ -0,0 +1,141 @@
+use zk_oracles::{config::NodeConfig, errors::{OracleError, OracleResult}, models::{report_delivery::{DeliveryStatus, ReportDelivery}, request_lifecycle::RequestLifecycle}, services::{report_outbox_service::ReportOutboxService, request_lifecycle_service::RequestLifecycleService}};
+use zkcdid_lib_rs::utils::db;
+
+const USAGE: &str = "Usage:
+    cli deliveries <request_id>
+        show the deliveries of a request
+    cli deliveries --status <pending|delivered|expired|cancelled|rejected>
+        show the deliveries with a status
+    cli requests <request_id>
+        show the lifecycle of a request
+    cli requests --state <received|fetched|reported|quorum_reached|submitted|confirmed|failed>
+        show the requests in a state
+    cli requests --stuck <seconds>
+        show the unfinished requests that have not moved for the given time
+    cli config check
+        show the effective config with the secrets hidden and check it";
+
+fn print_deliveries(deliveries: &[ReportDelivery]) {
+    println!("{:<66} {:>10} {:>10} {:>8} {:<25} last_error", "request_id", "aggregator", "status", "attempts", "next_attempt_at");
+    for delivery in deliveries.iter() {
+        println!(
+            "{:<66} {:>10} {:>10} {:>8} {:<25} {}",
+            delivery.request_id,
+            delivery.aggregator_id,
+            format!("{:?}", delivery.status),
+            delivery.attempts,
+            delivery.next_attempt_at.try_to_rfc3339_string().unwrap_or_default(),
+            delivery.last_error.clone().unwrap_or_default(),
+        );
+    }
+}
+
+fn parse_delivery_status(value: &str) -> OracleResult<DeliveryStatus> {
+    match value {
+        "pending" => Ok(DeliveryStatus::Pending),
+        "delivered" => Ok(DeliveryStatus::Delivered),
+        "expired" => Ok(DeliveryStatus::Expired),
+        "cancelled" => Ok(DeliveryStatus::Cancelled),
+        "rejected" => Ok(DeliveryStatus::Rejected),
+        _ => Err(OracleError::CommonError(format!("Unknown delivery status: {}", value))),
+    }
+}
+
+async fn show_deliveries(args: &[String]) -> OracleResult<()> {
+    let node_config = NodeConfig::load()?;
+    let database = db::get_db(&node_config.config).await?;
+    let outbox_service = ReportOutboxService::new(&database, &node_config.settings.outbox);
+
+    let deliveries = match args {
+        [flag, status] if flag == "--status" => outbox_service.get_deliveries_by_status(parse_delivery_status(status)?).await?,
+        [request_id] => outbox_service.get_deliveries_by_request_id(request_id).await?,
+        _ => return Err(OracleError::CommonError(USAGE.to_string())),
+    };
+
+    print_deliveries(&deliveries);
+    Ok(())
+}
+
+fn print_lifecycles(lifecycles: &[RequestLifecycle]) {
+    println!("{:<66} {:<15} {:<25} {:<25}", "request_id", "state", "created_at", "updated_at");
+    for lifecycle in lifecycles.iter() {
+        println!(
+            "{:<66} {:<15} {:<25} {:<25}",
+            lifecycle.request_id,
+            lifecycle.state.as_str(),
+            lifecycle.created_at.try_to_rfc3339_string().unwrap_or_default(),
+            lifecycle.updated_at.try_to_rfc3339_string().unwrap_or_default(),
+        );
+    }
+}
+
+fn print_history(lifecycle: &RequestLifecycle) {
+    println!("request_id: {}", lifecycle.request_id);
+    println!("onchain_request_id: {}", lifecycle.onchain_request_id);
+    println!("state: {}", lifecycle.state.as_str());
+    println!("{:<25} {:<15} note", "at", "state");
+    for transition in lifecycle.history.iter() {
+        println!(
+            "{:<25} {:<15} {}",
+            transition.at.try_to_rfc3339_string().unwrap_or_default(),
+            transition.state.as_str(),
+            transition.note.clone().unwrap_or_default(),
+        );
+    }
+}
+
+async fn show_requests(args: &[String]) -> OracleResult<()> {
+    let node_config = NodeConfig::load()?;
+    let database = db::get_db(&node_config.config).await?;
+    let lifecycle_service = RequestLifecycleService::new(&database);
+
+    match args {
+        [flag, state] if flag == "--state" => print_lifecycles(&lifecycle_service.find_by_state(state.parse()?).await?),
+        [flag, seconds] if flag == "--stuck" => {
+            let seconds: i64 = seconds.parse().map_err(|_| OracleError::CommonError(USAGE.to_string()))?;
+            print_lifecycles(&lifecycle_service.find_stuck(seconds * 1000).await?);
+        },
+        [request_id] => match lifecycle_service.find_one(request_id).await? {
+            Some(lifecycle) => print_history(&lifecycle),
+            None => println!("Request {} not found", request_id),
+        },
+        _ => return Err(OracleError::CommonError(USAGE.to_string())),
+    };
+
+    Ok(())
+}
+
+fn check_config(args: &[String]) -> OracleResult<()> {
+    if args.first().map(|command| command.as_str()) != Some("check") {
+        return Err(OracleError::CommonError(USAGE.to_string()));
+    }
+
+    let node_config = NodeConfig::load()?;
+    println!("{}", serde_json::to_string_pretty(&node_config.to_redacted()?)?);
+
+    let problems = node_config.get_problems();
+    if problems.is_empty() {
+        println!("The config is valid");
+        return Ok(());
+    }
+
+    for problem in problems.iter() {
+        println!("- {}", problem);
+    }
+    Err(OracleError::ConfigError(format!("{} problem(s) found", problems.len())))
+}
+
+#[tokio::main]
+async fn main() -> OracleResult<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(|command| command.as_str()) {
+        Some("deliveries") => show_deliveries(&args[1..]).await,
+        Some("requests") => show_requests(&args[1..]).await,
+        Some("config") => check_config(&args[1..]),
+        _ => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+    }
+}
@@ -1,4 +1,4 @@
-use zk_oracles::services::status_exchange_service::StatusExchangeService;
+use zk_oracles::{config::NodeConfig, services::{signer_service::SignerService, status_exchange_service::StatusExchangeService}};
 use zkcdid_lib_rs::models::{request_report::RequestReport, status_state::StatusState};
 
 
@@ -8,7 +8,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let url = "http://oracle0:50051";
     println!("url={:?}", url);
     let statuses = vec![StatusState::get_sample_status()];
-    let service = StatusExchangeService::new();
+    let node_config = NodeConfig::load()?;
+    let signer = SignerService::load(&node_config.config, &node_config.settings.signer).await?;
+    let service = StatusExchangeService::new(&node_config.config, &node_config.settings.outbox, &signer);
     let report = RequestReport::new(
         "0".to_string(),
         0,
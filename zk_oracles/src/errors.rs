@@ -1,7 +1,10 @@
-use std::net::AddrParseError;
+use std::{collections::HashMap, net::AddrParseError};
 use alloy::transports::{RpcError, TransportErrorKind};
+use prost::Message;
 use thiserror::Error;
 
+use crate::{models::request_failure::FailureCode, oracle_node::ErrorDetail, utils::mongo::is_duplicate_key_error};
+
 #[derive(Error, Debug)]
 pub enum OracleError {
     #[error("Common Error: {0}")]
@@ -51,12 +54,172 @@ pub enum OracleError {
 
     #[error("Bson Serialize Error: {0}")]
     BsonSerializeError(#[from] bson::ser::Error),
+
+    #[error("Transaction {0} is still pending after {1} fee bumps")]
+    TransactionStuckError(String, u32),
+
+    #[error("Issuer Data Error ({0:?}): {1}")]
+    IssuerDataError(FailureCode, String),
+
+    #[error("Unsupported Status Mechanism: {0}")]
+    UnsupportedMechanismError(String),
+
+    #[error("Prover Error: {0}")]
+    ProverError(String),
+
+    #[error("Request {0} was cancelled")]
+    RequestCancelledError(String),
+
+    #[error("Oracle {0} is not registered")]
+    UnknownOracleError(u8),
+
+    #[error("Request {0} not found")]
+    RequestNotFoundError(String),
+
+    #[error("Request {0} is not stored by this oracle yet")]
+    RequestNotReadyError(String),
+
+    #[error("This oracle is not an aggregator of request {0}")]
+    NotAggregatorError(String),
+
+    #[error("Invalid report: {0}")]
+    InvalidReportError(String),
+
+    #[error("Report of {0} bytes exceeds the limit of {1} bytes")]
+    ReportTooLargeError(usize, usize),
+
+    #[error("Config Error: {0}")]
+    ConfigError(String),
+
+    #[error("Signer Error: {0}")]
+    SignerError(String),
+
+    #[error("Report is not signed by oracle {0}")]
+    InvalidSignatureError(u8),
 }
 
 pub type OracleResult<T> = Result<T, OracleError>;
 
+const ERROR_DOMAIN: &str = "zk_oracles";
+
+impl OracleError {
+    /// The gRPC code a client sees for this error.
+    pub fn get_code(&self) -> tonic::Code {
+        match self {
+            OracleError::AddressParseError(_)
+            | OracleError::UrlParseError(_)
+            | OracleError::ContractNotDeployedOnChainError(_, _)
+            | OracleError::ConfigError(_) => tonic::Code::FailedPrecondition,
+            OracleError::ServerError(_) | OracleError::WebSocketError(_) => tonic::Code::Unavailable,
+            OracleError::HexParseError(_) | OracleError::SolidityError(_) => tonic::Code::InvalidArgument,
+            OracleError::ContractError(alloy::contract::Error::TransportError(e)) if e.as_error_resp().is_none() => tonic::Code::Unavailable,
+            // the call reached the chain and was rejected by the contract
+            OracleError::ContractError(_) => tonic::Code::FailedPrecondition,
+            OracleError::PendingContractError(_) | OracleError::TransactionStuckError(_, _) => tonic::Code::DeadlineExceeded,
+            OracleError::DatabaseError(e) if is_duplicate_key_error(e) => tonic::Code::AlreadyExists,
+            OracleError::DatabaseError(_) => tonic::Code::Unavailable,
+            OracleError::ApiCallingError(e) if e.is_timeout() => tonic::Code::DeadlineExceeded,
+            OracleError::ApiCallingError(_) => tonic::Code::Unavailable,
+            OracleError::RPCError(status) => status.code(),
+            OracleError::IssuerDataError(FailureCode::IssuerUnavailable, _) => tonic::Code::Unavailable,
+            OracleError::IssuerDataError(_, _) => tonic::Code::FailedPrecondition,
+            OracleError::UnsupportedMechanismError(_) => tonic::Code::Unimplemented,
+            OracleError::RequestCancelledError(_) => tonic::Code::FailedPrecondition,
+            OracleError::UnknownOracleError(_) | OracleError::NotAggregatorError(_) => tonic::Code::PermissionDenied,
+            OracleError::RequestNotFoundError(_) => tonic::Code::NotFound,
+            OracleError::RequestNotReadyError(_) => tonic::Code::Unavailable,
+            OracleError::InvalidReportError(_) => tonic::Code::InvalidArgument,
+            OracleError::ReportTooLargeError(_, _) => tonic::Code::ResourceExhausted,
+            OracleError::InvalidSignatureError(_) => tonic::Code::Unauthenticated,
+            // the remote signer may be restarting
+            OracleError::SignerError(_) => tonic::Code::Unavailable,
+            OracleError::CommonError(_)
+            | OracleError::UnknownError(_)
+            | OracleError::SerdeJsonError(_)
+            | OracleError::BsonSerializeError(_)
+            | OracleError::ProverError(_) => tonic::Code::Internal,
+        }
+    }
+
+    /// A stable, machine-readable name of the error.
+    pub fn get_reason(&self) -> &'static str {
+        match self {
+            OracleError::CommonError(_) => "COMMON",
+            OracleError::AddressParseError(_) => "ADDRESS_PARSE",
+            OracleError::UrlParseError(_) => "URL_PARSE",
+            OracleError::ServerError(_) => "SERVER",
+            OracleError::UnknownError(_) => "UNKNOWN",
+            OracleError::SerdeJsonError(_) => "JSON",
+            OracleError::ContractNotDeployedOnChainError(_, _) => "CONTRACT_NOT_DEPLOYED",
+            OracleError::HexParseError(_) => "HEX_PARSE",
+            OracleError::ContractError(_) => "CONTRACT",
+            OracleError::PendingContractError(_) => "PENDING_TRANSACTION",
+            OracleError::DatabaseError(_) => "DATABASE",
+            OracleError::WebSocketError(_) => "TRANSPORT",
+            OracleError::SolidityError(_) => "SOLIDITY_ENCODING",
+            OracleError::ApiCallingError(_) => "API_CALL",
+            OracleError::RPCError(_) => "RPC",
+            OracleError::BsonSerializeError(_) => "BSON",
+            OracleError::TransactionStuckError(_, _) => "TRANSACTION_STUCK",
+            OracleError::IssuerDataError(_, _) => "ISSUER_DATA",
+            OracleError::UnsupportedMechanismError(_) => "UNSUPPORTED_MECHANISM",
+            OracleError::ProverError(_) => "PROVER",
+            OracleError::RequestCancelledError(_) => "REQUEST_CANCELLED",
+            OracleError::UnknownOracleError(_) => "UNKNOWN_ORACLE",
+            OracleError::RequestNotFoundError(_) => "REQUEST_NOT_FOUND",
+            OracleError::RequestNotReadyError(_) => "REQUEST_NOT_READY",
+            OracleError::NotAggregatorError(_) => "NOT_AGGREGATOR",
+            OracleError::InvalidReportError(_) => "INVALID_REPORT",
+            OracleError::ReportTooLargeError(_, _) => "REPORT_TOO_LARGE",
+            OracleError::ConfigError(_) => "CONFIG",
+            OracleError::SignerError(_) => "SIGNER",
+            OracleError::InvalidSignatureError(_) => "INVALID_SIGNATURE",
+        }
+    }
+
+    fn get_metadata(&self) -> HashMap<String, String> {
+        let entries: Vec<(&str, String)> = match self {
+            OracleError::RequestCancelledError(request_id)
+            | OracleError::RequestNotFoundError(request_id)
+            | OracleError::RequestNotReadyError(request_id)
+            | OracleError::NotAggregatorError(request_id) => vec![("request_id", request_id.clone())],
+            OracleError::UnknownOracleError(oracle_id)
+            | OracleError::InvalidSignatureError(oracle_id) => vec![("oracle_id", oracle_id.to_string())],
+            OracleError::ReportTooLargeError(size, limit) => vec![("size", size.to_string()), ("limit", limit.to_string())],
+            OracleError::TransactionStuckError(tx_hash, num_bumps) => vec![("tx_hash", tx_hash.clone()), ("num_bumps", num_bumps.to_string())],
+            OracleError::IssuerDataError(code, _) => vec![("failure_code", (*code as u32).to_string())],
+            OracleError::ContractNotDeployedOnChainError(chain_id, contract) => vec![("chain_id", chain_id.to_string()), ("contract", contract.clone())],
+            _ => vec![],
+        };
+
+        entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+    }
+
+    /// Whether the same call may succeed later without any change on the caller's side.
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_code(self.get_code())
+    }
+}
+
+pub fn is_retryable_code(code: tonic::Code) -> bool {
+    matches!(code, tonic::Code::Unavailable | tonic::Code::DeadlineExceeded | tonic::Code::Aborted)
+}
+
+/// Reads the details attached by `From<OracleError> for tonic::Status`, if any.
+pub fn get_error_detail(status: &tonic::Status) -> Option<ErrorDetail> {
+    ErrorDetail::decode(status.details()).ok().filter(|detail| detail.domain == ERROR_DOMAIN)
+}
+
 impl From<OracleError> for tonic::Status {
     fn from(error: OracleError) -> tonic::Status {
-        tonic::Status::new(tonic::Code::Internal, error.to_string())
+        let code = error.get_code();
+        let detail = ErrorDetail {
+            reason: error.get_reason().to_string(),
+            domain: ERROR_DOMAIN.to_string(),
+            metadata: error.get_metadata(),
+            retryable: is_retryable_code(code),
+        };
+
+        tonic::Status::with_details(code, error.to_string(), detail.encode_to_vec().into())
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,10 @@
 pub mod errors;
 pub mod utils;
-pub mod services;
\ No newline at end of file
+pub mod services;
+pub mod models;
+pub mod settings;
+pub mod config;
+
+pub mod oracle_node {
+    tonic::include_proto!("oracle_node");
+}
\ No newline at end of file
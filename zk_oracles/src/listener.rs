@@ -1,10 +1,18 @@
-use zk_oracles::{errors::OracleResult, services::oracle_manager_service::OracleManagerService};
+use std::sync::Arc;
 
-async fn initialize() -> OracleResult<()> {
+use zk_oracles::{config::NodeConfig, errors::OracleResult, services::{fulfillment_claim_service::FulfillmentClaimService, issuer_key_service::IssuerKeyService, log_buffer_service::LogBufferService, oracle_manager_service::OracleManagerService, request_report_service::RequestReportService}};
+use zkcdid_lib_rs::utils::db;
+
+async fn initialize(manager_service: &OracleManagerService) -> OracleResult<()> {
     // register this oracle to the smart contract
-    let manager_service = OracleManagerService::new();
     let config = &manager_service.config;
 
+    let database = db::get_db(config).await?;
+    RequestReportService::new(&database, config).ensure_indexes().await?;
+    FulfillmentClaimService::new(&database).ensure_indexes().await?;
+    IssuerKeyService::new(&database).ensure_indexes().await?;
+    LogBufferService::new(&database).ensure_indexes().await?;
+
     while let Ok(false) = manager_service.is_this_oracle_registered().await {
         println!("Oracle is not registered. Registering...");
 
@@ -26,9 +34,8 @@ async fn initialize() -> OracleResult<()> {
     Ok(())
 }
 
-async fn listen() -> OracleResult<()> {
+async fn listen(manager_service: &Arc<OracleManagerService>) -> OracleResult<()> {
     // listen for events from the chain
-    let manager_service = OracleManagerService::new();
     println!("Listening for events from OracleManager contract at {:?}...", manager_service.contract_address);
 
     loop {
@@ -43,14 +50,48 @@ async fn listen() -> OracleResult<()> {
     }
 }
 
+async fn run_outbox(manager_service: Arc<OracleManagerService>) {
+    // retry the report deliveries that could not be sent to aggregators,
+    // and the fulfillments that could not be sent to the contract
+
+    loop {
+        if let Err(e) = manager_service.dispatch_pending_deliveries().await {
+            println!("Error dispatching pending deliveries: {:?}", e);
+        }
+
+        if let Err(e) = manager_service.retry_fulfillments().await {
+            println!("Error retrying fulfillments: {:?}", e);
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(manager_service.settings.outbox.poll_interval)).await;
+    }
+}
+
+async fn run_request_queue(manager_service: Arc<OracleManagerService>) {
+    // resume the requests left unfinished by a previous run and retry the failed ones
+
+    loop {
+        if let Err(e) = manager_service.process_queued_requests().await {
+            println!("Error processing queued requests: {:?}", e);
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(manager_service.settings.queue.poll_interval)).await;
+    }
+}
 
 #[tokio::main]
 async fn main() -> OracleResult<()> {
+    // a bad config stops the node here rather than in the middle of a request
+    let manager_service = Arc::new(OracleManagerService::new(&NodeConfig::load()?).await?);
+
     // initialize the oracle
-    initialize().await?;
+    initialize(&manager_service).await?;
+
+    tokio::spawn(run_outbox(manager_service.clone()));
+    tokio::spawn(run_request_queue(manager_service.clone()));
 
     // listen for events from the chain
-    listen().await?;
+    listen(&manager_service).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+use alloy::rpc::types::Log;
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+/// A contract log of the listener's confirmation buffer, stored so that a restarted listener
+/// still handles its pending logs and can roll back the processed ones removed by a reorg.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BufferedLog {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub block_number: u64,
+    pub log_index: u64,
+    pub log: Log,
+    pub processed: bool,
+    /// The local request created for a processed log, if any.
+    pub request_id: Option<String>,
+}
+
+impl BufferedLog {
+    /// `None` for logs without a position in the chain.
+    pub fn new(log: &Log, processed: bool, request_id: Option<String>) -> Option<Self> {
+        Some(Self {
+            id: None,
+            block_number: log.block_number?,
+            log_index: log.log_index?,
+            log: log.clone(),
+            processed,
+            request_id,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimStatus {
+    /// The response is being prepared and sent, or its transaction (`tx_hash`) is stuck in the mempool.
+    Claimed,
+    /// The fulfillment transaction was mined.
+    Submitted,
+    /// The fulfillment transaction was mined but reverted.
+    Reverted,
+    /// Nothing was mined; the request can be claimed again.
+    Released,
+}
+
+/// The right of one task to send the fulfillment of a request; there is at most one claim per request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FulfillmentClaim {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub request_id: String,
+    pub aggregator_id: u8,
+    pub status: ClaimStatus,
+    pub tx_hash: Option<String>,
+    pub last_error: Option<String>,
+    pub claimed_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl FulfillmentClaim {
+    pub fn new(request_id: String, aggregator_id: u8) -> Self {
+        let now = DateTime::now();
+
+        Self {
+            id: None,
+            request_id,
+            aggregator_id,
+            status: ClaimStatus::Claimed,
+            tx_hash: None,
+            last_error: None,
+            claimed_at: now,
+            updated_at: now,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use super::response_type::ResponseType;
+
+/// What it cost to fulfill a request on-chain.
+///
+/// Fee values are kept as decimal strings because BSON has no 128-bit integer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FulfillmentRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub request_id: String,
+    /// `None` for error fulfillments.
+    pub response_type: Option<ResponseType>,
+    pub subscription_id: u64,
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub effective_gas_price: String,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub num_bumps: u32,
+}
@@ -0,0 +1,34 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// The key of an issuer as published on its `/issuer/key` endpoint or in its DID document.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IssuerPublicKey {
+    pub algorithm: String,
+    pub key_id: String,
+    /// Base64url without padding.
+    pub public_key: String,
+}
+
+/// The key an issuer was first seen with. Later statuses must be signed with the same key,
+/// so that a spoofed endpoint cannot swap it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IssuerKey {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    /// The issuer url or DID of the requests.
+    pub url: String,
+    pub key: IssuerPublicKey,
+    pub pinned_at: DateTime,
+}
+
+impl IssuerKey {
+    pub fn new(url: String, key: IssuerPublicKey) -> Self {
+        Self {
+            id: None,
+            url,
+            key,
+            pinned_at: DateTime::now(),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+pub mod fulfillment_record;
+pub mod request_failure;
+pub mod response_type;
+pub mod report_delivery;
+pub mod registered_oracle;
+pub mod request_lifecycle;
+pub mod request_task;
+pub mod fulfillment_claim;
+pub mod issuer_key;
+pub mod buffered_log;
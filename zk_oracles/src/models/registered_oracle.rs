@@ -0,0 +1,55 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use zkcdid_lib_rs::{contracts::ZKOracleManager, models::oracle::Oracle};
+
+/// Local copy of an oracle registered in `ZKOracleManager`.
+///
+/// `addOracle` never changes an existing entry, so a mirrored oracle never goes stale.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RegisteredOracle {
+    pub id: u8,
+    /// Checksummed address, so that lookups by address do not depend on the letter case.
+    pub oracle_address: String,
+    pub url: String,
+    pub amount: u64,
+}
+
+fn normalize_address(address: &str) -> String {
+    match address.parse::<Address>() {
+        Ok(address) => address.to_string(),
+        Err(_) => address.to_string(),
+    }
+}
+
+impl From<&Oracle> for RegisteredOracle {
+    fn from(oracle: &Oracle) -> Self {
+        Self {
+            id: oracle.id,
+            oracle_address: normalize_address(&oracle.oracle_address),
+            url: oracle.url.clone(),
+            amount: oracle.amount,
+        }
+    }
+}
+
+impl From<ZKOracleManager::Oracle> for RegisteredOracle {
+    fn from(oracle: ZKOracleManager::Oracle) -> Self {
+        Self {
+            id: oracle.id,
+            oracle_address: oracle.oracleAddress.to_string(),
+            url: oracle.url,
+            amount: oracle.amount,
+        }
+    }
+}
+
+impl From<&RegisteredOracle> for Oracle {
+    fn from(oracle: &RegisteredOracle) -> Self {
+        Oracle {
+            id: oracle.id,
+            oracle_address: oracle.oracle_address.clone(),
+            url: oracle.url.clone(),
+            amount: oracle.amount,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use zkcdid_lib_rs::models::request_report::RequestReport;
+
+use super::request_failure::RequestFailure;
+
+/// What is delivered to an aggregator: the oracle's report, or why it could not make one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum DeliveryPayload {
+    Report(RequestReport),
+    Failure(RequestFailure),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Expired,
+    Cancelled,
+    /// The aggregator answered with an error that retrying cannot fix.
+    Rejected,
+}
+
+/// One (request, aggregator) delivery of the report outbox.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReportDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub request_id: String,
+    pub aggregator_id: u8,
+    pub payload: DeliveryPayload,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime,
+    pub next_attempt_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+impl ReportDelivery {
+    pub fn new(request_id: String, aggregator_id: u8, payload: DeliveryPayload, ttl_millis: i64) -> Self {
+        let now = DateTime::now();
+
+        Self {
+            id: None,
+            request_id,
+            aggregator_id,
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            created_at: now,
+            next_attempt_at: now,
+            expires_at: DateTime::from_millis(now.timestamp_millis() + ttl_millis),
+        }
+    }
+}
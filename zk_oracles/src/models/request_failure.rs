@@ -0,0 +1,135 @@
+use alloy::{dyn_abi::DynSolValue, primitives::{Bytes, U256}};
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::OracleError, oracle_node::FailureReport};
+
+/// Why an oracle could not produce a report for a request.
+///
+/// The numeric value is the `code` of the `err` bytes sent to `fulfillRequest`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureCode {
+    IssuerUnavailable = 1,
+    EmptyHistory = 2,
+    InvalidHistory = 3,
+}
+
+impl TryFrom<u32> for FailureCode {
+    type Error = OracleError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(FailureCode::IssuerUnavailable),
+            2 => Ok(FailureCode::EmptyHistory),
+            3 => Ok(FailureCode::InvalidHistory),
+            _ => Err(OracleError::InvalidReportError(format!("Unknown failure code: {}", value))),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RequestFailure {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub request_id: String,
+    pub oracle_id: u8,
+    pub code: FailureCode,
+    pub reason: String,
+}
+
+impl RequestFailure {
+    pub fn new(request_id: String, oracle_id: u8, code: FailureCode, reason: String) -> Self {
+        Self {
+            id: None,
+            request_id,
+            oracle_id,
+            code,
+            reason,
+        }
+    }
+
+    /// Encodes the failure as `abi.encode(uint8 code, string reason)`.
+    pub fn to_err_bytes(&self) -> Bytes {
+        let err_value = DynSolValue::Tuple(vec![
+            DynSolValue::Uint(U256::from(self.code as u8), 8),
+            DynSolValue::String(self.reason.clone()),
+        ]);
+
+        Bytes::from(err_value.abi_encode_params())
+    }
+}
+
+/// The first reported failure whose code at least `num_agreements` oracles reported, so that the
+/// contract only gets a failure a quorum agrees on. Failures of mixed codes are no quorum.
+pub fn get_agreed_failure(failures: &[RequestFailure], num_agreements: usize) -> Option<&RequestFailure> {
+    failures.iter().find(|failure| {
+        failures.iter().filter(|other| other.code == failure.code).count() >= num_agreements.max(1)
+    })
+}
+
+impl From<&RequestFailure> for FailureReport {
+    fn from(failure: &RequestFailure) -> Self {
+        FailureReport {
+            oracle_id: failure.oracle_id.into(),
+            request_id: failure.request_id.clone(),
+            code: failure.code as u32,
+            reason: failure.reason.clone(),
+        }
+    }
+}
+
+impl TryFrom<FailureReport> for RequestFailure {
+    type Error = OracleError;
+
+    fn try_from(report: FailureReport) -> Result<Self, Self::Error> {
+        let oracle_id = u8::try_from(report.oracle_id)
+            .map_err(|_| OracleError::InvalidReportError(format!("Invalid oracle id: {}", report.oracle_id)))?;
+
+        Ok(RequestFailure::new(report.request_id, oracle_id, FailureCode::try_from(report.code)?, report.reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_failures(codes: &[FailureCode]) -> Vec<RequestFailure> {
+        codes.iter().enumerate()
+            .map(|(oracle_id, code)| RequestFailure::new("request".to_string(), oracle_id as u8, *code, format!("{:?}", code)))
+            .collect()
+    }
+
+    #[test]
+    fn a_quorum_needs_failures_of_the_same_code() {
+        let failures = get_failures(&[FailureCode::IssuerUnavailable, FailureCode::EmptyHistory, FailureCode::InvalidHistory]);
+
+        assert_eq!(get_agreed_failure(&failures, 3), None);
+        assert_eq!(get_agreed_failure(&failures, 1), Some(&failures[0]));
+    }
+
+    #[test]
+    fn the_agreed_code_is_the_one_reaching_the_quorum() {
+        let failures = get_failures(&[FailureCode::IssuerUnavailable, FailureCode::EmptyHistory, FailureCode::EmptyHistory]);
+
+        assert_eq!(get_agreed_failure(&failures, 2), Some(&failures[1]));
+        assert_eq!(get_agreed_failure(&failures, 3), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_reported_code() {
+        let failures = get_failures(&[FailureCode::InvalidHistory, FailureCode::IssuerUnavailable, FailureCode::IssuerUnavailable, FailureCode::InvalidHistory]);
+
+        assert_eq!(get_agreed_failure(&failures, 2), Some(&failures[0]));
+    }
+
+    #[test]
+    fn failures_encode_their_code_and_reason() {
+        let failure = RequestFailure::new("request".to_string(), 1, FailureCode::InvalidHistory, "bad".to_string());
+        let err = failure.to_err_bytes();
+
+        assert_eq!(err[31], 3);
+        assert_eq!(err[63], 64);
+        assert_eq!(err[95], 3);
+        assert_eq!(&err[96..99], b"bad");
+    }
+}
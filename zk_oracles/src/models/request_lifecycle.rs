@@ -0,0 +1,221 @@
+use std::str::FromStr;
+
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use zkcdid_lib_rs::models::status_state::StatusMechanism;
+
+use crate::{errors::OracleError, oracle_node};
+
+/// Where a request is in its processing on this node.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestState {
+    Received,
+    Fetched,
+    Reported,
+    QuorumReached,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+impl RequestState {
+    pub const ALL: [RequestState; 7] = [
+        RequestState::Received,
+        RequestState::Fetched,
+        RequestState::Reported,
+        RequestState::QuorumReached,
+        RequestState::Submitted,
+        RequestState::Confirmed,
+        RequestState::Failed,
+    ];
+
+    fn rank(&self) -> u8 {
+        match self {
+            RequestState::Received => 0,
+            RequestState::Fetched => 1,
+            RequestState::Reported => 2,
+            RequestState::QuorumReached => 3,
+            RequestState::Submitted => 4,
+            RequestState::Confirmed | RequestState::Failed => 5,
+        }
+    }
+
+    /// Whether a request in this state has already gone through `state`.
+    pub fn has_reached(&self, state: RequestState) -> bool {
+        self.rank() >= state.rank()
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(self, RequestState::Confirmed | RequestState::Failed)
+    }
+
+    /// A request only moves forward, although it may skip states: an aggregator can reach
+    /// the quorum with the reports of other oracles before it has reported itself.
+    pub fn can_transition_to(&self, next: RequestState) -> bool {
+        !self.is_final() && next.rank() > self.rank()
+    }
+
+    /// States from which `next` can be reached.
+    pub fn get_previous_states(next: RequestState) -> Vec<RequestState> {
+        RequestState::ALL.into_iter().filter(|state| state.can_transition_to(next)).collect()
+    }
+}
+
+impl RequestState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestState::Received => "received",
+            RequestState::Fetched => "fetched",
+            RequestState::Reported => "reported",
+            RequestState::QuorumReached => "quorum_reached",
+            RequestState::Submitted => "submitted",
+            RequestState::Confirmed => "confirmed",
+            RequestState::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for RequestState {
+    type Err = OracleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RequestState::ALL
+            .into_iter()
+            .find(|state| state.as_str() == s)
+            .ok_or(OracleError::CommonError(format!("Unknown request state: {}", s)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StateTransition {
+    pub state: RequestState,
+    pub at: DateTime,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RequestLifecycle {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub request_id: String,
+    /// The `bytes32` id as emitted by `ZKOracleManager` events.
+    pub onchain_request_id: String,
+    pub status_mechanism: StatusMechanism,
+    pub state: RequestState,
+    /// When this node sent its own report or failure. Tracked apart from `state`, which also moves
+    /// with the reports of the other oracles.
+    #[serde(default)]
+    pub reported_at: Option<DateTime>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub history: Vec<StateTransition>,
+}
+
+impl RequestLifecycle {
+    pub fn new(request_id: String, onchain_request_id: String, status_mechanism: StatusMechanism) -> Self {
+        let now = DateTime::now();
+
+        Self {
+            id: None,
+            request_id,
+            onchain_request_id,
+            status_mechanism,
+            state: RequestState::Received,
+            reported_at: None,
+            created_at: now,
+            updated_at: now,
+            history: vec![StateTransition {
+                state: RequestState::Received,
+                at: now,
+                note: None,
+            }],
+        }
+    }
+}
+
+impl From<StateTransition> for oracle_node::StateTransition {
+    fn from(transition: StateTransition) -> Self {
+        Self {
+            state: transition.state.as_str().to_string(),
+            at: transition.at.timestamp_millis(),
+            note: transition.note.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<RequestLifecycle> for oracle_node::RequestLifecycleReply {
+    fn from(lifecycle: RequestLifecycle) -> Self {
+        Self {
+            request_id: lifecycle.request_id,
+            onchain_request_id: lifecycle.onchain_request_id,
+            state: lifecycle.state.as_str().to_string(),
+            created_at: lifecycle.created_at.timestamp_millis(),
+            updated_at: lifecycle.updated_at.timestamp_millis(),
+            history: lifecycle.history.into_iter().map(oracle_node::StateTransition::from).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_only_move_forward() {
+        assert!(RequestState::Received.can_transition_to(RequestState::Fetched));
+        assert!(RequestState::Reported.can_transition_to(RequestState::QuorumReached));
+        assert!(RequestState::Submitted.can_transition_to(RequestState::Confirmed));
+        assert!(!RequestState::Reported.can_transition_to(RequestState::Fetched));
+        assert!(!RequestState::Fetched.can_transition_to(RequestState::Fetched));
+    }
+
+    #[test]
+    fn states_can_be_skipped() {
+        // an aggregator can reach the quorum before it has reported itself
+        assert!(RequestState::Received.can_transition_to(RequestState::QuorumReached));
+        assert!(RequestState::Fetched.can_transition_to(RequestState::Failed));
+    }
+
+    #[test]
+    fn final_states_do_not_move() {
+        for state in RequestState::ALL {
+            assert!(!RequestState::Confirmed.can_transition_to(state));
+            assert!(!RequestState::Failed.can_transition_to(state));
+        }
+    }
+
+    #[test]
+    fn previous_states_are_the_ones_that_can_move_to_the_state() {
+        assert_eq!(RequestState::get_previous_states(RequestState::Received), vec![]);
+        assert_eq!(RequestState::get_previous_states(RequestState::Reported), vec![RequestState::Received, RequestState::Fetched]);
+        assert_eq!(
+            RequestState::get_previous_states(RequestState::Failed),
+            vec![RequestState::Received, RequestState::Fetched, RequestState::Reported, RequestState::QuorumReached, RequestState::Submitted]
+        );
+    }
+
+    #[test]
+    fn has_reached_follows_the_order_of_the_states() {
+        assert!(RequestState::QuorumReached.has_reached(RequestState::Reported));
+        assert!(!RequestState::Fetched.has_reached(RequestState::Reported));
+        assert!(RequestState::Failed.has_reached(RequestState::Confirmed));
+    }
+
+    #[test]
+    fn states_are_parsed_from_their_names() {
+        for state in RequestState::ALL {
+            assert_eq!(state.as_str().parse::<RequestState>().unwrap(), state);
+        }
+        assert!("unknown".parse::<RequestState>().is_err());
+    }
+
+    #[test]
+    fn new_lifecycles_are_received() {
+        let lifecycle = RequestLifecycle::new("1".to_string(), "0x01".to_string(), StatusMechanism::BitStatusList);
+
+        assert_eq!(lifecycle.state, RequestState::Received);
+        assert!(lifecycle.reported_at.is_none());
+        assert_eq!(lifecycle.history.len(), 1);
+        assert_eq!(lifecycle.history[0].state, RequestState::Received);
+    }
+}
@@ -0,0 +1,47 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use zkcdid_lib_rs::models::status_state::StatusMechanism;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Queued,
+    Processing,
+    Done,
+    Abandoned,
+}
+
+/// A received request waiting to be reported by this oracle.
+///
+/// A task stays `Processing` only while its lease runs, so a task left behind by a crashed node
+/// is picked up again once the lease has expired.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RequestTask {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub request_id: String,
+    pub status_mechanism: StatusMechanism,
+    pub status: TaskStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime,
+    pub next_attempt_at: DateTime,
+    pub lease_until: DateTime,
+}
+
+impl RequestTask {
+    pub fn new(request_id: String, status_mechanism: StatusMechanism) -> Self {
+        let now = DateTime::now();
+
+        Self {
+            id: None,
+            request_id,
+            status_mechanism,
+            status: TaskStatus::Queued,
+            attempts: 0,
+            last_error: None,
+            created_at: now,
+            next_attempt_at: now,
+            lease_until: now,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::OracleError;
+
+/// Mirrors `ZKOracleManager.ResponseType`; the discriminant is the value passed to `fulfillRequest`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResponseType {
+    LastStatus = 0,
+    AllStatuses = 1,
+    LastStatusWithProof = 2,
+}
+
+impl ResponseType {
+    pub const ALL: [ResponseType; 3] = [ResponseType::LastStatus, ResponseType::AllStatuses, ResponseType::LastStatusWithProof];
+}
+
+impl FromStr for ResponseType {
+    type Err = OracleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "last_status" => Ok(ResponseType::LastStatus),
+            "all_statuses" => Ok(ResponseType::AllStatuses),
+            "last_status_with_proof" => Ok(ResponseType::LastStatusWithProof),
+            _ => Err(OracleError::CommonError(format!("Unknown response type: {}", s))),
+        }
+    }
+}
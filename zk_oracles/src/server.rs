@@ -1,12 +1,23 @@
-use mongodb::Database;
 use zkcdid_lib_rs::{models::oracle_request::OracleRequest, status_exchange::{self, status_exchange_service_server::{StatusExchangeService, StatusExchangeServiceServer}, HelloReply, HelloRequest}};
+use std::sync::Arc;
+
+use prost::Message;
 use tonic::{transport::Server, Request, Response, Status};
-use zk_oracles::services::{oracle_manager_service, oracle_request_service::OracleRequestService, request_report_service::RequestReportService};
-use zkcdid_lib_rs::{config::Config, models::request_report::RequestReport, utils::db};
+use zk_oracles::{config::NodeConfig, models::{request_failure::RequestFailure, request_lifecycle::RequestState}, oracle_node::{self, oracle_node_service_server::{OracleNodeService, OracleNodeServiceServer}}, services::{fulfillment_claim_service::FulfillmentClaimService, issuer_key_service::IssuerKeyService, oracle_manager_service::OracleManagerService, request_failure_service::RequestFailureService, request_lifecycle_service::RequestLifecycleService, request_report_service::RequestReportService, signer_service::REPORT_SIGNATURE_METADATA_KEY}};
+use zkcdid_lib_rs::{models::request_report::RequestReport, utils::db};
 
 
-#[derive(Debug, Default)]
-pub struct MyStatusExchangeServer {}
+/// The sender's signature over the encoded message, see `StatusExchangeService::get_signed_request`.
+fn get_signature<T>(request: &Request<T>) -> Option<String> {
+    request.metadata()
+        .get(REPORT_SIGNATURE_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+pub struct MyStatusExchangeServer {
+    manager: Arc<OracleManagerService>,
+}
 
 #[tonic::async_trait]
 impl StatusExchangeService for MyStatusExchangeServer {
@@ -30,64 +41,90 @@ impl StatusExchangeService for MyStatusExchangeServer {
     ) -> Result<Response<status_exchange::RequestFulfillmentResult>, Status> {
         println!("Got a request: {:?}", request);
 
-        let config = Config::load_oracle_config();
+        let signature = get_signature(&request);
+        let fulfillment = request.into_inner();
+        let encoded = fulfillment.encode_to_vec();
+        let report = RequestReport::from(fulfillment);
 
-        println!("Connecting to database...");
-        let database: Database;
-        loop {
-            database = match db::get_db(&config).await {
-                Ok(db) => db,
-                Err(_) => continue,
-            };
-            break;
-        }
+        println!("Checking the report can be accepted...");
+        self.manager.authenticate_sender(report.oracle_id, &encoded, signature.as_deref()).await?;
+        self.manager.admit_report(&report, encoded.len()).await?;
+
+        println!("Recording the report and checking whether the quorum is reached...");
+        self.manager.record_report(&report).await?;
+
+        let reply = status_exchange::RequestFulfillmentResult {
+            result: true
+        };
+
+        Ok(Response::new(reply))
+    }
+}
+
+pub struct MyOracleNodeServer {
+    manager: Arc<OracleManagerService>,
+}
+
+#[tonic::async_trait]
+impl OracleNodeService for MyOracleNodeServer {
+    async fn report_failure(
+        &self,
+        request: Request<oracle_node::FailureReport>,
+    ) -> Result<Response<oracle_node::FailureReportResult>, Status> {
+        println!("Got a failure report: {:?}", request);
 
-        println!("Inserting or updating request report...");
-        let report_service = RequestReportService::new(&database);
-        let report = RequestReport::from(request.into_inner());
+        let database = db::get_db(&self.manager.config).await.map_err(|e| Status::unavailable(e.to_string()))?;
 
-        match report_service.insert_or_update(&report).await {
-            Ok(_) => println!("Request report inserted or updated successfully"),
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Err(e.into());
-            },
+        let signature = get_signature(&request);
+        let failure_report = request.into_inner();
+        let encoded = failure_report.encode_to_vec();
+
+        let failure = RequestFailure::try_from(failure_report)?;
+        self.manager.authenticate_sender(failure.oracle_id, &encoded, signature.as_deref()).await?;
+        self.manager.admit_sender(failure.oracle_id, &failure.request_id).await?;
+
+        let failure_service = RequestFailureService::new(&database);
+        failure_service.insert_or_update(&failure).await?;
+
+        println!("Checking the number of failures is enough to fail the request...");
+        self.manager.check_failures(&failure.request_id).await?;
+
+        let reply = oracle_node::FailureReportResult {
+            result: true
+        };
+
+        Ok(Response::new(reply))
+    }
+
+    async fn get_request_lifecycle(
+        &self,
+        request: Request<oracle_node::RequestLifecycleQuery>,
+    ) -> Result<Response<oracle_node::RequestLifecycleReply>, Status> {
+        let database = db::get_db(&self.manager.config).await.map_err(|e| Status::unavailable(e.to_string()))?;
+
+        let request_id = request.into_inner().request_id;
+        let lifecycle_service = RequestLifecycleService::new(&database);
+        match lifecycle_service.find_one(&request_id).await? {
+            Some(lifecycle) => Ok(Response::new(oracle_node::RequestLifecycleReply::from(lifecycle))),
+            None => Err(Status::not_found(format!("Request {} not found", request_id))),
         }
+    }
 
-        println!("Checking the number of reports is enough to fulfill the request...");
-        let mechanism = report.statuses[0].status_mechanism;
-        let oracle_manager_service = oracle_manager_service::OracleManagerService::new();
-        // if oracle_manager_service.check_reports(&report.request_id, mechanism).await? {
-        //     oracle_manager_service.send_reports_to_contract(&report.request_id).await?;
-        // }
-
-        let mechanism = report.statuses[0].status_mechanism;
-        let num_reports = report_service.get_num_reports_by_request_id(&report.request_id, mechanism).await?;
-        println!("Number of reports: {}", num_reports);
-
-        // check the number of agreements
-        let request_service = OracleRequestService::new(&database);
-        // let request = request_service.find_one(&report.request_id, mechanism).await?;
-        match request_service.find_one(&report.request_id, mechanism).await? {
-            Some(r) => {
-                println!("Number of agreements: {}", r.num_agreements);
-
-                if r.num_agreements <= num_reports {
-                    println!("All agreements are collected. Fulfilling the request...");
-                    let reports = report_service.get_reports_by_request_id(&report.request_id, mechanism).await?;
-                    // println!("Reports: {:?}", reports);
-
-                    // send report to the contract
-                    oracle_manager_service.send_last_status_to_contract(&report.request_id, &reports).await?;
-                }
-            },
-            None => {
-                return Err(Status::invalid_argument("Request not found"));
-            }
+    async fn list_request_lifecycles(
+        &self,
+        request: Request<oracle_node::RequestLifecycleFilter>,
+    ) -> Result<Response<oracle_node::RequestLifecycleList>, Status> {
+        let database = db::get_db(&self.manager.config).await.map_err(|e| Status::unavailable(e.to_string()))?;
+
+        let filter = request.into_inner();
+        let lifecycle_service = RequestLifecycleService::new(&database);
+        let lifecycles = match filter.state.as_str() {
+            "" => lifecycle_service.find_stuck(filter.idle_seconds as i64 * 1000).await?,
+            state => lifecycle_service.find_by_state(state.parse::<RequestState>()?).await?,
         };
 
-        let reply = status_exchange::RequestFulfillmentResult {
-            result: true
+        let reply = oracle_node::RequestLifecycleList {
+            lifecycles: lifecycles.into_iter().map(oracle_node::RequestLifecycleReply::from).collect(),
         };
 
         Ok(Response::new(reply))
@@ -96,13 +133,31 @@ impl StatusExchangeService for MyStatusExchangeServer {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::load_oracle_config();
+    // a bad config stops the node here rather than in the middle of a request
+    let manager = Arc::new(OracleManagerService::new(&NodeConfig::load()?).await?);
+    let config = manager.config.clone();
     let addr = format!("0.0.0.0:{}", config.get_server_port()).parse()?;
-    let server = MyStatusExchangeServer::default();
+    let server = MyStatusExchangeServer { manager: manager.clone() };
+    let node_server = MyOracleNodeServer { manager: manager.clone() };
+
+    // oversized reports are rejected by the transport before they are decoded
+    let max_message_size = manager.settings.admission.max_report_bytes;
+
+    // one report per oracle and one fulfillment claim per request, whatever the number of concurrent deliveries
+    let database = db::get_db(&config).await?;
+    RequestReportService::new(&database, &config).ensure_indexes().await?;
+    FulfillmentClaimService::new(&database).ensure_indexes().await?;
+    IssuerKeyService::new(&database).ensure_indexes().await?;
+
+    // resolve aggregators and senders from the local registry instead of the chain
+    if let Err(e) = manager.sync_oracle_registry().await {
+        println!("Error synchronizing the oracle registry: {:?}", e);
+    }
 
     println!("zkOracle Server {} listening on {}", config.get_name(), addr);
     Server::builder()
-        .add_service(StatusExchangeServiceServer::new(server))
+        .add_service(StatusExchangeServiceServer::new(server).max_decoding_message_size(max_message_size))
+        .add_service(OracleNodeServiceServer::new(node_server))
         .serve(addr)
         .await?;
 
@@ -0,0 +1,135 @@
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::{errors::{OracleError, OracleResult}, models::issuer_key::IssuerPublicKey, settings::{DidSettings, DidWebScheme}};
+
+const DID_WEB_PREFIX: &str = "did:web:";
+/// Type of the issuer's status service, see `data_apis::did`.
+const STATUS_SERVICE_TYPE: &str = "ZkcdidStatusService";
+const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
+/// What a request url that is a DID resolves to.
+#[derive(Debug, Clone)]
+pub struct ResolvedIssuer {
+    pub did: String,
+    /// The base url of `/statuses/{mechanism}/{type}`.
+    pub status_url: String,
+    pub public_key: IssuerPublicKey,
+}
+
+pub fn is_did(url: &str) -> bool {
+    url.starts_with("did:")
+}
+
+/// Where the document of a `did:web` is served: `/.well-known/did.json` without a path, `<path>/did.json` with one.
+pub fn get_did_document_url(did: &str, scheme: DidWebScheme) -> OracleResult<String> {
+    let id = did.strip_prefix(DID_WEB_PREFIX)
+        .ok_or(OracleError::CommonError(format!("Unsupported DID method in {}, only did:web is resolved", did)))?;
+
+    let mut parts = id.split(':');
+    let host = parts.next()
+        .filter(|host| !host.is_empty())
+        .ok_or(OracleError::CommonError(format!("{} has no host", did)))?
+        .replace("%3A", ":")
+        .replace("%3a", ":");
+    let path: Vec<&str> = parts.collect();
+
+    match path.is_empty() {
+        true => Ok(format!("{}://{}/.well-known/did.json", scheme.as_str(), host)),
+        false => Ok(format!("{}://{}/{}/did.json", scheme.as_str(), host, path.join("/"))),
+    }
+}
+
+/// Resolves issuer DIDs to their status endpoint and status signing key.
+#[derive(Debug)]
+pub struct DidResolverService {
+    web_scheme: DidWebScheme,
+}
+
+impl DidResolverService {
+    pub fn new(settings: &DidSettings) -> Self {
+        Self {
+            web_scheme: settings.web_scheme,
+        }
+    }
+
+    pub async fn resolve(&self, did: &str) -> OracleResult<ResolvedIssuer> {
+        let url = get_did_document_url(did, self.web_scheme)?;
+        let response = Client::new().get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(OracleError::CommonError(format!("Failed to get the DID document of {} from {}", did, url)));
+        }
+
+        let document: Value = response.json().await?;
+        Self::read_document(did, &document)
+    }
+
+    fn read_document(did: &str, document: &Value) -> OracleResult<ResolvedIssuer> {
+        // a document served for another DID must not be trusted for this one
+        if document["id"].as_str() != Some(did) {
+            return Err(OracleError::CommonError(format!("The DID document of {} is for {}", did, document["id"])));
+        }
+
+        let status_url = document["service"].as_array()
+            .into_iter()
+            .flatten()
+            .find(|service| service["type"].as_str() == Some(STATUS_SERVICE_TYPE))
+            .and_then(|service| service["serviceEndpoint"].as_str())
+            .ok_or(OracleError::CommonError(format!("{} has no {} service", did, STATUS_SERVICE_TYPE)))?;
+
+        // the status key is the Ed25519 verification method the DID asserts with
+        let assertion_methods: Vec<&str> = document["assertionMethod"].as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|method| method.as_str())
+            .collect();
+        let (key_id, jwk) = document["verificationMethod"].as_array()
+            .into_iter()
+            .flatten()
+            .filter(|method| method["id"].as_str().is_some_and(|id| assertion_methods.contains(&id)))
+            .map(|method| (method["id"].as_str().unwrap_or_default(), &method["publicKeyJwk"]))
+            .find(|(_, jwk)| jwk["kty"].as_str() == Some("OKP") && jwk["crv"].as_str() == Some(SIGNATURE_ALGORITHM))
+            .ok_or(OracleError::CommonError(format!("{} has no {} assertion key", did, SIGNATURE_ALGORITHM)))?;
+        let public_key = jwk["x"].as_str()
+            .ok_or(OracleError::CommonError(format!("The key {} of {} has no x", key_id, did)))?;
+
+        Ok(ResolvedIssuer {
+            did: did.to_string(),
+            status_url: status_url.trim_end_matches('/').to_string(),
+            public_key: IssuerPublicKey {
+                algorithm: SIGNATURE_ALGORITHM.to_string(),
+                key_id: key_id.rsplit('#').next().unwrap_or(key_id).to_string(),
+                public_key: public_key.to_string(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dids_without_a_path_resolve_to_the_well_known_document() {
+        assert_eq!(get_did_document_url("did:web:issuer.example", DidWebScheme::Https).unwrap(), "https://issuer.example/.well-known/did.json");
+    }
+
+    #[test]
+    fn did_paths_and_ports_are_kept() {
+        assert_eq!(
+            get_did_document_url("did:web:issuer.example%3A8443:issuers:a", DidWebScheme::Https).unwrap(),
+            "https://issuer.example:8443/issuers/a/did.json",
+        );
+    }
+
+    #[test]
+    fn local_issuers_resolve_over_http() {
+        assert_eq!(get_did_document_url("did:web:localhost%3A3000", DidWebScheme::Http).unwrap(), "http://localhost:3000/.well-known/did.json");
+    }
+
+    #[test]
+    fn other_methods_and_empty_hosts_are_rejected() {
+        assert!(get_did_document_url("did:key:z6Mk", DidWebScheme::Https).is_err());
+        assert!(get_did_document_url("did:web:", DidWebScheme::Https).is_err());
+    }
+}
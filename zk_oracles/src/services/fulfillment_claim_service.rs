@@ -0,0 +1,146 @@
+use bson::{doc, DateTime, Document};
+use mongodb::{options::IndexOptions, Collection, Database, IndexModel};
+
+use crate::{errors::OracleResult, models::fulfillment_claim::{ClaimStatus, FulfillmentClaim}, utils::mongo::is_duplicate_key_error};
+
+const FULFILLMENT_CLAIMS_COLLECTION_NAME: &str = "fulfillment_claims";
+
+pub struct FulfillmentClaimService {
+    pub collection: Collection<FulfillmentClaim>,
+}
+
+impl FulfillmentClaimService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(FULFILLMENT_CLAIMS_COLLECTION_NAME),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> OracleResult<()> {
+        let index = IndexModel::builder()
+            .keys(doc! { "request_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        self.collection.create_index(index).await?;
+        Ok(())
+    }
+
+    /// A claim still held after `timeout` seconds belongs to a task that never finished.
+    fn get_claimable_filter(&self, timeout: u64) -> OracleResult<Document> {
+        let claimed_before = DateTime::from_millis(DateTime::now().timestamp_millis() - timeout as i64 * 1000);
+
+        Ok(doc! {
+            "$or": [
+                { "status": bson::to_bson(&ClaimStatus::Released)? },
+                { "status": bson::to_bson(&ClaimStatus::Claimed)?, "updated_at": { "$lte": claimed_before } },
+            ]
+        })
+    }
+
+    /// Claims the fulfillment of the request. Returns `false` when another task holds it,
+    /// or has already sent a transaction for it.
+    pub async fn claim(&self, request_id: &str, aggregator_id: u8, timeout: u64) -> OracleResult<bool> {
+        let claim = FulfillmentClaim::new(request_id.to_string(), aggregator_id);
+
+        match self.collection.insert_one(&claim).await {
+            Ok(_) => return Ok(true),
+            Err(e) if is_duplicate_key_error(&e) => {},
+            Err(e) => return Err(e.into()),
+        }
+
+        // only a released or expired claim can be taken over
+        let mut filter = self.get_claimable_filter(timeout)?;
+        filter.insert("request_id", request_id);
+
+        let update_doc = doc! {
+            "$set": {
+                "aggregator_id": aggregator_id as i32,
+                "status": bson::to_bson(&ClaimStatus::Claimed)?,
+                "claimed_at": DateTime::now(),
+                "updated_at": DateTime::now(),
+            }
+        };
+
+        let result = self.collection.update_one(filter, update_doc).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Whether `claim` could take the request, without taking it.
+    pub async fn is_claimable(&self, request_id: &str, timeout: u64) -> OracleResult<bool> {
+        if self.find_one(request_id).await?.is_none() {
+            return Ok(true);
+        }
+
+        let mut filter = self.get_claimable_filter(timeout)?;
+        filter.insert("request_id", request_id);
+        Ok(self.collection.find_one(filter).await?.is_some())
+    }
+
+    pub async fn find_one(&self, request_id: &str) -> OracleResult<Option<FulfillmentClaim>> {
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let claim = self.collection.find_one(query).await?;
+        Ok(claim)
+    }
+
+    /// Records the mined fulfillment transaction, whether it succeeded (`Submitted`) or not (`Reverted`).
+    pub async fn mark_mined(&self, request_id: &str, status: ClaimStatus, tx_hash: &str) -> OracleResult<()> {
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&status)?,
+                "tx_hash": tx_hash,
+                "updated_at": DateTime::now(),
+            }
+        };
+
+        self.collection.update_one(doc! { "request_id": request_id }, update_doc).await?;
+        Ok(())
+    }
+
+    /// Records the transaction that stayed pending after the last replacement. The claim is kept, and
+    /// `updated_at` restarts its timeout, so that no other transaction is sent while this one can still be mined.
+    pub async fn mark_stuck(&self, request_id: &str, tx_hash: &str, error: &str) -> OracleResult<()> {
+        let filter = doc! {
+            "request_id": request_id,
+            "status": bson::to_bson(&ClaimStatus::Claimed)?,
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "tx_hash": tx_hash,
+                "last_error": error,
+                "updated_at": DateTime::now(),
+            }
+        };
+
+        self.collection.update_one(filter, update_doc).await?;
+        Ok(())
+    }
+
+    /// Gives the claim up after a failure that left nothing on-chain, so that the sweeper can retry.
+    pub async fn release(&self, request_id: &str, error: &str) -> OracleResult<()> {
+        let filter = doc! {
+            "request_id": request_id,
+            "status": bson::to_bson(&ClaimStatus::Claimed)?,
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&ClaimStatus::Released)?,
+                "last_error": error,
+                "updated_at": DateTime::now(),
+            }
+        };
+
+        self.collection.update_one(filter, update_doc).await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        self.collection.delete_many(doc! { "request_id": request_id }).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use bson::doc;
+use mongodb::{Collection, Database};
+
+use crate::{errors::OracleResult, models::fulfillment_record::FulfillmentRecord};
+
+const FULFILLMENT_RECORDS_COLLECTION_NAME: &str = "fulfillment_records";
+
+pub struct FulfillmentRecordService {
+    pub collection: Collection<FulfillmentRecord>,
+}
+
+impl FulfillmentRecordService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(FULFILLMENT_RECORDS_COLLECTION_NAME),
+        }
+    }
+
+    pub async fn insert_one(&self, record: &FulfillmentRecord) -> OracleResult<()> {
+        self.collection.insert_one(record).await?;
+        Ok(())
+    }
+
+    pub async fn find_one(&self, request_id: &str) -> OracleResult<Option<FulfillmentRecord>> {
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let record = self.collection.find_one(query).await?;
+        Ok(record)
+    }
+}
@@ -0,0 +1,57 @@
+use bson::doc;
+use mongodb::{options::IndexOptions, Collection, Database, IndexModel};
+
+use crate::{errors::{OracleError, OracleResult}, models::issuer_key::IssuerKey, utils::mongo::is_duplicate_key_error};
+
+const ISSUER_KEYS_COLLECTION_NAME: &str = "issuer_keys";
+
+pub struct IssuerKeyService {
+    pub collection: Collection<IssuerKey>,
+}
+
+impl IssuerKeyService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(ISSUER_KEYS_COLLECTION_NAME),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> OracleResult<()> {
+        let index = IndexModel::builder()
+            .keys(doc! { "url": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        self.collection.create_index(index).await?;
+        Ok(())
+    }
+
+    pub async fn find_one(&self, url: &str) -> OracleResult<Option<IssuerKey>> {
+        let query = doc! {
+            "url": doc! { "$eq": url }
+        };
+
+        let key = self.collection.find_one(query).await?;
+        Ok(key)
+    }
+
+    /// Stores the key unless one is already pinned for the url, and returns the pinned one.
+    pub async fn pin(&self, key: &IssuerKey) -> OracleResult<IssuerKey> {
+        let filter = doc! { "url": &key.url };
+        let update_doc = doc! {
+            "$setOnInsert": bson::to_document(key)?
+        };
+
+        // a concurrent pin of the same url fails on the unique index; the key it stored wins
+        match self.collection.update_one(filter, update_doc).upsert(true).await {
+            Ok(_) => {},
+            Err(e) if is_duplicate_key_error(&e) => {},
+            Err(e) => return Err(e.into()),
+        }
+
+        match self.find_one(&key.url).await? {
+            Some(key) => Ok(key),
+            None => Err(OracleError::CommonError(format!("The key of {} was not pinned", key.url))),
+        }
+    }
+}
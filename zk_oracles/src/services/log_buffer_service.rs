@@ -0,0 +1,112 @@
+use alloy::{primitives::Address, rpc::types::Log};
+use bson::{doc, Document};
+use futures_util::TryStreamExt;
+use mongodb::{options::IndexOptions, Collection, Database, IndexModel};
+
+use crate::{errors::OracleResult, models::buffered_log::BufferedLog, utils::confirmation_buffer::{ConfirmationBuffer, PROCESSED_LOG_RETENTION}};
+
+const BUFFERED_LOGS_COLLECTION_NAME: &str = "buffered_logs";
+const LISTENER_CHECKPOINTS_COLLECTION_NAME: &str = "listener_checkpoints";
+const LAST_CONFIRMED_BLOCK_FIELD: &str = "last_confirmed_block";
+
+/// Keeps the listener's confirmation buffer and the last block it confirmed across restarts.
+pub struct LogBufferService {
+    pub collection: Collection<BufferedLog>,
+    checkpoints: Collection<Document>,
+}
+
+impl LogBufferService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(BUFFERED_LOGS_COLLECTION_NAME),
+            checkpoints: database.collection(LISTENER_CHECKPOINTS_COLLECTION_NAME),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> OracleResult<()> {
+        let index = IndexModel::builder()
+            .keys(doc! { "block_number": 1, "log_index": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        self.collection.create_index(index).await?;
+        Ok(())
+    }
+
+    /// The buffer as it was left by the previous run.
+    pub async fn load(&self, confirmations: u64) -> OracleResult<ConfirmationBuffer> {
+        let cursor = self.collection.find(doc! {}).sort(doc! { "block_number": 1, "log_index": 1 }).await?;
+        let buffered_logs = cursor.try_collect::<Vec<BufferedLog>>().await?;
+
+        let mut buffer = ConfirmationBuffer::new(confirmations);
+        for buffered_log in buffered_logs {
+            if buffered_log.processed {
+                buffer.restore_processed(buffered_log.log, buffered_log.request_id);
+            } else {
+                buffer.push(buffered_log.log);
+            }
+        }
+
+        Ok(buffer)
+    }
+
+    async fn save(&self, buffered_log: &BufferedLog) -> OracleResult<()> {
+        let filter = doc! {
+            "block_number": buffered_log.block_number as i64,
+            "log_index": buffered_log.log_index as i64,
+        };
+
+        self.collection.replace_one(filter, buffered_log).upsert(true).await?;
+        Ok(())
+    }
+
+    pub async fn save_pending(&self, log: &Log) -> OracleResult<()> {
+        match BufferedLog::new(log, false, None) {
+            Some(buffered_log) => self.save(&buffered_log).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Stores the log as processed and forgets the processed logs too old to be reorged out.
+    pub async fn mark_processed(&self, log: &Log, request_id: Option<String>, head: u64) -> OracleResult<()> {
+        if let Some(buffered_log) = BufferedLog::new(log, true, request_id) {
+            self.save(&buffered_log).await?;
+        }
+
+        let query = doc! {
+            "processed": true,
+            "block_number": { "$lt": head.saturating_sub(PROCESSED_LOG_RETENTION) as i64 },
+        };
+
+        self.collection.delete_many(query).await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, log: &Log) -> OracleResult<()> {
+        let (Some(block_number), Some(log_index)) = (log.block_number, log.log_index) else {
+            return Ok(());
+        };
+
+        let query = doc! {
+            "block_number": block_number as i64,
+            "log_index": log_index as i64,
+        };
+
+        self.collection.delete_one(query).await?;
+        Ok(())
+    }
+
+    pub async fn get_last_confirmed_block(&self, contract_address: &Address) -> OracleResult<Option<u64>> {
+        let checkpoint = self.checkpoints.find_one(doc! { "_id": contract_address.to_string() }).await?;
+        Ok(checkpoint.and_then(|checkpoint| checkpoint.get_i64(LAST_CONFIRMED_BLOCK_FIELD).ok()).map(|block| block as u64))
+    }
+
+    pub async fn set_last_confirmed_block(&self, contract_address: &Address, block_number: u64) -> OracleResult<()> {
+        self.checkpoints
+            .update_one(doc! { "_id": contract_address.to_string() }, doc! { "$max": { LAST_CONFIRMED_BLOCK_FIELD: block_number as i64 } })
+            .upsert(true)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -2,4 +2,17 @@ pub mod oracle_manager_service;
 pub mod status_service;
 pub mod status_exchange_service;
 pub mod request_report_service;
-pub mod oracle_request_service;
\ No newline at end of file
+pub mod oracle_request_service;
+pub mod transaction_service;
+pub mod fulfillment_record_service;
+pub mod request_failure_service;
+pub mod prover_service;
+pub mod report_outbox_service;
+pub mod oracle_registry_service;
+pub mod request_lifecycle_service;
+pub mod request_queue_service;
+pub mod fulfillment_claim_service;
+pub mod signer_service;
+pub mod issuer_key_service;
+pub mod did_resolver_service;
+pub mod log_buffer_service;
@@ -1,27 +1,37 @@
-use alloy::{contract::{ContractInstance, Interface}, dyn_abi::{DynSolError, DynSolType, DynSolValue}, hex::{self, encode}, network::EthereumWallet, primitives::{address, Address, FixedBytes, Uint, U256, U64, Bytes}, providers::{Provider, ProviderBuilder, WsConnect}, rpc::types::{request, BlockNumberOrTag, Filter}, signers::local::PrivateKeySigner};
+use alloy::{contract::{ContractInstance, Interface}, dyn_abi::{DynSolError, DynSolType, DynSolValue}, hex::{self, encode}, network::EthereumWallet, primitives::{address, Address, FixedBytes, TxHash, Uint, U256, U64, Bytes}, providers::{Provider, ProviderBuilder, WsConnect}, rpc::types::{request, BlockNumberOrTag, Filter, Log, TransactionRequest}};
 // use alloy::primitives::{fixed_bytes, b256, Bytes};
-use futures_util::StreamExt;
+use std::{collections::HashMap, sync::Arc};
+use futures_util::{future::join_all, StreamExt};
+use mongodb::ClientSession;
 use alloy_sol_types::{SolEvent};
-use zkcdid_lib_rs::{config::Config, contracts::ZKOracleManager, models::{oracle::Oracle, oracle_request::OracleRequest, request_report::RequestReport, status_state::StatusMechanism}, utils::db};
+use zkcdid_lib_rs::{config::Config, contracts::ZKOracleManager, models::{oracle::Oracle, oracle_request::OracleRequest, request_report::RequestReport, status_state::{StatusMechanism, StatusState}}, utils::db};
 
-use crate::{errors::{OracleError, OracleResult}, services::{oracle_request_service::OracleRequestService, request_report_service::RequestReportService, status_exchange_service::StatusExchangeService}, utils::solidity::{get_solidity_artifact, get_solidity_contract_address, Artifact}};
+use crate::{config::NodeConfig, errors::{OracleError, OracleResult}, models::{fulfillment_claim::ClaimStatus, fulfillment_record::FulfillmentRecord, issuer_key::{IssuerKey, IssuerPublicKey}, registered_oracle::RegisteredOracle, report_delivery::{DeliveryPayload, ReportDelivery}, request_failure::{get_agreed_failure, FailureCode, RequestFailure}, request_lifecycle::{RequestLifecycle, RequestState}, request_task::RequestTask, response_type::ResponseType}, services::{did_resolver_service::{is_did, DidResolverService}, fulfillment_claim_service::FulfillmentClaimService, fulfillment_record_service::FulfillmentRecordService, issuer_key_service::IssuerKeyService, log_buffer_service::LogBufferService, oracle_registry_service::OracleRegistryService, oracle_request_service::OracleRequestService, prover_service::ProverService, report_outbox_service::ReportOutboxService, request_failure_service::RequestFailureService, request_lifecycle_service::RequestLifecycleService, request_queue_service::RequestQueueService, request_report_service::RequestReportService, signer_service::{is_signed_by, SignerService}, status_exchange_service::StatusExchangeService, transaction_service::TransactionService}, settings::Settings, utils::{confirmation_buffer::{ConfirmationBuffer, RemovedLog}, response, status_signature::is_signed_by_issuer, solidity::{get_solidity_artifact, get_solidity_contract_address, Artifact}, mongo}};
 
-use super::status_service::StatusService;
+use super::status_service::{get_status_list, StatusService};
 
 pub struct OracleManagerService {
     pub config: Config,
+    pub settings: Settings,
     oracle: Oracle,
     pub contract_address: Address,
     contract_artifact: Artifact,
+    signer: SignerService,
     wallet: EthereumWallet,
+    sender: Address,
     // collection: Collection<Oracle>,
 }
 
 impl OracleManagerService {
-    pub fn new() -> Self {
-        let config = Config::load_oracle_config();
+    /// Fails when the configuration cannot run a node, instead of panicking later in a request.
+    pub async fn new(node_config: &NodeConfig) -> OracleResult<Self> {
+        node_config.validate()?;
+
+        let config = node_config.config.clone();
+
         let contract_name = config.get_oracle_manager_contract_name();
-        let signer: PrivateKeySigner = config.get_private_key().parse().unwrap();
+        let signer = SignerService::load(&config, &node_config.settings.signer).await?;
+        let sender = signer.address();
         let this_oracle = Oracle {
             id: config.get_id(),
             oracle_address: signer.address().to_string(),
@@ -31,30 +41,38 @@ impl OracleManagerService {
 
         // let collection = database.collection(&config.neighbors_collection_name);
 
-        Self {
+        Ok(Self {
             config: config.clone(),
+            settings: node_config.settings.clone(),
             oracle: this_oracle,
-            contract_address: get_solidity_contract_address(&config, contract_name).unwrap(),
-            contract_artifact: get_solidity_artifact(contract_name).unwrap(),
-            wallet: EthereumWallet::from(signer),
+            contract_address: get_solidity_contract_address(&config, contract_name)?,
+            contract_artifact: get_solidity_artifact(&config, contract_name)?,
+            wallet: signer.get_wallet(),
+            signer,
+            sender,
             // collection,
-        }
+        })
     }
 
     pub async fn get_num_oracles(&self) -> OracleResult<u8> {
         let provider = ProviderBuilder::new()
             .with_recommended_fillers()
             .wallet(self.wallet.clone())
-            .on_http(self.config.get_solidity_http_rpc_url().parse().unwrap());
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
         let contract = ContractInstance::new(self.contract_address, provider, Interface::new(self.contract_artifact.abi.clone()));
-        Ok(U256::to(&contract.function("getNumOracles", &[])?.call().await.unwrap()[0].as_uint().unwrap().0))
+        let result = contract.function("getNumOracles", &[])?.call().await?;
+
+        match result.first().and_then(|value| value.as_uint()) {
+            Some((num_oracles, _)) => num_oracles.try_into().map_err(|_| OracleError::CommonError(format!("Too many oracles: {}", num_oracles))),
+            None => Err(OracleError::CommonError("Cannot decode the number of oracles".to_string())),
+        }
     }
 
     pub async fn get_oracle(&self, oracle_id: u8) -> OracleResult<Oracle> {
         let provider = ProviderBuilder::new()
             .with_recommended_fillers()
             .wallet(self.wallet.clone())
-            .on_http(self.config.get_solidity_http_rpc_url().parse().unwrap());
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
         let contract = ContractInstance::new(self.contract_address, provider, Interface::new(self.contract_artifact.abi.clone()));
         let oracle_id = DynSolValue::Uint(Uint::from(oracle_id), 8);
         let result = contract.function("getOracle", &[oracle_id])?.call().await?;
@@ -67,7 +85,7 @@ impl OracleManagerService {
         let provider = ProviderBuilder::new()
             .with_recommended_fillers()
             .wallet(self.wallet.clone())
-            .on_http(self.config.get_solidity_http_rpc_url().parse().unwrap());
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
         let contract = ContractInstance::new(self.contract_address, provider, Interface::new(self.contract_artifact.abi.clone()));
         let oracle_id = DynSolValue::Uint(Uint::from(oracle.id), 8);
         // let oracle_address = DynSolValue::Address(Address::from(oracle.oracle_address.clone()));
@@ -84,7 +102,7 @@ impl OracleManagerService {
     }
 
     pub async fn is_this_oracle_registered(&self) -> OracleResult<bool> {
-        match self.get_oracle(self.oracle.id).await {
+        match self.get_registered_oracle(self.oracle.id).await {
             Ok(oracle) => {
                 println!("{:?} == {:?}", oracle, self.oracle);
                 Ok(oracle == self.oracle)
@@ -97,34 +115,323 @@ impl OracleManagerService {
         let provider = ProviderBuilder::new()
             .with_recommended_fillers()
             .wallet(self.wallet.clone())
-            .on_http(self.config.get_solidity_http_rpc_url().parse().unwrap());
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
         let contract = ContractInstance::new(self.contract_address, provider, Interface::new(self.contract_artifact.abi.clone()));
         let result = contract.function("getOracles", &[])?.call().await?;
-        let sol_vals = result[0].as_array().unwrap();
+        let sol_vals = match result.first().and_then(|value| value.as_array()) {
+            Some(sol_vals) => sol_vals,
+            None => return Err(OracleError::CommonError("Cannot decode the oracles".to_string())),
+        };
 
         Ok(sol_vals.iter().map(|val| {
             Oracle::from(val.clone())
         }).collect())
     }
 
+    /// Replaces the local oracle registry with the oracles currently registered on-chain.
+    pub async fn sync_oracle_registry(&self) -> OracleResult<()> {
+        let oracles = self.get_all_onchain_oracles().await?;
+        let registered_oracles: Vec<RegisteredOracle> = oracles.iter().map(RegisteredOracle::from).collect();
+
+        let database = db::get_db(&self.config).await?;
+        let registry_service = OracleRegistryService::new(&database);
+        registry_service.upsert_many(&registered_oracles).await?;
+
+        println!("Synchronized {} oracles from the chain", registered_oracles.len());
+        Ok(())
+    }
+
+    /// Looks the oracle up in the local registry and only asks the chain when it is not mirrored yet.
+    pub async fn get_registered_oracle(&self, oracle_id: u8) -> OracleResult<Oracle> {
+        let database = db::get_db(&self.config).await?;
+        let registry_service = OracleRegistryService::new(&database);
+
+        if let Some(oracle) = registry_service.find_by_id(oracle_id).await? {
+            return Ok(Oracle::from(&oracle));
+        }
+
+        let oracle = self.get_oracle(oracle_id).await?;
+        registry_service.upsert(&RegisteredOracle::from(&oracle)).await?;
+        Ok(oracle)
+    }
+
+    pub async fn get_registered_oracle_by_address(&self, address: &Address) -> OracleResult<Option<RegisteredOracle>> {
+        let database = db::get_db(&self.config).await?;
+        let registry_service = OracleRegistryService::new(&database);
+
+        if let Some(oracle) = registry_service.find_by_address(address).await? {
+            return Ok(Some(oracle));
+        }
+
+        // the oracle may have been added while this node was not listening
+        self.sync_oracle_registry().await?;
+        registry_service.find_by_address(address).await
+    }
+
+    /// Looks the oracle up in the local registry, refreshing it from the chain when the oracle is missing.
+    async fn find_registered_oracle(&self, oracle_id: u8) -> OracleResult<Option<RegisteredOracle>> {
+        let database = db::get_db(&self.config).await?;
+        let registry_service = OracleRegistryService::new(&database);
+
+        if let Some(oracle) = registry_service.find_by_id(oracle_id).await? {
+            return Ok(Some(oracle));
+        }
+
+        self.sync_oracle_registry().await?;
+        registry_service.find_by_id(oracle_id).await
+    }
+
+    /// Checks that a report or failure was signed by the registered oracle it claims to come from.
+    pub async fn authenticate_sender(&self, oracle_id: u8, message: &[u8], signature: Option<&str>) -> OracleResult<()> {
+        let oracle = match self.find_registered_oracle(oracle_id).await? {
+            Some(oracle) => oracle,
+            None => return Err(OracleError::UnknownOracleError(oracle_id)),
+        };
+        let oracle_address = oracle.oracle_address.parse::<Address>()?;
+
+        match signature {
+            Some(signature) if is_signed_by(message, signature, &oracle_address) => Ok(()),
+            _ => Err(OracleError::InvalidSignatureError(oracle_id)),
+        }
+    }
+
+    /// Admits a report or failure sent by another oracle: the sender must be registered, the request
+    /// must exist on-chain and this oracle must be one of its aggregators.
+    pub async fn admit_sender(&self, oracle_id: u8, request_id: &str) -> OracleResult<()> {
+        if self.find_registered_oracle(oracle_id).await?.is_none() {
+            return Err(OracleError::UnknownOracleError(oracle_id));
+        }
+
+        let onchain_request_id = Self::parse_request_id(request_id)
+            .map_err(|_| OracleError::InvalidReportError(format!("Malformed request id {}", request_id)))?;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(self.wallet.clone())
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
+        let contract = ZKOracleManager::new(self.contract_address, provider);
+
+        let onchain_request = match contract.getRequestById(onchain_request_id).call().await {
+            Ok(result) => result._0,
+            // getRequestById reverts for unknown requests
+            Err(alloy::contract::Error::TransportError(e)) if e.as_error_resp().is_some() => {
+                return Err(OracleError::RequestNotFoundError(request_id.to_string()));
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        if !onchain_request.aggregatorIds.iter().any(|id| *id == self.oracle.id) {
+            return Err(OracleError::NotAggregatorError(request_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the bounds of a report before anything of it is stored.
+    pub async fn admit_report(&self, report: &RequestReport, encoded_len: usize) -> OracleResult<()> {
+        let admission = &self.settings.admission;
+        if encoded_len > admission.max_report_bytes {
+            return Err(OracleError::ReportTooLargeError(encoded_len, admission.max_report_bytes));
+        }
+
+        let mechanism = match report.statuses.first() {
+            Some(status) => status.status_mechanism,
+            None => return Err(OracleError::InvalidReportError("Report must have at least one status".to_string())),
+        };
+
+        if report.statuses.len() > admission.max_statuses {
+            return Err(OracleError::InvalidReportError(format!("Report has {} statuses, at most {} are allowed", report.statuses.len(), admission.max_statuses)));
+        }
+
+        if report.statuses.iter().any(|status| status.status_mechanism != mechanism) {
+            return Err(OracleError::InvalidReportError("Statuses must share one status mechanism".to_string()));
+        }
+
+        self.admit_sender(report.oracle_id, &report.request_id).await
+    }
+
     pub fn is_this_oracle_aggregator(&self, request: &OracleRequest) -> bool {
         request.aggregator_ids.iter().any(|id| *id == self.oracle.id)
     }
 
+    /// Stores a received request and queues it, before any work is done on it.
+    pub async fn queue_request(&self, request: &OracleRequest) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+
+        // the lifecycle comes first: reports for a stored request can arrive at any time and need it for the quorum
+        let request_id = request.request_id.to_string();
+        let onchain_request_id = Self::parse_request_id(&request_id).map(|id| id.to_string()).unwrap_or(request_id.clone());
+        RequestLifecycleService::new(&database).start(&RequestLifecycle::new(request_id.clone(), onchain_request_id, request.status_mechanism)).await?;
+
+        OracleRequestService::new(&database, &self.config).insert_if_absent(request).await?;
+
+        RequestQueueService::new(&database, &self.settings.queue).enqueue(&request_id, request.status_mechanism).await
+    }
+
+    /// Handles the request if its task is not leased by another worker.
+    pub async fn process_request(&self, request_id: &str) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let queue_service = RequestQueueService::new(&database, &self.settings.queue);
+
+        if let Some(task) = queue_service.claim_one(request_id).await? {
+            self.run_task(&queue_service, &task).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Handles every queued request that is due, including the ones left unfinished by a previous run.
+    pub async fn process_queued_requests(&self) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let queue_service = RequestQueueService::new(&database, &self.settings.queue);
+
+        while let Some(task) = queue_service.claim_next().await? {
+            println!("Resuming request {:?} (attempt {})", task.request_id, task.attempts);
+            self.run_task(&queue_service, &task).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_task(&self, queue_service: &RequestQueueService, task: &RequestTask) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let request = match OracleRequestService::new(&database, &self.config).find_one(&task.request_id, task.status_mechanism).await? {
+            Some(request) => request,
+            None => {
+                // rolled back by a reorg
+                queue_service.delete_by_request_id(&task.request_id).await?;
+                return Ok(());
+            }
+        };
+
+        match self.handle_new_request(&request).await {
+            Ok(_) => queue_service.mark_done(&task.request_id).await?,
+            Err(e) => {
+                println!("Error handling request {:?}: {:?}", task.request_id, e);
+                if !queue_service.mark_failed(task, &e.to_string()).await? {
+                    println!("Giving up request {:?} after {} attempts", task.request_id, task.attempts);
+                    RequestLifecycleService::new(&database).advance(&task.request_id, RequestState::Failed, Some(e.to_string())).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reports a stored request. Every step can be run again, so the request can be handled anew
+    /// after a crash, starting from the last step recorded in its lifecycle.
     pub async fn handle_new_request(&self, request: &OracleRequest) -> OracleResult<()> {
-        let is_aggregator = self.is_this_oracle_aggregator(&request);
         let database = db::get_db(&self.config).await?;
-        let request_service = OracleRequestService::new(&database);
+        let request_id = request.request_id.to_string();
+        let lifecycle_service = RequestLifecycleService::new(&database);
+        let report_service = RequestReportService::new(&database, &self.config);
 
-        // if is_aggregator {
-        request_service.insert_one(&request).await?;
-        // }
+        let lifecycle = match lifecycle_service.find_one(&request_id).await? {
+            Some(lifecycle) => lifecycle,
+            None => return Err(OracleError::CommonError(format!("Request {} is not queued", request_id))),
+        };
+
+        // the state alone may have moved on with the reports of the other oracles
+        if lifecycle.reported_at.is_some() {
+            println!("Request {:?} is already reported", request_id);
+            return Ok(());
+        }
+
+        // the report stored before a crash is sent again instead of fetching the statuses twice
+        if lifecycle.state.has_reached(RequestState::Fetched) {
+            let stored_report = report_service.get_reports_by_request_id(&request_id, request.status_mechanism).await?
+                .into_iter()
+                .find(|report| report.oracle_id == self.oracle.id);
+
+            if let Some(report) = stored_report {
+                println!("Resending stored report to aggregators...");
+                self.send_report_to_aggregators(&request.aggregator_ids, &report).await?;
+                lifecycle_service.mark_reported(&request_id, None).await?;
+                return Ok(());
+            }
+        }
+
+        let statuses = match self.get_valid_statuses(request).await {
+            Ok(statuses) => statuses,
+            Err(OracleError::IssuerDataError(code, reason)) => {
+                // let the aggregators know, so that a quorum of failures can be reported on-chain
+                println!("Cannot generate report for request {:?}: {}", request.request_id, reason);
+                let failure = RequestFailure::new(request_id.clone(), self.oracle.id, code, reason.clone());
+                self.send_failure_to_aggregators(&request.aggregator_ids, &failure).await?;
+                lifecycle_service.mark_reported(&request_id, Some(format!("Issuer data unavailable: {}", reason))).await?;
+                return Ok(());
+            },
+            Err(e) => return Err(e),
+        };
+
+        let report = RequestReport::new(request_id.clone(), self.oracle.id, statuses);
+        println!("Generated report: {:?}", report);
 
+        // the report is stored before moving on, so that a resumed request does not fetch it again
+        println!("Inserting report to db: {:?}", report);
+        report_service.insert_or_update(&report).await?;
+        lifecycle_service.advance(&request_id, RequestState::Fetched, None).await?;
+
+        println!("Sending report to aggregators...");
+        self.send_report_to_aggregators(&request.aggregator_ids, &report).await?;
+        println!("Sent report to aggregators");
+
+        lifecycle_service.mark_reported(&request_id, None).await?;
+
+        Ok(())
+    }
+
+    /// The key pinned for the issuer, pinned the first time the issuer is seen. `resolved_key` is the key
+    /// of the issuer's DID document; issuers identified by a url are asked for their key.
+    async fn get_issuer_key(&self, status_service: &StatusService, url: &str, resolved_key: Option<IssuerPublicKey>) -> OracleResult<IssuerKey> {
+        let database = db::get_db(&self.config).await?;
+        let issuer_key_service = IssuerKeyService::new(&database);
+
+        if let Some(key) = issuer_key_service.find_one(url).await? {
+            return Ok(key);
+        }
+
+        let key = match resolved_key {
+            Some(key) => key,
+            None => status_service.get_issuer_key_from_api(url).await?,
+        };
+        println!("Pinning key {} of issuer {}", key.key_id, url);
+        issuer_key_service.pin(&IssuerKey::new(url.to_string(), key)).await
+    }
+
+    /// Fetches the issuer history and checks that it is signed by the issuer and extends the request's last status state.
+    pub async fn get_valid_statuses(&self, request: &OracleRequest) -> OracleResult<Vec<StatusState>> {
         let status_service = StatusService::new();
-        let mut statuses = status_service.get_status_from_api(&request).await?;
+        // the key belongs to the issuer, whichever of its status lists is requested
+        let (issuer_url, list_id) = match get_status_list(&request.url) {
+            Ok(status_list) => status_list,
+            Err(e) => return Err(OracleError::IssuerDataError(FailureCode::IssuerUnavailable, e.to_string())),
+        };
+        // an issuer DID is resolved to where its statuses are served and to its key
+        let (status_url, resolved_key) = match is_did(&issuer_url) {
+            true => match DidResolverService::new(&self.settings.did).resolve(&issuer_url).await {
+                Ok(issuer) => (issuer.status_url, Some(issuer.public_key)),
+                Err(e) => return Err(OracleError::IssuerDataError(FailureCode::IssuerUnavailable, e.to_string())),
+            },
+            false => (issuer_url.clone(), None),
+        };
+        let issuer_key = match self.get_issuer_key(&status_service, &issuer_url, resolved_key).await {
+            Ok(issuer_key) => issuer_key,
+            Err(e) => return Err(OracleError::IssuerDataError(FailureCode::IssuerUnavailable, e.to_string())),
+        };
+
+        let mut statuses = match status_service.get_status_from_api(request, &status_url, list_id).await {
+            Ok(statuses) => statuses,
+            Err(e) => return Err(OracleError::IssuerDataError(FailureCode::IssuerUnavailable, e.to_string())),
+        };
 
         if statuses.is_empty() {
-            return Err(OracleError::CommonError("Statuses are empty".into()));
+            return Err(OracleError::IssuerDataError(FailureCode::EmptyHistory, "Statuses are empty".into()));
+        }
+
+        // an unsigned or tampered status cannot come from the issuer
+        if let Some(status) = statuses.iter().find(|status| !is_signed_by_issuer(status, list_id, &issuer_key.key.public_key)) {
+            return Err(OracleError::IssuerDataError(FailureCode::InvalidHistory, format!("Status at time {} is not signed by issuer key {}", status.time, issuer_key.key.key_id)));
         }
 
         // sort statuses by time
@@ -138,11 +445,11 @@ impl OracleManagerService {
         println!("Pre Status status: {:?}", pre_status_status);
         for status in statuses.iter() {
             if status.time < pre_status_time {
-                return Err(OracleError::CommonError("Statuses are not sorted by time".into()));
+                return Err(OracleError::IssuerDataError(FailureCode::InvalidHistory, "Statuses are not sorted by time".into()));
             }
 
             if (status.status & pre_status_status) != pre_status_status {
-                return Err(OracleError::CommonError("Statuses are invalid".to_string()));
+                return Err(OracleError::IssuerDataError(FailureCode::InvalidHistory, "Statuses are invalid".to_string()));
             }
 
             pre_status_time = status.time;
@@ -151,41 +458,82 @@ impl OracleManagerService {
             println!("Pre Status status: {:?}", pre_status_status);
         }
 
-        let report = RequestReport::new(request.request_id.to_string(), self.oracle.id, statuses);
-        println!("Generated report: {:?}", report);
+        Ok(statuses)
+    }
 
-        // if is_aggregator {
-        println!("Inserting report to db: {:?}", report);
-        let report_service = RequestReportService::new(&database);
-        report_service.insert_or_update(&report).await?;
-        // }
+    pub async fn send_failure_to_aggregators(&self, aggregator_ids: &Vec<u8>, failure: &RequestFailure) -> OracleResult<()> {
+        self.send_to_aggregators(&failure.request_id, aggregator_ids, DeliveryPayload::Failure(failure.clone())).await
+    }
 
-        println!("Sending report to aggregators...");
-        self.send_report_to_aggregators(&request.aggregator_ids, &report).await?;
-        println!("Sent report to aggregators");
+    pub async fn send_report_to_aggregators(&self, aggregator_ids: &Vec<u8>, report: &RequestReport) -> OracleResult<()> {
+        self.send_to_aggregators(&report.request_id, aggregator_ids, DeliveryPayload::Report(report.clone())).await
+    }
+
+    /// Queues the payload for every aggregator in the outbox and tries to deliver it right away.
+    /// Deliveries that fail here are retried by `dispatch_pending_deliveries`.
+    async fn send_to_aggregators(&self, request_id: &str, aggregator_ids: &Vec<u8>, payload: DeliveryPayload) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let outbox_service = ReportOutboxService::new(&database, &self.settings.outbox);
+
+        let deliveries = outbox_service.enqueue(request_id, aggregator_ids, &payload).await?;
+        self.dispatch_deliveries(&outbox_service, deliveries).await;
 
         Ok(())
     }
 
-    pub async fn send_report_to_aggregators(&self, aggregator_ids: &Vec<u8>, report: &RequestReport) -> OracleResult<()> {
-        let service = StatusExchangeService::new();
+    async fn deliver(&self, delivery: &ReportDelivery) -> OracleResult<bool> {
+        let service = StatusExchangeService::new(&self.config, &self.settings.outbox, &self.signer);
+        let aggregator = self.get_registered_oracle(delivery.aggregator_id).await?;
+        println!("Sending to Aggregator: {:?}", aggregator);
 
-        // send to all neighbors
-        for aggregator_id in aggregator_ids.iter() {
-            // if *aggregator_id == self.oracle.id {
-            //     continue;
-            // }
+        match &delivery.payload {
+            DeliveryPayload::Report(report) => service.fulfill_request(&aggregator.url, report).await,
+            DeliveryPayload::Failure(failure) => service.report_failure(&aggregator.url, failure).await,
+        }
+    }
+
+    /// Sends the deliveries concurrently and records the outcome of each one in the outbox.
+    async fn dispatch_deliveries(&self, outbox_service: &ReportOutboxService, deliveries: Vec<ReportDelivery>) {
+        let results = join_all(deliveries.iter().map(|delivery| self.deliver(delivery))).await;
+
+        for (delivery, result) in deliveries.iter().zip(results) {
+            let outcome = match result {
+                Ok(true) => match delivery.id.as_ref() {
+                    Some(id) => outbox_service.mark_delivered(id).await,
+                    None => Ok(()),
+                },
+                Ok(false) => outbox_service.mark_failed(delivery, "Aggregator did not acknowledge the delivery").await,
+                Err(e) if e.is_retryable() => {
+                    println!("Error sending request {:?} to aggregator {:?}: {:?}", delivery.request_id, delivery.aggregator_id, e);
+                    outbox_service.mark_failed(delivery, &e.to_string()).await
+                },
+                Err(e) => {
+                    println!("Aggregator {:?} rejected request {:?}: {:?}", delivery.aggregator_id, delivery.request_id, e);
+                    outbox_service.mark_rejected(delivery, &e.to_string()).await
+                },
+            };
 
-            let aggregator = self.get_oracle(*aggregator_id).await?;
-            println!("Sending to Aggregator: {:?}", aggregator);
-            service.fulfill_request(&aggregator.url, report).await?;
+            if let Err(e) = outcome {
+                println!("Error updating delivery of request {:?} to aggregator {:?}: {:?}", delivery.request_id, delivery.aggregator_id, e);
+            }
         }
+    }
+
+    /// Retries every pending delivery whose backoff has elapsed.
+    pub async fn dispatch_pending_deliveries(&self) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let outbox_service = ReportOutboxService::new(&database, &self.settings.outbox);
 
+        let deliveries = outbox_service.get_due_deliveries().await?;
+        if !deliveries.is_empty() {
+            println!("Retrying {} deliveries...", deliveries.len());
+        }
 
+        self.dispatch_deliveries(&outbox_service, deliveries).await;
         Ok(())
     }
 
-    pub async fn listen_for_requests(&self) -> OracleResult<()> {
+    pub async fn listen_for_requests(self: &Arc<Self>) -> OracleResult<()> {
         let ws = WsConnect::new(self.config.get_solidity_ws_rpc_url());
         let provider = ProviderBuilder::new()
             .with_recommended_fillers()
@@ -193,6 +541,34 @@ impl OracleManagerService {
             .on_ws(ws)
             .await?;
 
+        // catch up with the oracles added while this node was not listening
+        self.sync_oracle_registry().await?;
+
+        // logs are only handled once they are deep enough to survive a reorg
+        let database = db::get_db(&self.config).await?;
+        let log_buffer_service = LogBufferService::new(&database);
+        let confirmations = self.config.get_confirmations();
+        let mut buffer = log_buffer_service.load(confirmations).await?;
+        let mut head = provider.get_block_number().await?;
+
+        // the logs of a previous run that a reorg removed while this node was not listening
+        let mut block_hashes = HashMap::new();
+        for log in buffer.get_logs() {
+            let Some(block_number) = log.block_number else { continue };
+            let block_hash = match block_hashes.get(&block_number) {
+                Some(block_hash) => *block_hash,
+                None => {
+                    let block_hash = provider.get_block_by_number(block_number.into(), false.into()).await?.map(|block| block.header.hash);
+                    block_hashes.insert(block_number, block_hash);
+                    block_hash
+                },
+            };
+
+            if block_hash != log.block_hash {
+                self.handle_removed_log(&log_buffer_service, &mut buffer, &log).await?;
+            }
+        }
+
         // println!("Oracle Manager Contract address: {:?}", self.contract_address);
         let filter = Filter::new()
             .address(self.contract_address)
@@ -200,78 +576,302 @@ impl OracleManagerService {
 
         let sub = provider.subscribe_logs(&filter).await?;
         let mut stream = sub.into_stream();
+        let mut block_stream = provider.subscribe_blocks().await?.into_stream();
 
-        while let Some(log) = stream.next().await {
-            match log.topic0() {
-                Some(&ZKOracleManager::RequestReceived::SIGNATURE_HASH) => {
-                    let ZKOracleManager::RequestReceived { requestId } = log.log_decode()?.inner.data;
-                    println!("RequestReceived: {:?}", requestId);
-                    let contract = ZKOracleManager::new(self.contract_address, provider.clone());
+        // the subscription only brings new logs: the ones since before the last confirmed block are
+        // fetched, so that no log is missed across a restart; the ones seen twice are skipped
+        if let Some(last_confirmed_block) = log_buffer_service.get_last_confirmed_block(&self.contract_address).await? {
+            let missed_filter = filter.clone()
+                .from_block(last_confirmed_block.saturating_sub(confirmations))
+                .to_block(head);
 
-                    let request = contract.getRequestById(requestId).call().await?._0;
+            for log in provider.get_logs(&missed_filter).await? {
+                self.buffer_log(&log_buffer_service, &mut buffer, log).await?;
+            }
+        }
 
-                    println!("Handling Request: {:?}", request);
-                    self.handle_new_request(&request.into()).await?;
+        loop {
+            tokio::select! {
+                Some(log) = stream.next() => {
+                    if log.removed {
+                        self.handle_removed_log(&log_buffer_service, &mut buffer, &log).await?;
+                        continue;
+                    }
+
+                    self.buffer_log(&log_buffer_service, &mut buffer, log).await?;
                 },
-                _ => {
-                    println!("None");
-                }
+                Some(block) = block_stream.next() => {
+                    head = head.max(block.number);
+                },
+                else => break,
             }
+
+            for log in buffer.pop_confirmed(head) {
+                let request_id = self.handle_log(&log).await?;
+                buffer.mark_processed(&log, request_id.clone(), head);
+                log_buffer_service.mark_processed(&log, request_id, head).await?;
+            }
+
+            log_buffer_service.set_last_confirmed_block(&self.contract_address, head.saturating_sub(confirmations)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn buffer_log(&self, log_buffer_service: &LogBufferService, buffer: &mut ConfirmationBuffer, log: Log) -> OracleResult<()> {
+        if buffer.push(log.clone()) {
+            log_buffer_service.save_pending(&log).await?;
         }
 
         Ok(())
     }
 
-    pub async fn check_reports(&self, request_id: &str, mechanism: StatusMechanism) -> OracleResult<()> {
+    /// Handles a confirmed contract log and returns the id of the request it created, if any.
+    async fn handle_log(self: &Arc<Self>, log: &Log) -> OracleResult<Option<String>> {
+        match log.topic0() {
+            Some(&ZKOracleManager::RequestReceived::SIGNATURE_HASH) => {
+                let ZKOracleManager::RequestReceived { requestId } = log.log_decode()?.inner.data;
+                println!("RequestReceived: {:?}", requestId);
+
+                let provider = ProviderBuilder::new()
+                    .with_recommended_fillers()
+                    .wallet(self.wallet.clone())
+                    .on_http(self.config.get_solidity_http_rpc_url().parse()?);
+                let contract = ZKOracleManager::new(self.contract_address, provider);
+
+                let request: OracleRequest = contract.getRequestById(requestId).call().await?._0.into();
+
+                println!("Handling Request: {:?}", request);
+                self.queue_request(&request).await?;
+
+                // the request is handled apart, so that the next logs are confirmed meanwhile;
+                // the request queue retries it if this task fails
+                let request_id = request.request_id.to_string();
+                let manager_service = self.clone();
+                let task_request_id = request_id.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = manager_service.process_request(&task_request_id).await {
+                        println!("Error processing request {:?}: {:?}", task_request_id, e);
+                    }
+                });
+
+                Ok(Some(request_id))
+            },
+            Some(&ZKOracleManager::OracleAdded::SIGNATURE_HASH) => {
+                let ZKOracleManager::OracleAdded { oracle } = log.log_decode()?.inner.data;
+                println!("OracleAdded: {:?}", oracle);
+
+                let database = db::get_db(&self.config).await?;
+                let registry_service = OracleRegistryService::new(&database);
+                registry_service.upsert(&RegisteredOracle::from(oracle)).await?;
+                Ok(None)
+            },
+            Some(&ZKOracleManager::ResponseReceived::SIGNATURE_HASH) => {
+                let ZKOracleManager::ResponseReceived { requestId, err, .. } = log.log_decode()?.inner.data;
+                println!("ResponseReceived: {:?}", requestId);
+
+                self.handle_response_received(&requestId, &err, log.transaction_hash).await?;
+                Ok(None)
+            },
+            _ => {
+                println!("None");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Closes the lifecycle of a request fulfilled on-chain, whichever aggregator sent the fulfillment.
+    async fn handle_response_received(&self, onchain_request_id: &FixedBytes<32>, err: &Bytes, tx_hash: Option<TxHash>) -> OracleResult<()> {
         let database = db::get_db(&self.config).await?;
-        let report_service = RequestReportService::new(&database);
-        let num_reports = report_service.get_num_reports_by_request_id(request_id, mechanism).await?;
-        println!("Number of reports: {}", num_reports);
-
-        // check the number of agreements
-        let request_service = OracleRequestService::new(&database);
-        // let request = request_service.find_one(&report.request_id, mechanism).await?;
-        match request_service.find_one(request_id, mechanism).await? {
-            Some(r) => {
-                println!("Number of agreements: {}", r.num_agreements);
-
-                if r.num_agreements <= num_reports {
-                    println!("All agreements are collected. Fulfilling the request...");
-                    let reports = report_service.get_reports_by_request_id(request_id, mechanism).await?;
-                    // println!("Reports: {:?}", reports);
-
-                    // check the report validity
-
-                    // send the valid one to the contract
-                    self.send_last_status_to_contract(request_id, &reports).await?;
-                    // self.send_all_statuses_to_contract(request_id, &reports).await?;
+        let lifecycle_service = RequestLifecycleService::new(&database);
+
+        let lifecycle = match lifecycle_service.find_by_onchain_request_id(&onchain_request_id.to_string()).await? {
+            Some(lifecycle) => lifecycle,
+            None => {
+                println!("Request {:?} is not handled by this oracle", onchain_request_id);
+                return Ok(());
+            }
+        };
+
+        let tx_hash = tx_hash.map(|hash| hash.to_string()).unwrap_or_default();
+        if err.is_empty() {
+            lifecycle_service.advance(&lifecycle.request_id, RequestState::Confirmed, Some(format!("Fulfilled in {}", tx_hash))).await?;
+        } else {
+            lifecycle_service.advance(&lifecycle.request_id, RequestState::Failed, Some(format!("Failed in {}", tx_hash))).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_removed_log(&self, log_buffer_service: &LogBufferService, buffer: &mut ConfirmationBuffer, log: &Log) -> OracleResult<()> {
+        let removed_log = buffer.remove(log);
+        if !matches!(removed_log, RemovedLog::Unknown) {
+            log_buffer_service.remove(log).await?;
+        }
+
+        match removed_log {
+            RemovedLog::Pending => {
+                println!("Dropped unconfirmed log removed by a reorg: {:?}", log.transaction_hash);
+            },
+            RemovedLog::Processed(Some(request_id)) => {
+                println!("Request {:?} was removed by a reorg. Rolling it back...", request_id);
+                self.rollback_request(&request_id).await?;
+            },
+            RemovedLog::Processed(None) => {
+                if let Some(&ZKOracleManager::OracleAdded::SIGNATURE_HASH) = log.topic0() {
+                    let ZKOracleManager::OracleAdded { oracle } = log.log_decode()?.inner.data;
+                    println!("Oracle {:?} was removed by a reorg", oracle.id);
+
+                    let database = db::get_db(&self.config).await?;
+                    OracleRegistryService::new(&database).delete_by_id(oracle.id).await?;
                 }
             },
-            None => {
-                return Err(OracleError::CommonError("Request not found".to_string()));
+            RemovedLog::Unknown => {},
+        }
+
+        Ok(())
+    }
+
+    /// Forgets everything derived from a request whose log is no longer on the chain.
+    /// A fulfillment that has not been sent yet is cancelled because the request cannot be found anymore.
+    pub async fn rollback_request(&self, request_id: &str) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+
+        ReportOutboxService::new(&database, &self.settings.outbox).cancel_by_request_id(request_id).await?;
+        RequestReportService::new(&database, &self.config).delete_by_request_id(request_id).await?;
+        RequestFailureService::new(&database).delete_by_request_id(request_id).await?;
+        OracleRequestService::new(&database, &self.config).delete_by_request_id(request_id).await?;
+        RequestQueueService::new(&database, &self.settings.queue).delete_by_request_id(request_id).await?;
+        FulfillmentClaimService::new(&database).delete_by_request_id(request_id).await?;
+        RequestLifecycleService::new(&database).delete_by_request_id(request_id).await?;
+
+        Ok(())
+    }
+
+    /// Stores a report and decides, in the same transaction, whether it completes the quorum.
+    /// Only the task whose transaction moves the request to `QuorumReached` fulfills it.
+    pub async fn record_report(&self, report: &RequestReport) -> OracleResult<()> {
+        let mechanism = match report.statuses.first() {
+            Some(status) => status.status_mechanism,
+            None => return Err(OracleError::CommonError("Report must have at least one status".to_string())),
+        };
+
+        let database = db::get_db(&self.config).await?;
+        let request = match OracleRequestService::new(&database, &self.config).find_one(&report.request_id, mechanism).await? {
+            Some(r) => r,
+            // the listener has not confirmed the request yet; the sender retries
+            None => return Err(OracleError::RequestNotReadyError(report.request_id.to_string())),
+        };
+
+        let report_service = RequestReportService::new(&database, &self.config);
+        let lifecycle_service = RequestLifecycleService::new(&database);
+
+        let quorum_reached = mongo::run_transaction(database.client(), |mut session| {
+            let (report_service, lifecycle_service, request) = (&report_service, &lifecycle_service, &request);
+            async move {
+                let result = Self::record_report_in_session(report_service, lifecycle_service, request, report, &mut session).await;
+                (session, result)
             }
+        }).await?;
+
+        if quorum_reached {
+            println!("All agreements are collected. Fulfilling the request...");
+            let reports = report_service.get_reports_by_request_id(&report.request_id, mechanism).await?;
+
+            // check the report validity
+
+            // send the valid one to the contract
+            self.send_response_to_contract(&request, &reports).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn record_report_in_session(report_service: &RequestReportService, lifecycle_service: &RequestLifecycleService, request: &OracleRequest, report: &RequestReport, session: &mut ClientSession) -> OracleResult<bool> {
+        let mechanism = request.status_mechanism;
+
+        report_service.insert_or_update_in_session(report, session).await?;
+        let num_reports = report_service.get_num_reports_in_session(&report.request_id, mechanism, session).await?;
+        println!("Number of reports: {}, number of agreements: {}", num_reports, request.num_agreements);
+
+        if num_reports < request.num_agreements as u64 {
+            return Ok(false);
+        }
+
+        lifecycle_service.advance_in_session(&report.request_id, RequestState::QuorumReached, Some(format!("{} reports", num_reports)), session).await
+    }
+
+    pub async fn check_failures(&self, request_id: &str) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let request_service = OracleRequestService::new(&database, &self.config);
+        let request = match request_service.find_by_request_id(request_id).await? {
+            Some(r) => r,
+            None => return Err(OracleError::RequestNotReadyError(request_id.to_string())),
         };
 
+        let failure_service = RequestFailureService::new(&database);
+        let failures = failure_service.get_failures_by_request_id(request_id).await?;
+        println!("Number of failures: {}, number of agreements: {}", failures.len(), request.num_agreements);
+
+        if failures.len() < request.num_agreements as usize {
+            return Ok(());
+        }
+
+        if let Some(failure) = get_agreed_failure(&failures, request.num_agreements as usize) {
+            // only the first task to reach the quorum reports it
+            if !RequestLifecycleService::new(&database).advance(request_id, RequestState::QuorumReached, Some(failure.reason.clone())).await? {
+                return Ok(());
+            }
+
+            println!("A quorum of oracles cannot get the issuer data. Reporting the failure...");
+            self.send_error_to_contract(request_id, failure).await?;
+        }
+
         Ok(())
     }
 
-    pub async fn send_last_status_to_contract(&self, request_id: &str, reports: &Vec<RequestReport>) -> OracleResult<()> {
-        // let ws = WsConnect::new(self.config.get_solidity_ws_rpc_url());
-        let provider = ProviderBuilder::new()
-            .with_recommended_fillers()
-            // .with_simple_nonce_management()
-            // .with_cached_nonce_management()
-            // .with_recommended_fillers()
-            .wallet(self.wallet.clone())
-            .on_http(self.config.get_solidity_http_rpc_url().parse().unwrap());
-            // .await?;
+    /// Fulfills again the requests that reached the quorum but were not fulfilled: their claim was
+    /// released after a retryable error, or is still held by a task that never finished.
+    pub async fn retry_fulfillments(&self) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let lifecycles = RequestLifecycleService::new(&database).find_by_state(RequestState::QuorumReached).await?;
+        let claim_service = FulfillmentClaimService::new(&database);
 
-        let contract = ZKOracleManager::new(self.contract_address, provider);
+        for lifecycle in lifecycles.iter() {
+            if !claim_service.is_claimable(&lifecycle.request_id, self.settings.gas.claim_timeout).await? {
+                continue;
+            }
 
-        let last_report = reports.last().unwrap();
-        let status = last_report.statuses.last().unwrap();
+            println!("Retrying the fulfillment of request {:?}...", lifecycle.request_id);
+            if let Err(e) = self.retry_fulfillment(&lifecycle.request_id, lifecycle.status_mechanism).await {
+                println!("Error retrying the fulfillment of request {:?}: {:?}", lifecycle.request_id, e);
+            }
+        }
 
-        println!("fulfilling request_id: {:?}", request_id);
+        Ok(())
+    }
+
+    async fn retry_fulfillment(&self, request_id: &str, status_mechanism: StatusMechanism) -> OracleResult<()> {
+        let database = db::get_db(&self.config).await?;
+        let request = match OracleRequestService::new(&database, &self.config).find_one(request_id, status_mechanism).await? {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+
+        let reports = RequestReportService::new(&database, &self.config).get_reports_by_request_id(request_id, status_mechanism).await?;
+        if reports.len() >= request.num_agreements as usize {
+            return self.send_response_to_contract(&request, &reports).await;
+        }
+
+        // otherwise the quorum was reached by failures
+        let failures = RequestFailureService::new(&database).get_failures_by_request_id(request_id).await?;
+        match get_agreed_failure(&failures, request.num_agreements as usize) {
+            Some(failure) => self.send_error_to_contract(request_id, failure).await,
+            None => Ok(()),
+        }
+    }
+
+    fn parse_request_id(request_id: &str) -> OracleResult<FixedBytes<32>> {
         let re_bytes: [u8; 32];
         match hex::decode(request_id) {
             Ok(v) => match v.as_slice().try_into() {
@@ -283,38 +883,210 @@ impl OracleManagerService {
             Err(e) => return Err(OracleError::CommonError(format!("Cannot parse request_id: {:?} with err: {:?}", request_id, e))),
         };
 
-        let onchain_request_id: FixedBytes<32> = FixedBytes::from(re_bytes);
+        Ok(FixedBytes::from(re_bytes))
+    }
 
-        let response_value = DynSolValue::Tuple(vec![
-            DynSolValue::Uint(U256::from(status.time), 64),
-            DynSolValue::Uint(U256::from(status.status), 64)
-        ]);
+    /// Sends a fulfillment transaction under the gas policy and records what it cost.
+    async fn submit_fulfillment(&self, request_id: &str, response_type: Option<ResponseType>, tx: TransactionRequest, callback_gas_limit: u32, subscription_id: u64) -> OracleResult<()> {
+        // a request rolled back by a reorg must not be fulfilled
+        let database = db::get_db(&self.config).await?;
+        if OracleRequestService::new(&database, &self.config).find_by_request_id(request_id).await?.is_none() {
+            return Err(OracleError::RequestCancelledError(request_id.to_string()));
+        }
 
-        let response_bytes = Bytes::from(response_value.abi_encode());
+        // the gas limit is derived from what the requester is willing to spend on the callback
+        let transaction_service = TransactionService::new(&self.config, &self.settings.gas, &self.wallet, self.sender);
+        let gas_limit = transaction_service.get_fulfillment_gas_limit(callback_gas_limit);
+
+        let lifecycle_service = RequestLifecycleService::new(&database);
+        let sent = match transaction_service.send_with_policy(tx, gas_limit).await {
+            Ok(sent) => sent,
+            // the pending transaction may still be mined, so the claim is kept until it expires
+            Err(OracleError::TransactionStuckError(tx_hash, num_bumps)) => {
+                let e = OracleError::TransactionStuckError(tx_hash.clone(), num_bumps);
+                FulfillmentClaimService::new(&database).mark_stuck(request_id, &tx_hash, &e.to_string()).await?;
+                return Err(e);
+            },
+            // the request stays at QuorumReached and the sweeper takes its released claim again
+            Err(e) if e.is_retryable() => return Err(e),
+            Err(e) => {
+                lifecycle_service.advance(request_id, RequestState::Failed, Some(e.to_string())).await?;
+                return Err(e);
+            }
+        };
+        println!("Receipt: {:?}", sent.receipt);
 
-        println!("Calling fulfillRequestWithLastStatus...");
-        let builder = contract.fulfillRequestWithLastStatus(onchain_request_id, response_bytes.clone(), Bytes::new());
-        println!("builder: {:?}", builder);
+        let tx_hash = sent.receipt.transaction_hash.to_string();
+        let claim_service = FulfillmentClaimService::new(&database);
 
-        let receipt = builder.send().await?.get_receipt().await?;
-        println!("Receipt: {:?}", receipt);
+        // a successful fulfillment is confirmed by its ResponseReceived event
+        if sent.receipt.status() {
+            claim_service.mark_mined(request_id, ClaimStatus::Submitted, &tx_hash).await?;
+            lifecycle_service.advance(request_id, RequestState::Submitted, Some(format!("Mined in {}", tx_hash))).await?;
+        } else {
+            claim_service.mark_mined(request_id, ClaimStatus::Reverted, &tx_hash).await?;
 
-        // println!("Tx: {:?}", tx);
+            // another aggregator may have fulfilled it in the meantime
+            if !self.is_fulfilled_onchain(&Self::parse_request_id(request_id)?).await? {
+                lifecycle_service.advance(request_id, RequestState::Failed, Some(format!("Reverted in {}", tx_hash))).await?;
+            }
+        }
 
-        // let receipt = tx.get_receipt().await?;
+        let record = FulfillmentRecord {
+            id: None,
+            request_id: request_id.to_string(),
+            response_type,
+            subscription_id,
+            tx_hash,
+            block_number: sent.receipt.block_number.unwrap_or_default(),
+            gas_limit: sent.gas_limit,
+            gas_used: sent.receipt.gas_used,
+            effective_gas_price: sent.receipt.effective_gas_price.to_string(),
+            max_fee_per_gas: sent.max_fee_per_gas.to_string(),
+            max_priority_fee_per_gas: sent.max_priority_fee_per_gas.to_string(),
+            num_bumps: sent.num_bumps,
+        };
+        println!("Fulfillment record: {:?}", record);
 
-        // println!("Gas Used: {:?}", receipt.gas_used);
+        FulfillmentRecordService::new(&database).insert_one(&record).await?;
 
         Ok(())
     }
 
-    pub async fn send_all_statuses_to_contract(&self, request_id: &str, report: &Vec<RequestReport>) -> OracleResult<()> {
+    /// Index of the request in `ZKOracleManager.requests`, which is what the request id encodes.
+    fn get_request_index(onchain_request_id: &FixedBytes<32>) -> u64 {
+        let mut index_bytes = [0u8; 8];
+        index_bytes.copy_from_slice(&onchain_request_id[24..]);
+        u64::from_be_bytes(index_bytes)
+    }
+
+    /// Whether any aggregator has already fulfilled the request.
+    pub async fn is_fulfilled_onchain(&self, onchain_request_id: &FixedBytes<32>) -> OracleResult<bool> {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(self.wallet.clone())
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
+        let contract = ContractInstance::new(self.contract_address, provider, Interface::new(self.contract_artifact.abi.clone()));
+        let request_id = DynSolValue::FixedBytes(*onchain_request_id, 32);
+        let result = contract.function("fulfilled", &[request_id])?.call().await?;
+
+        match result.first().and_then(|value| value.as_bool()) {
+            Some(fulfilled) => Ok(fulfilled),
+            None => Err(OracleError::CommonError("Cannot decode the fulfilled state".to_string())),
+        }
+    }
+
+    /// Takes the right to fulfill the request, unless it is fulfilled on-chain already
+    /// or another task of this node is fulfilling it.
+    async fn claim_fulfillment(&self, request_id: &str) -> OracleResult<bool> {
+        let onchain_request_id = Self::parse_request_id(request_id)?;
+        if self.is_fulfilled_onchain(&onchain_request_id).await? {
+            println!("Request {:?} is already fulfilled on-chain. Skipping...", request_id);
+            return Ok(false);
+        }
 
+        let database = db::get_db(&self.config).await?;
+        if !FulfillmentClaimService::new(&database).claim(request_id, self.oracle.id, self.settings.gas.claim_timeout).await? {
+            println!("Request {:?} is already being fulfilled. Skipping...", request_id);
+            return Ok(false);
+        }
 
-        Ok(())
+        Ok(true)
     }
 
-    pub async fn send_all_statuses_to_contract_with_zk(&self, request_id: &str, report: &Vec<RequestReport>) -> OracleResult<()> {
-        Ok(())
+    /// Gives the claim back when nothing was mined for it. A stuck transaction keeps its claim,
+    /// since it can still be mined: the claim expires after `claim_timeout` like the one of a task that never finished.
+    async fn release_fulfillment(&self, request_id: &str, result: OracleResult<()>) -> OracleResult<()> {
+        match &result {
+            Err(OracleError::TransactionStuckError(_, _)) | Ok(_) => {},
+            Err(e) => {
+                let database = db::get_db(&self.config).await?;
+                FulfillmentClaimService::new(&database).release(request_id, &e.to_string()).await?;
+            },
+        }
+
+        result
+    }
+
+    pub async fn send_response_to_contract(&self, request: &OracleRequest, reports: &Vec<RequestReport>) -> OracleResult<()> {
+        let request_id = request.request_id.to_string();
+        if !self.claim_fulfillment(&request_id).await? {
+            return Ok(());
+        }
+
+        let result = self.submit_response(request, reports).await;
+        self.release_fulfillment(&request_id, result).await
+    }
+
+    async fn submit_response(&self, request: &OracleRequest, reports: &Vec<RequestReport>) -> OracleResult<()> {
+        // let ws = WsConnect::new(self.config.get_solidity_ws_rpc_url());
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            // .with_simple_nonce_management()
+            // .with_cached_nonce_management()
+            // .with_recommended_fillers()
+            .wallet(self.wallet.clone())
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
+            // .await?;
+
+        let contract = ZKOracleManager::new(self.contract_address, provider);
+
+        let request_id = request.request_id.to_string();
+        let last_report = match reports.last() {
+            Some(report) => report,
+            None => return Err(OracleError::InvalidReportError(format!("No reports for request {}", request_id))),
+        };
+        let status = match last_report.statuses.last() {
+            Some(status) => status,
+            None => return Err(OracleError::InvalidReportError(format!("Report of oracle {} has no status", last_report.oracle_id))),
+        };
+
+        println!("fulfilling request_id: {:?}", request_id);
+        let onchain_request_id = Self::parse_request_id(&request_id)?;
+        let response_type = self.settings.response.select(request.status_mechanism, Self::get_request_index(&onchain_request_id));
+        println!("Response type: {:?}", response_type);
+
+        let response_bytes = match response_type {
+            ResponseType::LastStatus => response::encode_last_status(status)?,
+            ResponseType::AllStatuses => response::encode_all_statuses(&last_report.statuses)?,
+            ResponseType::LastStatusWithProof => {
+                let prover_service = ProverService::new(&self.settings.prover);
+                let proof = prover_service.prove_state_transition(&request_id, request.last_status_state.time, request.last_status_state.status, &last_report.statuses).await?;
+                response::encode_last_status_with_proof(status, &proof)?
+            },
+        };
+
+        let onchain_request = contract.getRequestById(onchain_request_id).call().await?._0;
+
+        println!("Calling fulfillRequest...");
+        let tx = contract.fulfillRequest(onchain_request_id, response_type as u8, response_bytes, Bytes::new()).into_transaction_request();
+        self.submit_fulfillment(&request_id, Some(response_type), tx, onchain_request.callbackGasLimit, onchain_request.subscriptionId).await
+    }
+
+    pub async fn send_error_to_contract(&self, request_id: &str, failure: &RequestFailure) -> OracleResult<()> {
+        if !self.claim_fulfillment(request_id).await? {
+            return Ok(());
+        }
+
+        let result = self.submit_error(request_id, failure).await;
+        self.release_fulfillment(request_id, result).await
+    }
+
+    async fn submit_error(&self, request_id: &str, failure: &RequestFailure) -> OracleResult<()> {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(self.wallet.clone())
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
+
+        let contract = ZKOracleManager::new(self.contract_address, provider);
+
+        println!("failing request_id: {:?} with {:?}", request_id, failure);
+        let onchain_request_id = Self::parse_request_id(request_id)?;
+        let onchain_request = contract.getRequestById(onchain_request_id).call().await?._0;
+
+        // the response type is ignored by the contract when err is set
+        println!("Calling fulfillRequest with err...");
+        let tx = contract.fulfillRequest(onchain_request_id, 0, Bytes::new(), failure.to_err_bytes()).into_transaction_request();
+        self.submit_fulfillment(request_id, None, tx, onchain_request.callbackGasLimit, onchain_request.subscriptionId).await
     }
 }
\ No newline at end of file
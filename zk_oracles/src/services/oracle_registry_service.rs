@@ -0,0 +1,67 @@
+use alloy::primitives::Address;
+use bson::doc;
+use futures_util::TryStreamExt;
+use mongodb::{Collection, Database};
+
+use crate::{errors::OracleResult, models::registered_oracle::RegisteredOracle};
+
+const ORACLE_REGISTRY_COLLECTION_NAME: &str = "oracle_registry";
+
+/// Mirror of the on-chain oracle registry, shared by the listener and the server of a node.
+pub struct OracleRegistryService {
+    pub collection: Collection<RegisteredOracle>,
+}
+
+impl OracleRegistryService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(ORACLE_REGISTRY_COLLECTION_NAME),
+        }
+    }
+
+    pub async fn upsert(&self, oracle: &RegisteredOracle) -> OracleResult<()> {
+        let filter = doc! {
+            "id": oracle.id as i32,
+        };
+
+        self.collection.replace_one(filter, oracle).upsert(true).await?;
+        Ok(())
+    }
+
+    pub async fn upsert_many(&self, oracles: &[RegisteredOracle]) -> OracleResult<()> {
+        for oracle in oracles.iter() {
+            self.upsert(oracle).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn find_by_id(&self, oracle_id: u8) -> OracleResult<Option<RegisteredOracle>> {
+        let query = doc! {
+            "id": oracle_id as i32,
+        };
+
+        let oracle = self.collection.find_one(query).await?;
+        Ok(oracle)
+    }
+
+    pub async fn find_by_address(&self, address: &Address) -> OracleResult<Option<RegisteredOracle>> {
+        let query = doc! {
+            "oracle_address": address.to_string(),
+        };
+
+        let oracle = self.collection.find_one(query).await?;
+        Ok(oracle)
+    }
+
+    pub async fn get_all(&self) -> OracleResult<Vec<RegisteredOracle>> {
+        let cursor = self.collection.find(doc! {}).sort(doc! { "id": 1 }).await?;
+        let oracles = cursor.try_collect::<Vec<RegisteredOracle>>().await?;
+        Ok(oracles)
+    }
+
+    pub async fn delete_by_id(&self, oracle_id: u8) -> OracleResult<()> {
+        self.collection.delete_one(doc! { "id": oracle_id as i32 }).await?;
+        Ok(())
+    }
+}
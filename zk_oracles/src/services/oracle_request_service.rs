@@ -12,9 +12,7 @@ pub struct OracleRequestService {
 }
 
 impl OracleRequestService {
-    pub fn new(database: &Database) -> Self {
-        let config = Config::load_oracle_config();
-
+    pub fn new(database: &Database, config: &Config) -> Self {
         Self {
             collections: [
                 (StatusMechanism::BitStatusList, database.collection(&format!("{}_bsl", config.get_oracle_requests_collection_name()))),
@@ -23,8 +21,14 @@ impl OracleRequestService {
         }
     }
 
+    fn get_collection(&self, status_mechanism: StatusMechanism) -> OracleResult<&Collection<OracleRequest>> {
+        self.collections
+            .get(&status_mechanism)
+            .ok_or(OracleError::UnsupportedMechanismError(format!("{:?}", status_mechanism)))
+    }
+
     pub async fn is_existed(&self, request: &OracleRequest) -> OracleResult<bool> {
-        let collection = self.collections.get(&request.status_mechanism).unwrap();
+        let collection = self.get_collection(request.status_mechanism)?;
         let request_id = request.request_id.clone();
 
         let query = doc! {
@@ -44,13 +48,45 @@ impl OracleRequestService {
             return Err(OracleError::CommonError("Request already existed".to_string()));
         }
 
-        let collection = self.collections.get(&request.status_mechanism).unwrap();
+        let collection = self.get_collection(request.status_mechanism)?;
         collection.insert_one(request).await?;
         Ok(())
     }
 
+    /// Stores the request unless it is already stored, so that a request seen again after a restart is not an error.
+    pub async fn insert_if_absent(&self, request: &OracleRequest) -> OracleResult<()> {
+        let collection = self.get_collection(request.status_mechanism)?;
+
+        let update_doc = doc! {
+            "$setOnInsert": bson::to_document(request)?,
+        };
+
+        collection.update_one(doc! { "request_id": request.request_id.clone() }, update_doc).upsert(true).await?;
+        Ok(())
+    }
+
+    /// Removes the request from every mechanism collection, e.g. when its log was reorged away.
+    pub async fn delete_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        for collection in self.collections.values() {
+            collection.delete_many(doc! { "request_id": request_id }).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Looks the request up in every mechanism collection, for callers that only know its id.
+    pub async fn find_by_request_id(&self, request_id: &str) -> OracleResult<Option<OracleRequest>> {
+        for status_mechanism in self.collections.keys() {
+            if let Some(request) = self.find_one(request_id, *status_mechanism).await? {
+                return Ok(Some(request));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub async fn find_one(&self, request_id: &str, status_mechanism: StatusMechanism) -> OracleResult<Option<OracleRequest>> {
-        let collection = self.collections.get(&status_mechanism).unwrap();
+        let collection = self.get_collection(status_mechanism)?;
 
         let query = doc! {
             "request_id": doc! { "$eq": request_id }
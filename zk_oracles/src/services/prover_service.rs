@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use alloy::primitives::U256;
+use serde_json::json;
+use tokio::process::Command;
+use zkcdid_lib_rs::models::status_state::StatusState;
+
+use crate::{errors::{OracleError, OracleResult}, settings::ProverSettings};
+
+const STATE_TRANSITION_CIRCUIT_NAME: &str = "StateTransition";
+/// `NumMiddleStatuses` of the gnark `StateTransition` circuit.
+const NUM_MIDDLE_STATUSES: usize = 9;
+const PROOF_SIZE: usize = 8 * 32;
+
+/// Generates Groth16 proofs by calling the gnark circuits in `circuits-go`, like `scripts/gnark/gnark.ts` does.
+pub struct ProverService {
+    settings: ProverSettings,
+}
+
+impl ProverService {
+    pub fn new(settings: &ProverSettings) -> Self {
+        Self {
+            settings: settings.clone(),
+        }
+    }
+
+    /// Proves that `statuses` is a valid transition from the last status state `(last_time, last_status)`.
+    pub async fn prove_state_transition(&self, request_id: &str, last_time: u64, last_status: u64, statuses: &[StatusState]) -> OracleResult<[U256; 8]> {
+        let (new_status, middle_statuses) = match statuses.split_last() {
+            Some(v) => v,
+            None => return Err(OracleError::ProverError("Cannot prove an empty transition".to_string())),
+        };
+
+        if middle_statuses.len() > NUM_MIDDLE_STATUSES {
+            return Err(OracleError::ProverError(format!("Cannot prove more than {} middle statuses, got {}", NUM_MIDDLE_STATUSES, middle_statuses.len())));
+        }
+
+        // padding with the previous state keeps the transition valid
+        let mut middle_times = vec![last_time.to_string(); NUM_MIDDLE_STATUSES - middle_statuses.len()];
+        let mut middle_values = vec![last_status.to_string(); NUM_MIDDLE_STATUSES - middle_statuses.len()];
+        for status in middle_statuses.iter() {
+            middle_times.push(status.time.to_string());
+            middle_values.push(status.status.to_string());
+        }
+
+        let inputs = json!({
+            "middleTimes": middle_times,
+            "middleStatuses": middle_values,
+            "transitionTime": [last_time.to_string(), new_status.time.to_string()],
+            "transitionStatus": [last_status.to_string(), new_status.status.to_string()],
+        });
+
+        std::fs::create_dir_all(&self.settings.io_path)?;
+        let io_path = std::fs::canonicalize(&self.settings.io_path)?;
+        let input_path = io_path.join(format!("{}.json", request_id));
+        let proof_path = io_path.join(format!("{}.json.proof", request_id));
+        std::fs::write(&input_path, serde_json::to_string_pretty(&inputs)?)?;
+
+        self.run("prove", STATE_TRANSITION_CIRCUIT_NAME, &input_path, &proof_path).await?;
+
+        let proof_bytes = std::fs::read(&proof_path)?;
+        if proof_bytes.len() < PROOF_SIZE {
+            return Err(OracleError::ProverError(format!("Invalid proof size: {}", proof_bytes.len())));
+        }
+
+        let mut proof = [U256::ZERO; 8];
+        for (i, chunk) in proof_bytes[..PROOF_SIZE].chunks(32).enumerate() {
+            proof[i] = U256::from_be_slice(chunk);
+        }
+
+        Ok(proof)
+    }
+
+    async fn run(&self, command_name: &str, circuit_name: &str, input_path: &Path, proof_path: &Path) -> OracleResult<()> {
+        let mut parts = self.settings.command.split_whitespace();
+        let program = match parts.next() {
+            Some(program) => program,
+            None => return Err(OracleError::ProverError("Prover command is empty".to_string())),
+        };
+
+        println!("Running {} {} for circuit {}...", self.settings.command, command_name, circuit_name);
+        let output = Command::new(program)
+            .args(parts)
+            .arg(command_name)
+            .arg(circuit_name)
+            .arg(input_path)
+            .arg(proof_path)
+            .current_dir(&self.settings.circuits_path)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(OracleError::ProverError(String::from_utf8_lossy(&output.stderr).to_string()));
+        }
+
+        Ok(())
+    }
+}
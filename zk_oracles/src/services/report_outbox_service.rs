@@ -0,0 +1,165 @@
+use bson::{doc, oid::ObjectId, DateTime};
+use futures_util::TryStreamExt;
+use mongodb::{Collection, Database};
+
+use crate::{errors::OracleResult, models::report_delivery::{DeliveryPayload, DeliveryStatus, ReportDelivery}, settings::OutboxSettings};
+
+const REPORT_OUTBOX_COLLECTION_NAME: &str = "report_outbox";
+
+pub struct ReportOutboxService {
+    pub collection: Collection<ReportDelivery>,
+    settings: OutboxSettings,
+}
+
+impl ReportOutboxService {
+    pub fn new(database: &Database, settings: &OutboxSettings) -> Self {
+        Self {
+            collection: database.collection(REPORT_OUTBOX_COLLECTION_NAME),
+            settings: settings.clone(),
+        }
+    }
+
+    /// Stores one pending delivery per aggregator, replacing whatever was queued for the same request before.
+    pub async fn enqueue(&self, request_id: &str, aggregator_ids: &[u8], payload: &DeliveryPayload) -> OracleResult<Vec<ReportDelivery>> {
+        let mut deliveries = vec![];
+
+        for aggregator_id in aggregator_ids.iter() {
+            let mut delivery = ReportDelivery::new(request_id.to_string(), *aggregator_id, payload.clone(), (self.settings.delivery_ttl * 1000) as i64);
+
+            let filter = doc! {
+                "request_id": request_id,
+                "aggregator_id": *aggregator_id as i32,
+            };
+
+            let result = self.collection.replace_one(filter, &delivery).upsert(true).await?;
+            delivery.id = match result.upserted_id {
+                Some(id) => id.as_object_id(),
+                None => self.find_one(request_id, *aggregator_id).await?.and_then(|stored| stored.id),
+            };
+
+            deliveries.push(delivery);
+        }
+
+        Ok(deliveries)
+    }
+
+    pub async fn find_one(&self, request_id: &str, aggregator_id: u8) -> OracleResult<Option<ReportDelivery>> {
+        let query = doc! {
+            "request_id": request_id,
+            "aggregator_id": aggregator_id as i32,
+        };
+
+        let delivery = self.collection.find_one(query).await?;
+        Ok(delivery)
+    }
+
+    /// Pending deliveries whose next attempt is due.
+    pub async fn get_due_deliveries(&self) -> OracleResult<Vec<ReportDelivery>> {
+        let query = doc! {
+            "status": bson::to_bson(&DeliveryStatus::Pending)?,
+            "next_attempt_at": doc! { "$lte": DateTime::now() },
+        };
+
+        let cursor = self.collection.find(query).await?;
+        let deliveries = cursor.try_collect::<Vec<ReportDelivery>>().await?;
+        Ok(deliveries)
+    }
+
+    pub async fn get_deliveries_by_request_id(&self, request_id: &str) -> OracleResult<Vec<ReportDelivery>> {
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let cursor = self.collection.find(query).await?;
+        let deliveries = cursor.try_collect::<Vec<ReportDelivery>>().await?;
+        Ok(deliveries)
+    }
+
+    pub async fn get_deliveries_by_status(&self, status: DeliveryStatus) -> OracleResult<Vec<ReportDelivery>> {
+        let query = doc! {
+            "status": bson::to_bson(&status)?,
+        };
+
+        let cursor = self.collection.find(query).await?;
+        let deliveries = cursor.try_collect::<Vec<ReportDelivery>>().await?;
+        Ok(deliveries)
+    }
+
+    pub async fn mark_delivered(&self, id: &ObjectId) -> OracleResult<()> {
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&DeliveryStatus::Delivered)?,
+                "last_error": bson::Bson::Null,
+            },
+            "$inc": { "attempts": 1 },
+        };
+
+        self.collection.update_one(doc! { "_id": id }, update_doc).await?;
+        Ok(())
+    }
+
+    /// Schedules the next attempt with exponential backoff, or expires the delivery once its deadline has passed.
+    pub async fn mark_failed(&self, delivery: &ReportDelivery, error: &str) -> OracleResult<()> {
+        let id = match delivery.id.as_ref() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let now = DateTime::now();
+        let attempts = delivery.attempts + 1;
+        let next_attempt_at = DateTime::from_millis(now.timestamp_millis() + (self.settings.get_retry_delay(attempts) * 1000) as i64);
+        let status = if next_attempt_at > delivery.expires_at {
+            DeliveryStatus::Expired
+        } else {
+            DeliveryStatus::Pending
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&status)?,
+                "attempts": attempts as i64,
+                "last_error": error,
+                "next_attempt_at": next_attempt_at,
+            }
+        };
+
+        self.collection.update_one(doc! { "_id": id }, update_doc).await?;
+        Ok(())
+    }
+
+    /// Gives the delivery up right away, because the aggregator refused it for good.
+    pub async fn mark_rejected(&self, delivery: &ReportDelivery, error: &str) -> OracleResult<()> {
+        let id = match delivery.id.as_ref() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&DeliveryStatus::Rejected)?,
+                "last_error": error,
+            },
+            "$inc": { "attempts": 1 },
+        };
+
+        self.collection.update_one(doc! { "_id": id }, update_doc).await?;
+        Ok(())
+    }
+
+    /// Stops retrying the deliveries of a request that no longer exists on-chain.
+    pub async fn cancel_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        let filter = doc! {
+            "request_id": request_id,
+            "status": bson::to_bson(&DeliveryStatus::Pending)?,
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&DeliveryStatus::Cancelled)?,
+            }
+        };
+
+        self.collection.update_many(filter, update_doc).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use bson::doc;
+use futures_util::TryStreamExt;
+use mongodb::{Collection, Database};
+
+use crate::{errors::OracleResult, models::request_failure::RequestFailure};
+
+const REQUEST_FAILURES_COLLECTION_NAME: &str = "request_failures";
+
+pub struct RequestFailureService {
+    pub collection: Collection<RequestFailure>,
+}
+
+impl RequestFailureService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(REQUEST_FAILURES_COLLECTION_NAME),
+        }
+    }
+
+    /// Stores the failure reported by an oracle, replacing any earlier one from the same oracle.
+    pub async fn insert_or_update(&self, failure: &RequestFailure) -> OracleResult<()> {
+        let filter = doc! {
+            "request_id": failure.request_id.clone(),
+            "oracle_id": failure.oracle_id as i32,
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "code": bson::to_bson(&failure.code)?,
+                "reason": failure.reason.clone(),
+            }
+        };
+
+        self.collection.update_one(filter, update_doc).upsert(true).await?;
+        Ok(())
+    }
+
+    pub async fn get_failures_by_request_id(&self, request_id: &str) -> OracleResult<Vec<RequestFailure>> {
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let cursor = self.collection.find(query).await?;
+        let failures = cursor.try_collect::<Vec<RequestFailure>>().await?;
+        Ok(failures)
+    }
+
+    pub async fn delete_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        self.collection.delete_many(doc! { "request_id": request_id }).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use bson::{doc, DateTime, Document};
+use futures_util::TryStreamExt;
+use mongodb::{ClientSession, Collection, Database};
+
+use crate::{errors::OracleResult, models::request_lifecycle::{RequestLifecycle, RequestState, StateTransition}};
+
+const REQUEST_LIFECYCLES_COLLECTION_NAME: &str = "request_lifecycles";
+
+pub struct RequestLifecycleService {
+    pub collection: Collection<RequestLifecycle>,
+}
+
+impl RequestLifecycleService {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection(REQUEST_LIFECYCLES_COLLECTION_NAME),
+        }
+    }
+
+    /// Records a newly received request. Does nothing if its lifecycle already exists.
+    pub async fn start(&self, lifecycle: &RequestLifecycle) -> OracleResult<()> {
+        let filter = doc! {
+            "request_id": lifecycle.request_id.clone(),
+        };
+
+        let update_doc = doc! {
+            "$setOnInsert": bson::to_document(lifecycle)?,
+        };
+
+        self.collection.update_one(filter, update_doc).upsert(true).await?;
+        Ok(())
+    }
+
+    fn get_transition(&self, request_id: &str, state: RequestState, note: Option<String>) -> OracleResult<(Document, Document)> {
+        let previous_states = RequestState::get_previous_states(state)
+            .iter()
+            .map(bson::to_bson)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let filter = doc! {
+            "request_id": request_id,
+            "state": doc! { "$in": previous_states },
+        };
+
+        let now = DateTime::now();
+        let transition = StateTransition {
+            state,
+            at: now,
+            note,
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "state": bson::to_bson(&state)?,
+                "updated_at": now,
+            },
+            "$push": {
+                "history": bson::to_bson(&transition)?,
+            }
+        };
+
+        Ok((filter, update_doc))
+    }
+
+    /// Moves the request to `state` if the transition is allowed from its current state.
+    /// The check and the update are a single operation, so concurrent tasks cannot move it backwards,
+    /// and only one of them sees `true` for the same transition.
+    pub async fn advance(&self, request_id: &str, state: RequestState, note: Option<String>) -> OracleResult<bool> {
+        let (filter, update_doc) = self.get_transition(request_id, state, note)?;
+
+        let result = self.collection.update_one(filter, update_doc).await?;
+        if result.modified_count == 0 {
+            println!("Request {:?} cannot move to {:?}", request_id, state);
+        }
+
+        Ok(result.modified_count > 0)
+    }
+
+    /// Same as `advance`, as part of the session's transaction.
+    pub async fn advance_in_session(&self, request_id: &str, state: RequestState, note: Option<String>, session: &mut ClientSession) -> OracleResult<bool> {
+        let (filter, update_doc) = self.get_transition(request_id, state, note)?;
+
+        let result = self.collection.update_one(filter, update_doc).session(session).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Records that this node has sent its report or failure, and moves the request to `Reported`
+    /// unless the reports of the other oracles have already taken it further.
+    pub async fn mark_reported(&self, request_id: &str, note: Option<String>) -> OracleResult<()> {
+        let filter = doc! {
+            "request_id": request_id,
+            "reported_at": null,
+        };
+
+        self.collection.update_one(filter, doc! { "$set": { "reported_at": DateTime::now() } }).await?;
+        self.advance(request_id, RequestState::Reported, note).await?;
+        Ok(())
+    }
+
+    pub async fn find_one(&self, request_id: &str) -> OracleResult<Option<RequestLifecycle>> {
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let lifecycle = self.collection.find_one(query).await?;
+        Ok(lifecycle)
+    }
+
+    pub async fn find_by_onchain_request_id(&self, onchain_request_id: &str) -> OracleResult<Option<RequestLifecycle>> {
+        let query = doc! {
+            "onchain_request_id": doc! { "$eq": onchain_request_id }
+        };
+
+        let lifecycle = self.collection.find_one(query).await?;
+        Ok(lifecycle)
+    }
+
+    pub async fn find_by_state(&self, state: RequestState) -> OracleResult<Vec<RequestLifecycle>> {
+        let query = doc! {
+            "state": bson::to_bson(&state)?,
+        };
+
+        let cursor = self.collection.find(query).sort(doc! { "updated_at": 1 }).await?;
+        let lifecycles = cursor.try_collect::<Vec<RequestLifecycle>>().await?;
+        Ok(lifecycles)
+    }
+
+    /// Requests that are neither confirmed nor failed and have not moved for `idle_millis`.
+    pub async fn find_stuck(&self, idle_millis: i64) -> OracleResult<Vec<RequestLifecycle>> {
+        let final_states = vec![bson::to_bson(&RequestState::Confirmed)?, bson::to_bson(&RequestState::Failed)?];
+        let updated_before = DateTime::from_millis(DateTime::now().timestamp_millis() - idle_millis);
+
+        let query = doc! {
+            "state": doc! { "$nin": final_states },
+            "updated_at": doc! { "$lte": updated_before },
+        };
+
+        let cursor = self.collection.find(query).sort(doc! { "updated_at": 1 }).await?;
+        let lifecycles = cursor.try_collect::<Vec<RequestLifecycle>>().await?;
+        Ok(lifecycles)
+    }
+
+    pub async fn delete_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        self.collection.delete_many(doc! { "request_id": request_id }).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use bson::{doc, DateTime, Document};
+use futures_util::TryStreamExt;
+use mongodb::{options::ReturnDocument, Collection, Database};
+use zkcdid_lib_rs::models::status_state::StatusMechanism;
+
+use crate::{errors::OracleResult, models::request_task::{RequestTask, TaskStatus}, settings::QueueSettings};
+
+const REQUEST_QUEUE_COLLECTION_NAME: &str = "request_queue";
+
+pub struct RequestQueueService {
+    pub collection: Collection<RequestTask>,
+    settings: QueueSettings,
+}
+
+impl RequestQueueService {
+    pub fn new(database: &Database, settings: &QueueSettings) -> Self {
+        Self {
+            collection: database.collection(REQUEST_QUEUE_COLLECTION_NAME),
+            settings: settings.clone(),
+        }
+    }
+
+    /// Queues the request unless it has been queued before, whatever happened to it since.
+    pub async fn enqueue(&self, request_id: &str, status_mechanism: StatusMechanism) -> OracleResult<()> {
+        let task = RequestTask::new(request_id.to_string(), status_mechanism);
+
+        let update_doc = doc! {
+            "$setOnInsert": bson::to_document(&task)?,
+        };
+
+        self.collection.update_one(doc! { "request_id": request_id }, update_doc).upsert(true).await?;
+        Ok(())
+    }
+
+    /// Tasks that can be claimed: queued ones that are due, and processing ones whose lease has expired.
+    fn get_claimable_filter(&self) -> OracleResult<Document> {
+        let now = DateTime::now();
+
+        Ok(doc! {
+            "$or": [
+                doc! {
+                    "status": bson::to_bson(&TaskStatus::Queued)?,
+                    "next_attempt_at": doc! { "$lte": now },
+                },
+                doc! {
+                    "status": bson::to_bson(&TaskStatus::Processing)?,
+                    "lease_until": doc! { "$lte": now },
+                },
+            ]
+        })
+    }
+
+    async fn claim(&self, mut filter: Document) -> OracleResult<Option<RequestTask>> {
+        filter.extend(self.get_claimable_filter()?);
+
+        let lease_until = DateTime::from_millis(DateTime::now().timestamp_millis() + (self.settings.lease * 1000) as i64);
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&TaskStatus::Processing)?,
+                "lease_until": lease_until,
+            },
+            "$inc": { "attempts": 1 },
+        };
+
+        let task = self.collection
+            .find_one_and_update(filter, update_doc)
+            .return_document(ReturnDocument::After)
+            .await?;
+
+        Ok(task)
+    }
+
+    /// Takes the lease of the task of a request, if no one else holds it.
+    pub async fn claim_one(&self, request_id: &str) -> OracleResult<Option<RequestTask>> {
+        self.claim(doc! { "request_id": request_id }).await
+    }
+
+    /// Takes the lease of the oldest claimable task.
+    pub async fn claim_next(&self) -> OracleResult<Option<RequestTask>> {
+        self.claim(doc! {}).await
+    }
+
+    pub async fn find_one(&self, request_id: &str) -> OracleResult<Option<RequestTask>> {
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let task = self.collection.find_one(query).await?;
+        Ok(task)
+    }
+
+    pub async fn get_tasks_by_status(&self, status: TaskStatus) -> OracleResult<Vec<RequestTask>> {
+        let query = doc! {
+            "status": bson::to_bson(&status)?,
+        };
+
+        let cursor = self.collection.find(query).sort(doc! { "created_at": 1 }).await?;
+        let tasks = cursor.try_collect::<Vec<RequestTask>>().await?;
+        Ok(tasks)
+    }
+
+    pub async fn mark_done(&self, request_id: &str) -> OracleResult<()> {
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&TaskStatus::Done)?,
+                "last_error": bson::Bson::Null,
+            }
+        };
+
+        self.collection.update_one(doc! { "request_id": request_id }, update_doc).await?;
+        Ok(())
+    }
+
+    /// Releases the lease and schedules a retry with exponential backoff.
+    /// Returns `false` when the task has run out of attempts and is abandoned.
+    pub async fn mark_failed(&self, task: &RequestTask, error: &str) -> OracleResult<bool> {
+        let now = DateTime::now();
+        let next_attempt_at = DateTime::from_millis(now.timestamp_millis() + (self.settings.get_retry_delay(task.attempts) * 1000) as i64);
+        let retry = task.attempts < self.settings.max_attempts;
+        let status = if retry {
+            TaskStatus::Queued
+        } else {
+            TaskStatus::Abandoned
+        };
+
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&status)?,
+                "last_error": error,
+                "next_attempt_at": next_attempt_at,
+                "lease_until": now,
+            }
+        };
+
+        self.collection.update_one(doc! { "request_id": &task.request_id }, update_doc).await?;
+        Ok(retry)
+    }
+
+    pub async fn delete_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        self.collection.delete_many(doc! { "request_id": request_id }).await?;
+        Ok(())
+    }
+}
@@ -1,20 +1,18 @@
 use std::collections::HashMap;
 
-use bson::{doc, oid::ObjectId, Bson};
+use bson::{doc, oid::ObjectId, Bson, Document};
 use futures_util::TryStreamExt;
-use mongodb::{Collection, Database};
+use mongodb::{options::IndexOptions, ClientSession, Collection, Database, IndexModel};
 use zkcdid_lib_rs::{config::Config, models::{request_report::RequestReport, status_state::StatusMechanism}};
 
-use crate::errors::{OracleError, OracleResult};
+use crate::{errors::{OracleError, OracleResult}, utils::mongo::is_duplicate_key_error};
 
 pub struct RequestReportService {
     pub collections: HashMap<StatusMechanism, Collection<RequestReport>>,
 }
 
 impl RequestReportService {
-    pub fn new(database: &Database) -> Self {
-        let config = Config::load_oracle_config();
-
+    pub fn new(database: &Database, config: &Config) -> Self {
         Self {
             collections: [
                 (StatusMechanism::BitStatusList, database.collection(&format!("{}_bsl", config.get_reports_collection_name()))),
@@ -23,8 +21,28 @@ impl RequestReportService {
         }
     }
 
+    /// Makes sure an oracle has at most one report per request, whatever the number of concurrent writers.
+    pub async fn ensure_indexes(&self) -> OracleResult<()> {
+        for collection in self.collections.values() {
+            let index = IndexModel::builder()
+                .keys(doc! { "request_id": 1, "oracle_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+
+            collection.create_index(index).await?;
+        }
+
+        Ok(())
+    }
+
+    fn get_collection(&self, status_mechanism: StatusMechanism) -> OracleResult<&Collection<RequestReport>> {
+        self.collections
+            .get(&status_mechanism)
+            .ok_or(OracleError::UnsupportedMechanismError(format!("{:?}", status_mechanism)))
+    }
+
     pub async fn is_existed(&self, request_id: &str, oracle_id: u8, status_mechanism: StatusMechanism) -> OracleResult<bool> {
-        let collection = self.collections.get(&status_mechanism).unwrap();
+        let collection = self.get_collection(status_mechanism)?;
 
         let query = doc! {
             "$and": [
@@ -52,7 +70,7 @@ impl RequestReportService {
             return Err(OracleError::CommonError("Report already existed".to_string()));
         }
 
-        let collection = self.collections.get(&mechanism).unwrap();
+        let collection = self.get_collection(mechanism)?;
         collection.insert_one(request).await?;
         Ok(())
     }
@@ -63,7 +81,7 @@ impl RequestReportService {
         }
 
         let mechanism = request.statuses[0].status_mechanism;
-        let collection = self.collections.get(&mechanism).unwrap();
+        let collection = self.get_collection(mechanism)?;
 
         println!("Querying request report: request_id={:?}, oracle_id={:?}", request.request_id, request.oracle_id);
         let query = doc! {
@@ -98,32 +116,60 @@ impl RequestReportService {
 
         println!("Updating request report id {:?}: {:?}", find_filter, update_doc);
         let mechanism = request.statuses[0].status_mechanism;
-        let collection = self.collections.get(&mechanism).unwrap();
+        let collection = self.get_collection(mechanism)?;
 
         collection.update_one(find_filter, update_doc).await?;
         Ok(())
     }
 
-    pub async fn insert_or_update(&self, request: &RequestReport) -> OracleResult<()> {
-        let stored_report = self.find_one(request).await?;
+    fn get_upsert(&self, request: &RequestReport) -> OracleResult<(StatusMechanism, Document, Document)> {
+        if request.statuses.is_empty() {
+            return Err(OracleError::CommonError("Request must have at least one status".to_string()));
+        }
 
-        match stored_report {
-            Some(stored_report) => {
-                println!("Found stored report: {:?}", stored_report);
-                println!("Updating report...");
-                self.update_one(stored_report.id.as_ref().unwrap(), request).await?
-            },
-            None => {
-                println!("Stored report not found. Inserting new report...");
-                self.insert_one(request).await?
+        let filter = doc! {
+            "request_id": request.request_id.to_string(),
+            "oracle_id": request.oracle_id as i32,
+        };
+
+        let bson_statuses = request.statuses.iter().map(|status| bson::to_bson(&status)).collect::<Result<Vec<Bson>, _>>()?;
+
+        let update_doc = doc! {
+            "$set": {
+                "statuses": bson_statuses,
+            }
+        };
+
+        Ok((request.statuses[0].status_mechanism, filter, update_doc))
+    }
+
+    /// Inserts the report, or replaces the statuses of the one the oracle sent before, in a single write.
+    pub async fn insert_or_update(&self, request: &RequestReport) -> OracleResult<()> {
+        let (mechanism, filter, update_doc) = self.get_upsert(request)?;
+        let collection = self.get_collection(mechanism)?;
+
+        match collection.update_one(filter.clone(), update_doc.clone()).upsert(true).await {
+            Ok(_) => Ok(()),
+            // a concurrent upsert inserted the report first; this write is now a plain update
+            Err(e) if is_duplicate_key_error(&e) => {
+                collection.update_one(filter, update_doc).await?;
+                Ok(())
             },
+            Err(e) => Err(e.into()),
         }
+    }
+
+    /// Same as `insert_or_update`, as part of the session's transaction.
+    pub async fn insert_or_update_in_session(&self, request: &RequestReport, session: &mut ClientSession) -> OracleResult<()> {
+        let (mechanism, filter, update_doc) = self.get_upsert(request)?;
+        let collection = self.get_collection(mechanism)?;
 
+        collection.update_one(filter, update_doc).upsert(true).session(session).await?;
         Ok(())
     }
 
     pub async fn get_reports_by_request_id(&self, request_id: &str, mechanism: StatusMechanism) -> OracleResult<Vec<RequestReport>> {
-        let collection = self.collections.get(&mechanism).unwrap();
+        let collection = self.get_collection(mechanism)?;
 
         let query = doc! {
             "request_id": doc! { "$eq": request_id }
@@ -137,8 +183,22 @@ impl RequestReportService {
         Ok(requests)
     }
 
-    pub async fn get_num_reports_by_request_id(&self, request_id: &str, mechanism: StatusMechanism) -> OracleResult<u8> {
-        let collection = self.collections.get(&mechanism).unwrap();
+    pub async fn get_num_reports_by_request_id(&self, request_id: &str, mechanism: StatusMechanism) -> OracleResult<u64> {
+        let collection = self.get_collection(mechanism)?;
+
+        let query = doc! {
+            "request_id": doc! { "$eq": request_id }
+        };
+
+        let num_reports = collection
+            .count_documents(query)
+            .await?;
+
+        Ok(num_reports)
+    }
+
+    pub async fn get_num_reports_in_session(&self, request_id: &str, mechanism: StatusMechanism, session: &mut ClientSession) -> OracleResult<u64> {
+        let collection = self.get_collection(mechanism)?;
 
         let query = doc! {
             "request_id": doc! { "$eq": request_id }
@@ -146,8 +206,17 @@ impl RequestReportService {
 
         let num_reports = collection
             .count_documents(query)
+            .session(session)
             .await?;
 
-        Ok(num_reports as u8)
+        Ok(num_reports)
+    }
+
+    pub async fn delete_by_request_id(&self, request_id: &str) -> OracleResult<()> {
+        for collection in self.collections.values() {
+            collection.delete_many(doc! { "request_id": request_id }).await?;
+        }
+
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,220 @@
+use std::{fs::Permissions, os::unix::fs::PermissionsExt, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
+
+use alloy::{consensus::SignableTransaction, hex, network::{EthereumWallet, TxSigner}, primitives::{eip191_hash_message, keccak256, Address, PrimitiveSignature, B256}, signers::{local::PrivateKeySigner, Signer}};
+use serde::{Deserialize, Serialize};
+use tokio::{io::{AsyncBufReadExt, AsyncWriteExt, BufReader}, net::{UnixListener, UnixStream}};
+use zkcdid_lib_rs::config::Config;
+
+use crate::{errors::{OracleError, OracleResult}, settings::{SignerBackend, SignerSettings}};
+
+/// gRPC metadata holding the sender's EIP-191 signature, in hex, over the encoded message.
+pub const REPORT_SIGNATURE_METADATA_KEY: &str = "x-report-signature";
+
+/// One JSON line per call: `{"method":"address"}` or `{"method":"sign_hash","hash":"0x.."}`.
+#[derive(Serialize)]
+struct RemoteSignerRequest {
+    method: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+/// `RemoteSignerRequest` as the signer process reads it.
+#[derive(Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum RemoteSignerCall {
+    Address,
+    SignHash { hash: B256 },
+}
+
+/// One JSON line back: `{"result":"0x.."}` or `{"error":"..."}`.
+#[derive(Serialize, Deserialize)]
+struct RemoteSignerResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A signer process reached over a Unix socket, which keeps the key out of the node.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    socket_path: String,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub async fn connect(socket_path: &str) -> OracleResult<Self> {
+        let address = Self::call(socket_path, &RemoteSignerRequest { method: "address", hash: None }).await?;
+        let address = address.parse()
+            .map_err(|_| OracleError::SignerError(format!("The remote signer returned an invalid address {:?}", address)))?;
+        let signer = Self {
+            socket_path: socket_path.to_string(),
+            address,
+        };
+
+        // transactions signed with another key than the announced one would all be rejected
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let probe = keccak256(format!("zk_oracles signer probe {}", nanos));
+        let signer_address = signer.sign_hash(&probe).await?
+            .recover_address_from_prehash(&probe)
+            .map_err(|e| OracleError::SignerError(format!("Cannot recover the address of the remote signer: {}", e)))?;
+        if signer_address != address {
+            return Err(OracleError::SignerError(format!("The remote signer announces {} but signs as {}", address, signer_address)));
+        }
+
+        Ok(signer)
+    }
+
+    /// Opens a connection per call, so that a restarted signer is picked up on the next one.
+    async fn call(socket_path: &str, request: &RemoteSignerRequest) -> OracleResult<String> {
+        let stream = UnixStream::connect(socket_path).await
+            .map_err(|e| OracleError::SignerError(format!("Cannot reach the remote signer at {}: {}", socket_path, e)))?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await?;
+        let response: RemoteSignerResponse = serde_json::from_str(&line)?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(OracleError::SignerError(format!("The remote signer refused the call: {}", error))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(OracleError::SignerError("The remote signer returned nothing".to_string())),
+        }
+    }
+
+    pub async fn sign_hash(&self, hash: &B256) -> OracleResult<PrimitiveSignature> {
+        let signature = Self::call(&self.socket_path, &RemoteSignerRequest { method: "sign_hash", hash: Some(hash.to_string()) }).await?;
+        PrimitiveSignature::try_from(hex::decode(&signature)?.as_slice())
+            .map_err(|e| OracleError::SignerError(format!("The remote signer returned an invalid signature: {}", e)))
+    }
+}
+
+#[tonic::async_trait]
+impl TxSigner<PrimitiveSignature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, tx: &mut dyn SignableTransaction<PrimitiveSignature>) -> alloy::signers::Result<PrimitiveSignature> {
+        self.sign_hash(&tx.signature_hash()).await.map_err(alloy::signers::Error::other)
+    }
+}
+
+/// The key of the keystore in `keystore_path`, unlocked with the password in `password_path`.
+pub fn load_keystore(settings: &SignerSettings) -> OracleResult<PrivateKeySigner> {
+    let password = std::fs::read_to_string(&settings.password_path)
+        .map_err(|e| OracleError::SignerError(format!("Cannot read the keystore password from {}: {}", settings.password_path, e)))?;
+    PrivateKeySigner::decrypt_keystore(&settings.keystore_path, password.trim_end_matches(['\r', '\n']))
+        .map_err(|e| OracleError::SignerError(format!("Cannot unlock the keystore {}: {}", settings.keystore_path, e)))
+}
+
+/// Answers the calls of `RemoteSigner` on `socket_path`, one JSON line per call. This runs in the
+/// signer process, the only one that holds the key.
+pub async fn serve(signer: PrivateKeySigner, socket_path: &str) -> OracleResult<()> {
+    // the socket of a previous run would make bind fail
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixListener::bind(socket_path)
+        .map_err(|e| OracleError::SignerError(format!("Cannot listen on {}: {}", socket_path, e)))?;
+    std::fs::set_permissions(socket_path, Permissions::from_mode(0o600))?;
+
+    let signer = Arc::new(signer);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let signer = signer.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = answer_calls(&signer, stream).await {
+                println!("Error answering a signer call: {:?}", e);
+            }
+        });
+    }
+}
+
+async fn answer_calls(signer: &PrivateKeySigner, stream: UnixStream) -> OracleResult<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match answer_call(signer, &line).await {
+            Ok(result) => RemoteSignerResponse { result: Some(result), error: None },
+            Err(e) => RemoteSignerResponse { result: None, error: Some(e.to_string()) },
+        };
+
+        let mut line = serde_json::to_string(&response)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+async fn answer_call(signer: &PrivateKeySigner, line: &str) -> OracleResult<String> {
+    match serde_json::from_str(line)? {
+        RemoteSignerCall::Address => Ok(signer.address().to_string()),
+        RemoteSignerCall::SignHash { hash } => {
+            let signature = signer.sign_hash(&hash).await.map_err(|e| OracleError::SignerError(e.to_string()))?;
+            Ok(hex::encode(signature.as_bytes()))
+        },
+    }
+}
+
+/// Signs fulfillment transactions and reports with the oracle key, wherever it is kept.
+#[derive(Debug, Clone)]
+pub enum SignerService {
+    Local(PrivateKeySigner),
+    Remote(RemoteSigner),
+}
+
+impl SignerService {
+    pub async fn load(config: &Config, settings: &SignerSettings) -> OracleResult<Self> {
+        match settings.backend {
+            SignerBackend::Keystore => Ok(Self::Local(load_keystore(settings)?)),
+            SignerBackend::Remote => Ok(Self::Remote(RemoteSigner::connect(&settings.socket_path).await?)),
+            SignerBackend::PrivateKey => {
+                let signer = config.get_private_key().parse()
+                    .map_err(|_| OracleError::ConfigError("The private key is not a valid secp256k1 key".to_string()))?;
+                Ok(Self::Local(signer))
+            },
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            Self::Remote(signer) => signer.address,
+        }
+    }
+
+    /// The wallet that signs the node's transactions.
+    pub fn get_wallet(&self) -> EthereumWallet {
+        match self {
+            Self::Local(signer) => EthereumWallet::from(signer.clone()),
+            Self::Remote(signer) => EthereumWallet::from(signer.clone()),
+        }
+    }
+
+    /// EIP-191 signature of `message`, in hex, as it is sent along a report.
+    pub async fn sign_message(&self, message: &[u8]) -> OracleResult<String> {
+        let hash = eip191_hash_message(message);
+        let signature = match self {
+            Self::Local(signer) => signer.sign_hash(&hash).await.map_err(|e| OracleError::SignerError(e.to_string()))?,
+            Self::Remote(signer) => signer.sign_hash(&hash).await?,
+        };
+
+        Ok(hex::encode(signature.as_bytes()))
+    }
+}
+
+/// Whether `signature`, as made by `SignerService::sign_message`, was made over `message` by `signer`.
+pub fn is_signed_by(message: &[u8], signature: &str, signer: &Address) -> bool {
+    let signature = match hex::decode(signature).ok().and_then(|bytes| PrimitiveSignature::try_from(bytes.as_slice()).ok()) {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    signature.recover_address_from_msg(message).is_ok_and(|address| address == *signer)
+}
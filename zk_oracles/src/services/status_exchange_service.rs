@@ -1,18 +1,69 @@
-use crate::errors::OracleResult;
+use std::future::Future;
+
+use prost::Message;
+
+use crate::{errors::{get_error_detail, OracleError, OracleResult}, models::request_failure::RequestFailure, oracle_node::{oracle_node_service_client::OracleNodeServiceClient, FailureReport}, services::signer_service::{SignerService, REPORT_SIGNATURE_METADATA_KEY}, settings::OutboxSettings};
 use zkcdid_lib_rs::{config::Config, models::request_report::RequestReport, status_exchange::{self, status_exchange_service_client::StatusExchangeServiceClient, HelloRequest}};
 
 
 pub struct StatusExchangeService {
     config: Config,
+    settings: OutboxSettings,
+    signer: SignerService,
 }
 
 impl StatusExchangeService {
-    pub fn new() -> Self {
+    pub fn new(config: &Config, settings: &OutboxSettings, signer: &SignerService) -> Self {
         Self {
-            config: Config::load_oracle_config()
+            config: config.clone(),
+            settings: settings.clone(),
+            signer: signer.clone(),
+        }
+    }
+
+    /// Runs the call again while it fails with a code that allows it, e.g. the peer is unavailable.
+    /// Any other error, such as a rejected report, is returned right away.
+    async fn call_with_retry<T, F, Fut>(&self, url: &str, call: F) -> OracleResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = OracleResult<T>>,
+    {
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let error = match call().await {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            };
+
+            if let OracleError::RPCError(status) = &error {
+                match get_error_detail(status) {
+                    Some(detail) => println!("{} answered {:?} ({}): {}", url, status.code(), detail.reason, status.message()),
+                    None => println!("{} answered {:?}: {}", url, status.code(), status.message()),
+                }
+            }
+
+            if !error.is_retryable() || attempts >= self.settings.max_call_attempts {
+                return Err(error);
+            }
+
+            println!("Retrying the call to {} in {} ms...", url, self.settings.call_retry_delay);
+            tokio::time::sleep(tokio::time::Duration::from_millis(self.settings.call_retry_delay)).await;
         }
     }
 
+    /// Wraps `message` with this oracle's signature over its encoding, so that the receiver can tell who sent it.
+    async fn get_signed_request<T: Message + Clone>(&self, message: &T) -> OracleResult<tonic::Request<T>> {
+        let signature = self.signer.sign_message(&message.encode_to_vec()).await?;
+        let signature = signature.parse().map_err(|_| OracleError::SignerError("The signature is not valid metadata".to_string()))?;
+
+        let mut request = tonic::Request::new(message.clone());
+        request.metadata_mut().insert(REPORT_SIGNATURE_METADATA_KEY, signature);
+        Ok(request)
+    }
+
     pub async fn say_hello(&self, url: &str, name: &str) -> OracleResult<String> {
         let mut client = StatusExchangeServiceClient::connect(url.to_string()).await?;
 
@@ -26,19 +77,32 @@ impl StatusExchangeService {
     }
 
     pub async fn fulfill_request(&self, url: &str, report: &RequestReport) -> OracleResult<bool> {
-        let mut client = StatusExchangeServiceClient::connect(url.to_string()).await?;
-
-        let status_messages = report.statuses.iter().map(|status| {
+        let status_messages: Vec<_> = report.statuses.iter().map(|status| {
         ((*status).clone()).into()
         }).collect();
 
-        let request = tonic::Request::new(status_exchange::RequestFulfillment {
+        let fulfillment = status_exchange::RequestFulfillment {
             oracle_id: self.config.get_id().into(),
             request_id: report.request_id.clone(),
             statuses: status_messages,
-        });
+        };
 
-        let response = client.fulfill_request(request).await?;
-        Ok(response.into_inner().result)
+        self.call_with_retry(url, || async {
+            let request = self.get_signed_request(&fulfillment).await?;
+            let mut client = StatusExchangeServiceClient::connect(url.to_string()).await?;
+            let response = client.fulfill_request(request).await?;
+            Ok(response.into_inner().result)
+        }).await
     }
-}
\ No newline at end of file
+
+    pub async fn report_failure(&self, url: &str, failure: &RequestFailure) -> OracleResult<bool> {
+        let failure_report = FailureReport::from(failure);
+
+        self.call_with_retry(url, || async {
+            let request = self.get_signed_request(&failure_report).await?;
+            let mut client = OracleNodeServiceClient::connect(url.to_string()).await?;
+            let response = client.report_failure(request).await?;
+            Ok(response.into_inner().result)
+        }).await
+    }
+}
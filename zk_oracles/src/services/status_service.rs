@@ -1,7 +1,25 @@
 use zkcdid_lib_rs::models::{oracle_request::OracleRequest, status_state::{StatusMechanism, StatusState, StatusType}};
 use reqwest::Client;
 
-use crate::errors::{OracleError, OracleResult};
+use crate::{errors::{OracleError, OracleResult}, models::issuer_key::IssuerPublicKey};
+
+/// Query parameter of an issuer url selecting one of its status lists, see `data_apis::status_list`.
+const LIST_QUERY_PARAM: &str = "list";
+
+/// The issuer's base url and the status list a request url such as `https://issuer.example?list=3` addresses.
+/// A url without a list addresses list 0.
+pub fn get_status_list(url: &str) -> OracleResult<(String, u64)> {
+    let mut parsed_url = url::Url::parse(url)
+        .map_err(|e| OracleError::CommonError(format!("Invalid issuer url {}: {}", url, e)))?;
+    let list_id = match parsed_url.query_pairs().find(|(key, _)| key == LIST_QUERY_PARAM) {
+        Some((_, value)) => value.parse()
+            .map_err(|_| OracleError::CommonError(format!("Invalid status list {:?} in {}", value, url)))?,
+        None => 0,
+    };
+
+    parsed_url.set_query(None);
+    Ok((parsed_url.as_str().trim_end_matches('/').to_string(), list_id))
+}
 
 pub struct StatusService {
     // config: Config,
@@ -14,8 +32,8 @@ impl StatusService {
         }
     }
 
-    pub async fn get_status_from_api(&self, request: &OracleRequest) -> OracleResult<Vec<StatusState>> {
-        let domain = request.url.clone();
+    /// The statuses of list `list_id` of the issuer whose statuses are served under `domain`.
+    pub async fn get_status_from_api(&self, request: &OracleRequest, domain: &str, list_id: u64) -> OracleResult<Vec<StatusState>> {
         let status_mechanism = match request.status_mechanism {
             StatusMechanism::BitStatusList => "bsl",
             StatusMechanism::MerkleTree => "mt",
@@ -26,7 +44,7 @@ impl StatusService {
         };
 
         let last_status_time = request.last_status_state.time;
-        let url = format!("{}/statuses/{}/{}?time={}", domain, status_mechanism, status_type, last_status_time);
+        let url = format!("{}/statuses/{}/{}?time={}&{}={}", domain, status_mechanism, status_type, last_status_time, LIST_QUERY_PARAM, list_id);
 
         println!("Getting statuses url: {:?}", url);
         // let url = format!("{}/status", self.config.get_api_url());
@@ -48,4 +66,16 @@ impl StatusService {
             return Err(OracleError::CommonError("Failed to get status from API".to_string()));
         }
     }
-}
\ No newline at end of file
+
+    /// The key the issuer signs its status states with, as published on its `/issuer/key` endpoint.
+    pub async fn get_issuer_key_from_api(&self, domain: &str) -> OracleResult<IssuerPublicKey> {
+        let url = format!("{}/issuer/key", domain);
+        let response = Client::new().get(url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(OracleError::CommonError(format!("Failed to get the issuer key from {}", domain)));
+        }
+
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,162 @@
+use alloy::{network::{EthereumWallet, TransactionBuilder}, primitives::{Address, TxHash}, providers::{Provider, ProviderBuilder}, rpc::types::{TransactionReceipt, TransactionRequest}};
+use zkcdid_lib_rs::config::Config;
+
+use crate::{errors::{OracleError, OracleResult}, settings::GasSettings};
+
+/// Nodes only accept a replacement that raises both fees by at least this much, in percent (geth's price bump).
+const MIN_REPLACEMENT_BUMP_PERCENTAGE: u128 = 10;
+
+/// The fee of a replacement: `fee` raised by `percentage`, and by at least the nodes' minimum, up to `cap`.
+/// `None` when the cap leaves no room for the minimum, since the node would reject the replacement.
+fn get_replacement_fee(fee: u128, cap: u128, percentage: u128) -> Option<u128> {
+    let minimum = (fee * (100 + MIN_REPLACEMENT_BUMP_PERCENTAGE)).div_ceil(100).max(fee + 1);
+    let bumped = (fee * (100 + percentage) / 100).max(minimum).min(cap);
+    (bumped >= minimum).then_some(bumped)
+}
+
+/// A mined transaction together with the fees it was finally sent with.
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub receipt: TransactionReceipt,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub num_bumps: u32,
+}
+
+pub struct TransactionService {
+    config: Config,
+    gas: GasSettings,
+    wallet: EthereumWallet,
+    sender: Address,
+}
+
+impl TransactionService {
+    pub fn new(config: &Config, gas: &GasSettings, wallet: &EthereumWallet, sender: Address) -> Self {
+        Self {
+            config: config.clone(),
+            gas: gas.clone(),
+            wallet: wallet.clone(),
+            sender,
+        }
+    }
+
+    /// The gas limit for a fulfillment: what the requester asked for plus what the manager itself spends.
+    pub fn get_fulfillment_gas_limit(&self, callback_gas_limit: u32) -> u64 {
+        u64::from(callback_gas_limit) + self.gas.gas_limit_overhead
+    }
+
+    /// Sends `tx` with capped EIP-1559 fees and replaces it with higher fees whenever it
+    /// stays pending for more than `bump_after_blocks` blocks.
+    pub async fn send_with_policy(&self, tx: TransactionRequest, gas_limit: u64) -> OracleResult<SentTransaction> {
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(self.wallet.clone())
+            .on_http(self.config.get_solidity_http_rpc_url().parse()?);
+
+        let nonce = provider.get_transaction_count(self.sender).pending().await?;
+        let estimation = provider.estimate_eip1559_fees(None).await?;
+        let mut max_priority_fee_per_gas = estimation.max_priority_fee_per_gas.min(self.gas.max_priority_fee_per_gas);
+        let mut max_fee_per_gas = estimation.max_fee_per_gas.min(self.gas.max_fee_per_gas).max(max_priority_fee_per_gas);
+
+        let mut sent_hashes: Vec<TxHash> = vec![];
+        let mut num_bumps = 0;
+
+        loop {
+            let attempt = tx.clone()
+                .with_nonce(nonce)
+                .with_gas_limit(gas_limit)
+                .with_max_fee_per_gas(max_fee_per_gas)
+                .with_max_priority_fee_per_gas(max_priority_fee_per_gas);
+
+            println!("Sending transaction (nonce={}, max_fee_per_gas={}, max_priority_fee_per_gas={}, gas_limit={})", nonce, max_fee_per_gas, max_priority_fee_per_gas, gas_limit);
+            let send_error = match provider.send_transaction(attempt).await {
+                Ok(pending) => {
+                    sent_hashes.push(*pending.tx_hash());
+                    None
+                },
+                // e.g. "nonce too low" when a replaced transaction was mined in the meantime
+                Err(e) => Some(e),
+            };
+            let sent_at = provider.get_block_number().await?;
+
+            loop {
+                // any of the replaced transactions may have been mined instead of the latest one
+                for tx_hash in sent_hashes.iter() {
+                    if let Some(receipt) = provider.get_transaction_receipt(*tx_hash).await? {
+                        return Ok(SentTransaction {
+                            receipt,
+                            gas_limit,
+                            max_fee_per_gas,
+                            max_priority_fee_per_gas,
+                            num_bumps,
+                        });
+                    }
+                }
+
+                if let Some(e) = send_error {
+                    // an earlier attempt may still be mined, so the fulfillment is not free to be sent again
+                    return match sent_hashes.last() {
+                        Some(last_hash) => {
+                            println!("Cannot replace transaction {}: {}", last_hash, e);
+                            Err(OracleError::TransactionStuckError(last_hash.to_string(), num_bumps))
+                        },
+                        None => Err(e.into()),
+                    };
+                }
+
+                if provider.get_block_number().await? >= sent_at + self.gas.bump_after_blocks {
+                    break;
+                }
+
+                tokio::time::sleep(tokio::time::Duration::from_secs(self.gas.receipt_poll_interval)).await;
+            }
+
+            let last_hash = sent_hashes.last().map(|hash| hash.to_string()).unwrap_or_default();
+            if num_bumps >= self.gas.max_bumps {
+                return Err(OracleError::TransactionStuckError(last_hash, num_bumps));
+            }
+
+            // both fees must rise by the minimum, or the node rejects the replacement as underpriced
+            let bumped_fees = (
+                get_replacement_fee(max_fee_per_gas, self.gas.max_fee_per_gas, self.gas.bump_percentage),
+                get_replacement_fee(max_priority_fee_per_gas, self.gas.max_priority_fee_per_gas, self.gas.bump_percentage),
+            );
+            let (Some(bumped_max_fee_per_gas), Some(bumped_max_priority_fee_per_gas)) = bumped_fees else {
+                return Err(OracleError::TransactionStuckError(last_hash, num_bumps));
+            };
+            if bumped_max_priority_fee_per_gas > bumped_max_fee_per_gas {
+                return Err(OracleError::TransactionStuckError(last_hash, num_bumps));
+            }
+
+            max_fee_per_gas = bumped_max_fee_per_gas;
+            max_priority_fee_per_gas = bumped_max_priority_fee_per_gas;
+            num_bumps += 1;
+            println!("Transaction {} is still pending after {} blocks. Replacing it with higher fees...", last_hash, self.gas.bump_after_blocks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacements_raise_the_fee_by_the_bump_percentage() {
+        assert_eq!(get_replacement_fee(100, 1_000, 20), Some(120));
+    }
+
+    #[test]
+    fn replacements_raise_the_fee_by_at_least_the_nodes_minimum() {
+        assert_eq!(get_replacement_fee(100, 1_000, 5), Some(110));
+        assert_eq!(get_replacement_fee(15, 1_000, 10), Some(17));
+        assert_eq!(get_replacement_fee(0, 1_000, 10), Some(1));
+    }
+
+    #[test]
+    fn replacements_stop_at_the_cap() {
+        assert_eq!(get_replacement_fee(100, 115, 20), Some(115));
+        assert_eq!(get_replacement_fee(100, 109, 20), None);
+        assert_eq!(get_replacement_fee(100, 100, 20), None);
+    }
+}
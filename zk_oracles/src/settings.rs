@@ -0,0 +1,495 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use zkcdid_lib_rs::models::status_state::StatusMechanism;
+
+use crate::{errors::{OracleError, OracleResult}, models::response_type::ResponseType};
+
+/// Reads an environment variable and parses it, falling back to `default` when it is missing.
+fn env_or<T: FromStr>(key: &str, default: T) -> OracleResult<T> {
+    match std::env::var(key) {
+        Ok(value) => value.parse().map_err(|_| OracleError::ConfigError(format!("Invalid value {:?} for {}", value, key))),
+        Err(_) => Ok(default),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasSettings {
+    /// Upper bound for `maxFeePerGas` in wei.
+    pub max_fee_per_gas: u128,
+    /// Upper bound for `maxPriorityFeePerGas` in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Gas added on top of the request's `callbackGasLimit` to cover the manager's own execution.
+    pub gas_limit_overhead: u64,
+    /// Number of blocks a transaction may stay pending before it is replaced with higher fees.
+    pub bump_after_blocks: u64,
+    /// Fee increase (in percent) applied on each replacement.
+    pub bump_percentage: u128,
+    /// Maximum number of replacements before giving up.
+    pub max_bumps: u32,
+    /// Interval in seconds between two receipt checks.
+    pub receipt_poll_interval: u64,
+    /// Expected seconds between two blocks, to bound how long a fulfillment can stay pending.
+    pub block_time: u64,
+    /// Seconds after which a fulfillment claim held by a task that never finished can be taken over.
+    /// Must exceed `get_max_pending_time`, or a claim is taken over while its transaction is being replaced.
+    pub claim_timeout: u64,
+}
+
+impl Default for GasSettings {
+    fn default() -> Self {
+        Self {
+            max_fee_per_gas: 200_000_000_000,
+            max_priority_fee_per_gas: 5_000_000_000,
+            gas_limit_overhead: 100_000,
+            bump_after_blocks: 3,
+            bump_percentage: 20,
+            max_bumps: 5,
+            receipt_poll_interval: 1,
+            block_time: 12,
+            claim_timeout: 600,
+        }
+    }
+}
+
+impl GasSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            max_fee_per_gas: env_or("GAS_MAX_FEE_PER_GAS", self.max_fee_per_gas)?,
+            max_priority_fee_per_gas: env_or("GAS_MAX_PRIORITY_FEE_PER_GAS", self.max_priority_fee_per_gas)?,
+            gas_limit_overhead: env_or("GAS_LIMIT_OVERHEAD", self.gas_limit_overhead)?,
+            bump_after_blocks: env_or("GAS_BUMP_AFTER_BLOCKS", self.bump_after_blocks)?,
+            bump_percentage: env_or("GAS_BUMP_PERCENTAGE", self.bump_percentage)?,
+            max_bumps: env_or("GAS_MAX_BUMPS", self.max_bumps)?,
+            receipt_poll_interval: env_or("GAS_RECEIPT_POLL_INTERVAL", self.receipt_poll_interval)?,
+            block_time: env_or("GAS_BLOCK_TIME", self.block_time)?,
+            claim_timeout: env_or("GAS_CLAIM_TIMEOUT", self.claim_timeout)?,
+        })
+    }
+
+    /// The longest, in seconds, a fulfillment can be sent for: the first transaction and each replacement
+    /// wait `bump_after_blocks` blocks, plus one receipt poll, before the next one or before giving up.
+    pub fn get_max_pending_time(&self) -> u64 {
+        (u64::from(self.max_bumps) + 1) * (self.bump_after_blocks * self.block_time + self.receipt_poll_interval)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ResponsePolicy {
+    /// Always answer with the response type configured for the request's mechanism.
+    Fixed,
+    /// Cycle through every response type by request index, so that they can be compared on one deployment.
+    Rotate,
+}
+
+impl FromStr for ResponsePolicy {
+    type Err = OracleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(ResponsePolicy::Fixed),
+            "rotate" => Ok(ResponsePolicy::Rotate),
+            _ => Err(OracleError::CommonError(format!("Unknown response policy: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseSettings {
+    pub policy: ResponsePolicy,
+    pub bsl: ResponseType,
+    pub mt: ResponseType,
+}
+
+impl Default for ResponseSettings {
+    fn default() -> Self {
+        Self {
+            policy: ResponsePolicy::Fixed,
+            bsl: ResponseType::LastStatus,
+            mt: ResponseType::LastStatus,
+        }
+    }
+}
+
+impl ResponseSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            policy: env_or("RESPONSE_POLICY", self.policy)?,
+            bsl: env_or("RESPONSE_TYPE_BSL", self.bsl)?,
+            mt: env_or("RESPONSE_TYPE_MT", self.mt)?,
+        })
+    }
+
+    pub fn select(&self, status_mechanism: StatusMechanism, request_index: u64) -> ResponseType {
+        match self.policy {
+            ResponsePolicy::Fixed => match status_mechanism {
+                StatusMechanism::BitStatusList => self.bsl,
+                StatusMechanism::MerkleTree => self.mt,
+            },
+            ResponsePolicy::Rotate => ResponseType::ALL[(request_index % ResponseType::ALL.len() as u64) as usize],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProverSettings {
+    /// Directory of the gnark circuits (`circuits-go`).
+    pub circuits_path: String,
+    /// Command run inside `circuits_path`, e.g. `go run main.go` or `./zkssi`.
+    pub command: String,
+    /// Directory where circuit inputs and proofs are written.
+    pub io_path: String,
+}
+
+impl Default for ProverSettings {
+    fn default() -> Self {
+        Self {
+            circuits_path: "../circuits-go".to_string(),
+            command: "go run main.go".to_string(),
+            io_path: "../circuits-go/output".to_string(),
+        }
+    }
+}
+
+impl ProverSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            circuits_path: env_or("PROVER_CIRCUITS_PATH", self.circuits_path)?,
+            command: env_or("PROVER_COMMAND", self.command)?,
+            io_path: env_or("PROVER_IO_PATH", self.io_path)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutboxSettings {
+    /// Delay in seconds before the first retry; doubled on every failed attempt.
+    pub retry_base_delay: u64,
+    /// Upper bound in seconds for the delay between two attempts.
+    pub retry_max_delay: u64,
+    /// Seconds after which an undelivered report is given up.
+    pub delivery_ttl: u64,
+    /// Interval in seconds between two scans of the outbox.
+    pub poll_interval: u64,
+    /// Number of times one delivery attempt calls the aggregator while it answers with a retryable code.
+    pub max_call_attempts: u32,
+    /// Delay in milliseconds between two of these calls.
+    pub call_retry_delay: u64,
+}
+
+impl Default for OutboxSettings {
+    fn default() -> Self {
+        Self {
+            retry_base_delay: 2,
+            retry_max_delay: 60,
+            delivery_ttl: 600,
+            poll_interval: 5,
+            max_call_attempts: 3,
+            call_retry_delay: 500,
+        }
+    }
+}
+
+impl OutboxSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            retry_base_delay: env_or("OUTBOX_RETRY_BASE_DELAY", self.retry_base_delay)?,
+            retry_max_delay: env_or("OUTBOX_RETRY_MAX_DELAY", self.retry_max_delay)?,
+            delivery_ttl: env_or("OUTBOX_DELIVERY_TTL", self.delivery_ttl)?,
+            poll_interval: env_or("OUTBOX_POLL_INTERVAL", self.poll_interval)?,
+            max_call_attempts: env_or("OUTBOX_MAX_CALL_ATTEMPTS", self.max_call_attempts)?,
+            call_retry_delay: env_or("OUTBOX_CALL_RETRY_DELAY", self.call_retry_delay)?,
+        })
+    }
+
+    pub fn get_retry_delay(&self, attempts: u32) -> u64 {
+        let delay = self.retry_base_delay.saturating_mul(1u64 << attempts.min(32));
+        delay.min(self.retry_max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueSettings {
+    /// Seconds a node may work on a request before another worker is allowed to take it over.
+    pub lease: u64,
+    /// Delay in seconds before the first retry; doubled on every failed attempt.
+    pub retry_base_delay: u64,
+    /// Upper bound in seconds for the delay between two attempts.
+    pub retry_max_delay: u64,
+    /// Number of attempts after which a request is abandoned.
+    pub max_attempts: u32,
+    /// Interval in seconds between two scans of the queue.
+    pub poll_interval: u64,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self {
+            lease: 120,
+            retry_base_delay: 2,
+            retry_max_delay: 60,
+            max_attempts: 10,
+            poll_interval: 5,
+        }
+    }
+}
+
+impl QueueSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            lease: env_or("QUEUE_LEASE", self.lease)?,
+            retry_base_delay: env_or("QUEUE_RETRY_BASE_DELAY", self.retry_base_delay)?,
+            retry_max_delay: env_or("QUEUE_RETRY_MAX_DELAY", self.retry_max_delay)?,
+            max_attempts: env_or("QUEUE_MAX_ATTEMPTS", self.max_attempts)?,
+            poll_interval: env_or("QUEUE_POLL_INTERVAL", self.poll_interval)?,
+        })
+    }
+
+    pub fn get_retry_delay(&self, attempts: u32) -> u64 {
+        let delay = self.retry_base_delay.saturating_mul(1u64 << attempts.min(32));
+        delay.min(self.retry_max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdmissionSettings {
+    /// Maximum number of statuses in a report received from another oracle.
+    pub max_statuses: usize,
+    /// Maximum encoded size in bytes of a report received from another oracle.
+    pub max_report_bytes: usize,
+}
+
+impl Default for AdmissionSettings {
+    fn default() -> Self {
+        Self {
+            max_statuses: 256,
+            max_report_bytes: 256 * 1024,
+        }
+    }
+}
+
+impl AdmissionSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            max_statuses: env_or("ADMISSION_MAX_STATUSES", self.max_statuses)?,
+            max_report_bytes: env_or("ADMISSION_MAX_REPORT_BYTES", self.max_report_bytes)?,
+        })
+    }
+}
+
+/// Spelled the same in the config file and in `SIGNER_BACKEND`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerBackend {
+    /// An encrypted JSON keystore unlocked with the password in `password_path`.
+    Keystore,
+    /// A signer process listening on `socket_path`; the key never enters the node.
+    Remote,
+    /// The raw `private_key` of the shared configuration, for local development only.
+    PrivateKey,
+}
+
+impl FromStr for SignerBackend {
+    type Err = OracleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keystore" => Ok(SignerBackend::Keystore),
+            "remote" => Ok(SignerBackend::Remote),
+            "private_key" => Ok(SignerBackend::PrivateKey),
+            _ => Err(OracleError::CommonError(format!("Unknown signer backend: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignerSettings {
+    pub backend: SignerBackend,
+    /// Encrypted JSON keystore (Web3 Secret Storage).
+    pub keystore_path: String,
+    /// File holding the keystore password; trailing newlines are ignored.
+    pub password_path: String,
+    /// Unix socket of the remote signer.
+    pub socket_path: String,
+}
+
+impl Default for SignerSettings {
+    fn default() -> Self {
+        Self {
+            backend: SignerBackend::Keystore,
+            keystore_path: "/run/secrets/oracle/keystore.json".to_string(),
+            password_path: "/run/secrets/oracle/password".to_string(),
+            socket_path: "/run/signer/signer.sock".to_string(),
+        }
+    }
+}
+
+impl SignerSettings {
+    /// Environment variables take precedence over the config file.
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            backend: env_or("SIGNER_BACKEND", self.backend)?,
+            keystore_path: env_or("SIGNER_KEYSTORE_PATH", self.keystore_path)?,
+            password_path: env_or("SIGNER_PASSWORD_PATH", self.password_path)?,
+            socket_path: env_or("SIGNER_SOCKET_PATH", self.socket_path)?,
+        })
+    }
+}
+
+/// Scheme of the urls that `did:web` DIDs resolve to. The DID does not carry it, so an issuer
+/// served over plain http, e.g. on localhost, is only resolved with `Http`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DidWebScheme {
+    Https,
+    Http,
+}
+
+impl DidWebScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DidWebScheme::Https => "https",
+            DidWebScheme::Http => "http",
+        }
+    }
+}
+
+impl FromStr for DidWebScheme {
+    type Err = OracleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "https" => Ok(DidWebScheme::Https),
+            "http" => Ok(DidWebScheme::Http),
+            _ => Err(OracleError::CommonError(format!("Unknown did:web scheme: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DidSettings {
+    pub web_scheme: DidWebScheme,
+}
+
+impl Default for DidSettings {
+    fn default() -> Self {
+        Self {
+            web_scheme: DidWebScheme::Https,
+        }
+    }
+}
+
+impl DidSettings {
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            web_scheme: env_or("DID_WEB_SCHEME", self.web_scheme)?,
+        })
+    }
+}
+
+/// Node settings that are not part of the shared `zkcdid_lib_rs` configuration.
+/// Each one can be set in its section of the config file and overridden by its environment variable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub gas: GasSettings,
+    pub response: ResponseSettings,
+    pub prover: ProverSettings,
+    pub outbox: OutboxSettings,
+    pub queue: QueueSettings,
+    pub admission: AdmissionSettings,
+    pub signer: SignerSettings,
+    pub did: DidSettings,
+}
+
+impl Settings {
+    pub fn with_env(self) -> OracleResult<Self> {
+        Ok(Self {
+            gas: self.gas.with_env()?,
+            response: self.response.with_env()?,
+            prover: self.prover.with_env()?,
+            outbox: self.outbox.with_env()?,
+            queue: self.queue.with_env()?,
+            admission: self.admission.with_env()?,
+            signer: self.signer.with_env()?,
+            did: self.did.with_env()?,
+        })
+    }
+
+    /// Values that would not fail right away but make the node misbehave.
+    pub fn get_problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut check = |valid: bool, problem: &str| {
+            if !valid {
+                problems.push(problem.to_string());
+            }
+        };
+
+        check(self.gas.max_priority_fee_per_gas <= self.gas.max_fee_per_gas, "gas.max_priority_fee_per_gas must not exceed gas.max_fee_per_gas");
+        check(self.gas.bump_percentage > 0, "gas.bump_percentage must be positive");
+        check(self.gas.bump_after_blocks > 0, "gas.bump_after_blocks must be positive");
+        check(self.gas.receipt_poll_interval > 0, "gas.receipt_poll_interval must be positive");
+        check(self.gas.block_time > 0, "gas.block_time must be positive");
+        check(self.gas.claim_timeout > self.gas.get_max_pending_time(), "gas.claim_timeout must exceed (gas.max_bumps + 1) × (gas.bump_after_blocks × gas.block_time + gas.receipt_poll_interval)");
+        check(!self.prover.command.trim().is_empty(), "prover.command must not be empty");
+        check(self.outbox.retry_base_delay <= self.outbox.retry_max_delay, "outbox.retry_base_delay must not exceed outbox.retry_max_delay");
+        check(self.outbox.poll_interval > 0, "outbox.poll_interval must be positive");
+        check(self.outbox.max_call_attempts > 0, "outbox.max_call_attempts must be positive");
+        check(self.queue.lease > 0, "queue.lease must be positive");
+        check(self.queue.retry_base_delay <= self.queue.retry_max_delay, "queue.retry_base_delay must not exceed queue.retry_max_delay");
+        check(self.queue.max_attempts > 0, "queue.max_attempts must be positive");
+        check(self.queue.poll_interval > 0, "queue.poll_interval must be positive");
+        check(self.admission.max_statuses > 0, "admission.max_statuses must be positive");
+        check(self.admission.max_report_bytes > 0, "admission.max_report_bytes must be positive");
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signer_backends_are_spelled_the_same_in_the_file_and_the_environment() {
+        for backend in [SignerBackend::Keystore, SignerBackend::Remote, SignerBackend::PrivateKey] {
+            let name = serde_json::to_string(&backend).unwrap();
+            assert_eq!(name.trim_matches('"').parse::<SignerBackend>().unwrap(), backend);
+        }
+    }
+
+    #[test]
+    fn did_web_schemes_are_spelled_the_same_in_the_file_and_the_environment() {
+        for scheme in [DidWebScheme::Https, DidWebScheme::Http] {
+            let name = serde_json::to_string(&scheme).unwrap();
+            assert_eq!(name.trim_matches('"'), scheme.as_str());
+            assert_eq!(scheme.as_str().parse::<DidWebScheme>().unwrap(), scheme);
+        }
+        assert!("ftp".parse::<DidWebScheme>().is_err());
+    }
+
+    #[test]
+    fn claims_outlast_the_replacements_of_their_transaction() {
+        let settings = Settings::default();
+        assert_eq!(settings.gas.get_max_pending_time(), 6 * (3 * 12 + 1));
+        assert!(settings.get_problems().iter().all(|problem| !problem.contains("claim_timeout")));
+
+        let gas = GasSettings { max_bumps: 20, ..GasSettings::default() };
+        let settings = Settings { gas, ..Settings::default() };
+        assert!(settings.get_problems().iter().any(|problem| problem.contains("claim_timeout")));
+    }
+}
@@ -0,0 +1,14 @@
+use zk_oracles::{config::NodeConfig, errors::OracleResult, services::signer_service::{load_keystore, serve}};
+
+/// Holds the oracle key for the nodes with `backend = "remote"`: unlocks the keystore of the
+/// `[signer]` settings and signs on their `socket_path`.
+#[tokio::main]
+async fn main() -> OracleResult<()> {
+    let settings = NodeConfig::load_settings()?.signer;
+    let signer = load_keystore(&settings)?;
+
+    println!("Signing as {} on {}...", signer.address(), settings.socket_path);
+    serve(signer, &settings.socket_path).await?;
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use alloy::rpc::types::Log;
+
+/// How long processed logs are remembered, in blocks, so that a deeper reorg can still be rolled back.
+pub const PROCESSED_LOG_RETENTION: u64 = 128;
+
+/// Position of a log in the chain: (block number, log index).
+type LogKey = (u64, u64);
+
+pub enum RemovedLog {
+    /// The log was still waiting for confirmations and has simply been dropped.
+    Pending,
+    /// The log had already been handled; carries the id of the local request created for it, if any.
+    Processed(Option<String>),
+    /// The log was never seen by this buffer.
+    Unknown,
+}
+
+/// Holds contract logs until they are `confirmations` blocks deep.
+pub struct ConfirmationBuffer {
+    confirmations: u64,
+    pending: BTreeMap<LogKey, Log>,
+    processed: BTreeMap<LogKey, (Log, Option<String>)>,
+}
+
+impl ConfirmationBuffer {
+    pub fn new(confirmations: u64) -> Self {
+        Self {
+            confirmations,
+            pending: BTreeMap::new(),
+            processed: BTreeMap::new(),
+        }
+    }
+
+    fn get_key(log: &Log) -> Option<LogKey> {
+        Some((log.block_number?, log.log_index?))
+    }
+
+    /// Buffers a new log and returns whether it was new. Logs without a position (still pending
+    /// in the mempool) are ignored, and so are the ones seen again when resuming from an older block.
+    pub fn push(&mut self, log: Log) -> bool {
+        let key = match Self::get_key(&log) {
+            Some(key) => key,
+            None => return false,
+        };
+
+        let is_known = |known: &Log| known.block_hash == log.block_hash;
+        if self.pending.get(&key).is_some_and(is_known) || self.processed.get(&key).is_some_and(|(known, _)| is_known(known)) {
+            return false;
+        }
+
+        self.pending.insert(key, log);
+        true
+    }
+
+    /// Puts back a log handled before a restart.
+    pub fn restore_processed(&mut self, log: Log, request_id: Option<String>) {
+        if let Some(key) = Self::get_key(&log) {
+            self.processed.insert(key, (log, request_id));
+        }
+    }
+
+    /// Every pending and processed log, to check them against the chain after a restart.
+    pub fn get_logs(&self) -> Vec<Log> {
+        self.pending.values().chain(self.processed.values().map(|(log, _)| log)).cloned().collect()
+    }
+
+    /// Drops a log that a reorg removed from the chain.
+    pub fn remove(&mut self, log: &Log) -> RemovedLog {
+        let key = match Self::get_key(log) {
+            Some(key) => key,
+            None => return RemovedLog::Unknown,
+        };
+
+        if let Some(pending_log) = self.pending.get(&key) {
+            if pending_log.block_hash == log.block_hash {
+                self.pending.remove(&key);
+                return RemovedLog::Pending;
+            }
+        }
+
+        if self.processed.get(&key).is_some_and(|(processed_log, _)| processed_log.block_hash == log.block_hash) {
+            if let Some((_, request_id)) = self.processed.remove(&key) {
+                return RemovedLog::Processed(request_id);
+            }
+        }
+
+        RemovedLog::Unknown
+    }
+
+    /// Takes the logs that are deep enough at `head`, oldest first.
+    pub fn pop_confirmed(&mut self, head: u64) -> Vec<Log> {
+        let confirmed_keys: Vec<LogKey> = self.pending.keys()
+            .take_while(|(block_number, _)| block_number + self.confirmations <= head)
+            .cloned()
+            .collect();
+
+        confirmed_keys.iter().filter_map(|key| self.pending.remove(key)).collect()
+    }
+
+    /// Remembers a handled log so that its effects can be rolled back if it is removed later.
+    pub fn mark_processed(&mut self, log: &Log, request_id: Option<String>, head: u64) {
+        if let Some(key) = Self::get_key(log) {
+            self.processed.insert(key, (log.clone(), request_id));
+        }
+
+        let oldest_block = head.saturating_sub(PROCESSED_LOG_RETENTION);
+        self.processed.retain(|(block_number, _), _| *block_number >= oldest_block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+
+    fn log(block_number: u64, log_index: u64, block_hash: u8) -> Log {
+        Log {
+            block_hash: Some(B256::repeat_byte(block_hash)),
+            block_number: Some(block_number),
+            log_index: Some(log_index),
+            ..Default::default()
+        }
+    }
+
+    fn positions(logs: &[Log]) -> Vec<(u64, u64)> {
+        logs.iter().map(|log| (log.block_number.unwrap(), log.log_index.unwrap())).collect()
+    }
+
+    #[test]
+    fn logs_are_confirmed_once_deep_enough_and_in_order() {
+        let mut buffer = ConfirmationBuffer::new(2);
+        buffer.push(log(11, 0, 2));
+        buffer.push(log(10, 1, 1));
+        buffer.push(log(10, 0, 1));
+
+        assert!(buffer.pop_confirmed(11).is_empty());
+        assert_eq!(positions(&buffer.pop_confirmed(12)), vec![(10, 0), (10, 1)]);
+        assert_eq!(positions(&buffer.pop_confirmed(13)), vec![(11, 0)]);
+        assert!(buffer.pop_confirmed(20).is_empty());
+    }
+
+    #[test]
+    fn logs_without_a_position_are_ignored() {
+        let mut buffer = ConfirmationBuffer::new(0);
+
+        assert!(!buffer.push(Log::default()));
+        assert!(buffer.pop_confirmed(100).is_empty());
+    }
+
+    #[test]
+    fn logs_seen_again_are_skipped() {
+        let mut buffer = ConfirmationBuffer::new(1);
+        assert!(buffer.push(log(10, 0, 1)));
+        assert!(!buffer.push(log(10, 0, 1)));
+
+        let confirmed = buffer.pop_confirmed(11);
+        buffer.mark_processed(&confirmed[0], Some("request".to_string()), 11);
+        assert!(!buffer.push(log(10, 0, 1)));
+
+        // the same position in another block is a new log
+        assert!(buffer.push(log(10, 0, 2)));
+    }
+
+    #[test]
+    fn removed_pending_logs_are_dropped() {
+        let mut buffer = ConfirmationBuffer::new(2);
+        buffer.push(log(10, 0, 1));
+
+        assert!(matches!(buffer.remove(&log(10, 0, 1)), RemovedLog::Pending));
+        assert!(buffer.pop_confirmed(20).is_empty());
+    }
+
+    #[test]
+    fn removed_processed_logs_carry_their_request() {
+        let mut buffer = ConfirmationBuffer::new(0);
+        buffer.push(log(10, 0, 1));
+        let confirmed = buffer.pop_confirmed(10);
+        buffer.mark_processed(&confirmed[0], Some("request".to_string()), 10);
+
+        // a log of another block at the same position was never processed
+        assert!(matches!(buffer.remove(&log(10, 0, 2)), RemovedLog::Unknown));
+        assert!(matches!(buffer.remove(&log(10, 0, 1)), RemovedLog::Processed(Some(id)) if id == "request"));
+        assert!(matches!(buffer.remove(&log(10, 0, 1)), RemovedLog::Unknown));
+    }
+
+    #[test]
+    fn processed_logs_are_forgotten_after_the_retention() {
+        let mut buffer = ConfirmationBuffer::new(0);
+        buffer.restore_processed(log(10, 0, 1), None);
+        buffer.mark_processed(&log(200, 0, 3), None, 200);
+
+        assert_eq!(positions(&buffer.get_logs()), vec![(200, 0)]);
+        assert!(matches!(buffer.remove(&log(10, 0, 1)), RemovedLog::Unknown));
+    }
+
+    #[test]
+    fn restored_logs_are_returned_for_checking() {
+        let mut buffer = ConfirmationBuffer::new(2);
+        buffer.push(log(12, 0, 2));
+        buffer.restore_processed(log(10, 0, 1), Some("request".to_string()));
+
+        assert_eq!(positions(&buffer.get_logs()), vec![(12, 0), (10, 0)]);
+        assert!(matches!(buffer.remove(&log(10, 0, 1)), RemovedLog::Processed(Some(_))));
+    }
+}
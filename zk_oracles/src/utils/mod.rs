@@ -1 +1,5 @@
-pub mod solidity;
\ No newline at end of file
+pub mod solidity;
+pub mod response;
+pub mod confirmation_buffer;
+pub mod mongo;
+pub mod status_signature;
@@ -0,0 +1,83 @@
+use std::{future::Future, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use mongodb::{error::{Error, ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT}, Client, ClientSession};
+
+use crate::errors::{OracleError, OracleResult};
+
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// How many times a transaction is run again after transient errors.
+pub const MAX_TRANSACTION_ATTEMPTS: u32 = 5;
+/// Upper bound of the random pause before the first retry of a transaction, in milliseconds.
+/// Each further retry waits up to this much longer.
+const MAX_RETRY_DELAY: u64 = 50;
+
+/// Whether the write was rejected by a unique index.
+pub fn is_duplicate_key_error(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => write_error.code == DUPLICATE_KEY_CODE,
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY_CODE,
+        _ => false,
+    }
+}
+
+/// Whether the whole transaction can be run again, e.g. after a write conflict with a concurrent one.
+pub fn is_transient_transaction_error(error: &Error) -> bool {
+    error.contains_label(TRANSIENT_TRANSACTION_ERROR)
+}
+
+/// Whether the commit may or may not have been applied, in which case only the commit is retried.
+pub fn is_unknown_commit_result(error: &Error) -> bool {
+    error.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+}
+
+/// Commits the transaction, retrying the commit alone while its outcome is unknown.
+async fn commit(session: &mut ClientSession) -> Result<(), Error> {
+    loop {
+        match session.commit_transaction().await {
+            Err(e) if is_unknown_commit_result(&e) => continue,
+            result => return result,
+        }
+    }
+}
+
+/// Runs `attempt` in a transaction, and again after a transient error until `MAX_TRANSACTION_ATTEMPTS`.
+/// The attempt gets the session and hands it back with its result, so that it can borrow its other inputs.
+pub async fn run_transaction<T, F, Fut>(client: &Client, mut attempt: F) -> OracleResult<T>
+where
+    F: FnMut(ClientSession) -> Fut,
+    Fut: Future<Output = (ClientSession, OracleResult<T>)>,
+{
+    let mut session = client.start_session().await?;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        session.start_transaction().await?;
+
+        let (returned, result) = attempt(session).await;
+        session = returned;
+        let error = match result {
+            Ok(value) => match commit(&mut session).await {
+                Ok(()) => return Ok(value),
+                Err(e) => OracleError::from(e),
+            },
+            Err(e) => {
+                // a transaction left open would fail the next start_transaction
+                let _ = session.abort_transaction().await;
+                e
+            },
+        };
+
+        match error {
+            OracleError::DatabaseError(e) if is_transient_transaction_error(&e) && attempts < MAX_TRANSACTION_ATTEMPTS => {
+                // a random pause growing with the attempts, so that conflicting tasks do not collide again at once
+                let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos() as u64;
+                let delay = nanos % MAX_RETRY_DELAY * attempts as u64;
+                println!("Transaction conflict on attempt {}. Retrying in {}ms...", attempts, delay);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            },
+            e => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use alloy::{dyn_abi::DynSolValue, primitives::{Bytes, U256}};
+use zkcdid_lib_rs::models::status_state::{StatusMechanism, StatusState};
+
+use crate::errors::{OracleError, OracleResult};
+
+fn check_status_mechanism(status: &StatusState) -> OracleResult<()> {
+    match status.status_mechanism {
+        StatusMechanism::BitStatusList => Ok(()),
+        status_mechanism => Err(OracleError::UnsupportedMechanismError(format!("{:?}", status_mechanism))),
+    }
+}
+
+fn bsl_status_value(status: &StatusState) -> DynSolValue {
+    DynSolValue::Tuple(vec![
+        DynSolValue::Uint(U256::from(status.time), 64),
+        DynSolValue::Uint(U256::from(status.status), 64)
+    ])
+}
+
+/// `ResponseType.LastStatus`: decoded by `StatusState.decodeBSLStatus`.
+pub fn encode_last_status(status: &StatusState) -> OracleResult<Bytes> {
+    check_status_mechanism(status)?;
+    Ok(Bytes::from(bsl_status_value(status).abi_encode()))
+}
+
+/// `ResponseType.AllStatuses`: decoded by `StatusState.decodeBSLStatuses`.
+pub fn encode_all_statuses(statuses: &[StatusState]) -> OracleResult<Bytes> {
+    let mut values = vec![];
+    for status in statuses.iter() {
+        check_status_mechanism(status)?;
+        values.push(bsl_status_value(status));
+    }
+
+    Ok(Bytes::from(DynSolValue::Array(values).abi_encode()))
+}
+
+/// `ResponseType.LastStatusWithProof`: decoded as `(uint256[8] proof, uint64[2] publicInputs)`.
+pub fn encode_last_status_with_proof(status: &StatusState, proof: &[U256; 8]) -> OracleResult<Bytes> {
+    check_status_mechanism(status)?;
+
+    let response_value = DynSolValue::Tuple(vec![
+        DynSolValue::FixedArray(proof.iter().map(|value| DynSolValue::Uint(*value, 256)).collect()),
+        DynSolValue::FixedArray(vec![
+            DynSolValue::Uint(U256::from(status.time), 64),
+            DynSolValue::Uint(U256::from(status.status), 64),
+        ]),
+    ]);
+
+    Ok(Bytes::from(response_value.abi_encode_params()))
+}
+
+#[cfg(test)]
+mod tests {
+    use zkcdid_lib_rs::models::status_state::StatusType;
+
+    use super::*;
+
+    fn bsl_status(time: u64, status: u64) -> StatusState {
+        let mut state = StatusState::get_initial_status(StatusMechanism::BitStatusList, StatusType::Issuance);
+        state.time = time;
+        state.status = status;
+        state
+    }
+
+    fn word(value: u64) -> [u8; 32] {
+        U256::from(value).to_be_bytes::<32>()
+    }
+
+    #[test]
+    fn last_status_is_a_time_and_status_tuple() {
+        let encoded = encode_last_status(&bsl_status(7, 0b101)).unwrap();
+
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(encoded[..32], word(7));
+        assert_eq!(encoded[32..], word(0b101));
+    }
+
+    #[test]
+    fn all_statuses_is_a_dynamic_array_of_tuples() {
+        let encoded = encode_all_statuses(&[bsl_status(1, 1), bsl_status(2, 3)]).unwrap();
+
+        // offset of the array, its length, then one (time, status) pair per status
+        assert_eq!(encoded.len(), 32 * 6);
+        assert_eq!(encoded[..32], word(32));
+        assert_eq!(encoded[32..64], word(2));
+        assert_eq!(encoded[64..96], word(1));
+        assert_eq!(encoded[96..128], word(1));
+        assert_eq!(encoded[128..160], word(2));
+        assert_eq!(encoded[160..], word(3));
+    }
+
+    #[test]
+    fn last_status_with_proof_puts_the_proof_before_the_public_inputs() {
+        let proof: [U256; 8] = std::array::from_fn(|i| U256::from(100 + i));
+        let encoded = encode_last_status_with_proof(&bsl_status(9, 0b11), &proof).unwrap();
+
+        assert_eq!(encoded.len(), 32 * 10);
+        for (i, chunk) in encoded.chunks(32).take(8).enumerate() {
+            assert_eq!(chunk, word(100 + i as u64));
+        }
+        assert_eq!(encoded[256..288], word(9));
+        assert_eq!(encoded[288..], word(0b11));
+    }
+
+    #[test]
+    fn other_mechanisms_are_rejected() {
+        let status = StatusState::get_initial_status(StatusMechanism::MerkleTree, StatusType::Issuance);
+
+        assert!(matches!(encode_last_status(&status), Err(OracleError::UnsupportedMechanismError(_))));
+        assert!(matches!(encode_all_statuses(&[bsl_status(1, 1), status.clone()]), Err(OracleError::UnsupportedMechanismError(_))));
+        assert!(matches!(encode_last_status_with_proof(&status, &[U256::ZERO; 8]), Err(OracleError::UnsupportedMechanismError(_))));
+    }
+}
@@ -29,8 +29,7 @@ pub fn get_solidity_deployment_info(config: &Config) -> OracleResult<SolidityDep
     Ok(deployments)
 }
 
-pub fn get_solidity_artifact(contract_name: &str) -> OracleResult<Artifact> {
-    let config = Config::load_oracle_config();
+pub fn get_solidity_artifact(config: &Config, contract_name: &str) -> OracleResult<Artifact> {
     let path = Path::new(&config.get_solidity_artifacts_path()).join(format!("{contract_name}.json"));
     let artifact_file_content = std::fs::read_to_string(path)?;
     let artifact: Artifact = serde_json::from_str(&artifact_file_content)?;